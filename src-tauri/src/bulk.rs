@@ -0,0 +1,226 @@
+use std::collections::HashSet;
+
+use crate::models::{AppError, ImportEntryError, ParsedImport, ServerIdentity};
+use crate::subscription::decode_body;
+use crate::uri::{parse_vless_uri, url_decode};
+
+/// Schemes `parse_vless_uri` understands.
+const SCHEMES: [&str; 7] = [
+    "vless",
+    "vmess",
+    "trojan",
+    "ss",
+    "hysteria2",
+    "hy2",
+    "wireguard",
+];
+
+/// Left off the end of a link: sentence punctuation and closing brackets.
+const TRAILING_PUNCTUATION: [char; 8] = [',', '.', ';', '!', '?', ')', ']', '}'];
+
+/// Closing brackets paired with their opening ones.
+const BRACKETS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+/// Shortest whitespace-free token worth trying as a base64 subscription body.
+const MIN_BASE64_LEN: usize = 16;
+
+/// Every share link in `text`, in order of appearance and without exact
+/// repeats ([`parse_share_text`] also drops links to the same server).
+/// Links are found anywhere (inside chat messages, quotes, markdown), and
+/// base64 blobs that decode to links, such as a pasted subscription body,
+/// are expanded in place.
+pub fn extract_share_links(text: &str) -> Vec<String> {
+    // A subscription body wrapped over several lines only decodes as a whole
+    // (its lines on their own would decode to fragments of links).
+    let mut links = match decode_body(text) {
+        Ok(decoded) if !text.contains("://") => links_in(&decoded),
+        _ => Vec::new(),
+    };
+    if links.is_empty() {
+        for token in text.split_whitespace() {
+            if token.contains("://") {
+                links.extend(links_in(token));
+            } else if token.len() >= MIN_BASE64_LEN {
+                if let Ok(decoded) = decode_body(token) {
+                    links.extend(links_in(&decoded));
+                }
+            }
+        }
+    }
+
+    let mut unique = Vec::with_capacity(links.len());
+    for link in links {
+        if !unique.contains(&link) {
+            unique.push(link);
+        }
+    }
+    unique
+}
+
+/// The share links in a piece of text with no whitespace left to split on
+/// (a token, or a decoded body): each starts at a known scheme and ends
+/// before a quote, bracket or trailing punctuation.
+fn links_in(text: &str) -> Vec<String> {
+    let mut links = Vec::new();
+    for line in text.split_whitespace() {
+        let mut rest = line;
+        while let Some(pos) = rest.find("://") {
+            let scheme_start = rest[..pos]
+                .rfind(|c: char| !(c.is_ascii_alphanumeric() || "+.-".contains(c)))
+                .map_or(0, |i| i + 1);
+            let scheme = rest[scheme_start..pos].to_ascii_lowercase();
+            let body = &rest[pos + 3..];
+            let end = body.find(['"', '\'', '<', '>', '`']).unwrap_or(body.len());
+            if SCHEMES.contains(&scheme.as_str()) {
+                let link = trim_link_end(&body[..end]);
+                if !link.is_empty() {
+                    links.push(format!("{scheme}://{link}"));
+                }
+            }
+            rest = &body[end..];
+        }
+    }
+    links
+}
+
+/// Drop the sentence punctuation and closing brackets that end the text
+/// around a link rather than the link itself. An unencoded `#name` may end
+/// in them legitimately, so there only commas, semicolons and unmatched
+/// closing brackets go.
+fn trim_link_end(link: &str) -> &str {
+    let Some(hash) = link.find('#') else {
+        return link.trim_end_matches(TRAILING_PUNCTUATION);
+    };
+    let mut name = &link[hash + 1..];
+    while let Some(last) = name.chars().last() {
+        let trim = match last {
+            ',' | ';' => true,
+            _ => BRACKETS.iter().any(|&(open, close)| {
+                last == close && name.matches(close).count() > name.matches(open).count()
+            }),
+        };
+        if !trim {
+            break;
+        }
+        name = &name[..name.len() - last.len_utf8()];
+    }
+    &link[..hash + 1 + name.len()]
+}
+
+/// Parse and validate every share link found in `text` without saving
+/// anything. Links that fail are reported in `errors` (indexed by their
+/// position among the links found, named after their `#fragment`). A link
+/// to a server already found (same [`ServerIdentity`], whatever its name or
+/// parameter order) is dropped.
+pub fn parse_share_text(text: &str) -> Result<ParsedImport, AppError> {
+    let links = extract_share_links(text);
+    if links.is_empty() {
+        return Err(AppError::Config(
+            "No share links found in the text".to_string(),
+        ));
+    }
+    let mut parsed = ParsedImport::default();
+    let mut seen = HashSet::new();
+    for (index, link) in links.iter().enumerate() {
+        let server = parse_vless_uri(link)
+            .map_err(|e| e.to_string())
+            .and_then(|server| {
+                server.validate()?;
+                Ok(server)
+            });
+        match server {
+            Ok(server) => {
+                if seen.insert(ServerIdentity::of(&server)) {
+                    parsed.servers.push(server);
+                }
+            }
+            Err(error) => parsed.errors.push(ImportEntryError {
+                index,
+                name: link
+                    .split_once('#')
+                    .map(|(_, name)| url_decode(name))
+                    .unwrap_or_default(),
+                error,
+            }),
+        }
+    }
+    Ok(parsed)
+}
+
+#[tauri::command]
+pub fn parse_share_text_cmd(text: String) -> Result<ParsedImport, String> {
+    parse_share_text(&text).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+
+    const VLESS: &str = "vless://aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee@1.2.3.4:443?encryption=none&security=reality&sni=www.microsoft.com&fp=chrome&pbk=abc123&sid=def456&type=tcp#Finland";
+    const TROJAN: &str = "trojan://secret@trojan.example.com:443?sni=trojan.example.com#Trojan";
+
+    #[test]
+    fn extract_from_chat_message() {
+        let text = format!(
+            "New servers!\n1) {VLESS}, and \"{TROJAN}\".\nOld one: <{VLESS}>\nsee https://example.com/faq or [link](VLESS://x@y:1#bad)"
+        );
+        let links = extract_share_links(&text);
+        assert_eq!(links, vec![VLESS, TROJAN, "vless://x@y:1#bad"]);
+    }
+
+    #[test]
+    fn extract_keeps_punctuation_of_names() {
+        let text = "Try vless://x@y:1#DE(fast). Or (vless://x@y:2#Node.) or vless://x@y:3?sni=a.b.";
+        assert_eq!(
+            extract_share_links(text),
+            vec![
+                "vless://x@y:1#DE(fast).",
+                "vless://x@y:2#Node.",
+                "vless://x@y:3?sni=a.b"
+            ]
+        );
+    }
+
+    #[test]
+    fn extract_from_base64_body() {
+        let body = STANDARD.encode(format!("{VLESS}\n{TROJAN}\n"));
+        assert_eq!(extract_share_links(&body), vec![VLESS, TROJAN]);
+
+        // Wrapped at 76 columns like `base64` output.
+        let wrapped: Vec<String> = body
+            .as_bytes()
+            .chunks(76)
+            .map(|c| String::from_utf8(c.to_vec()).unwrap())
+            .collect();
+        assert_eq!(
+            extract_share_links(&wrapped.join("\n")),
+            vec![VLESS, TROJAN]
+        );
+
+        // A body pasted next to a plain link.
+        let mixed = format!("{TROJAN}\n{}", STANDARD.encode(VLESS));
+        assert_eq!(extract_share_links(&mixed), vec![TROJAN, VLESS]);
+    }
+
+    #[test]
+    fn parse_reports_bad_links() {
+        let text = format!("{VLESS}\nvless://nope@1.2.3.4:443#Broken%20one\n{VLESS}");
+        let parsed = parse_share_text(&text).unwrap();
+        assert_eq!(parsed.servers.len(), 1);
+        assert_eq!(parsed.servers[0].name, "Finland");
+        assert_eq!(parsed.errors.len(), 1);
+        assert_eq!(parsed.errors[0].index, 1);
+        assert_eq!(parsed.errors[0].name, "Broken one");
+
+        // The same server under another name and parameter order.
+        let reordered = "vless://aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee@1.2.3.4:443?type=tcp&security=reality&encryption=none&sni=www.microsoft.com&fp=chrome&pbk=abc123&sid=def456#Helsinki";
+        let parsed = parse_share_text(&format!("{VLESS}\n{reordered}")).unwrap();
+        assert_eq!(parsed.servers.len(), 1);
+        assert_eq!(parsed.servers[0].name, "Finland");
+
+        let err = parse_share_text("hello there").unwrap_err().to_string();
+        assert!(err.contains("No share links"), "unexpected error: {err}");
+    }
+}
@@ -0,0 +1,314 @@
+use serde_json::Value;
+
+use crate::models::{
+    AppError, ImportEntryError, ParsedImport, RealitySettings, ServerConfig, TlsSettings,
+};
+
+/// Read the `proxies:` list of a Clash / Mihomo YAML profile. VLESS entries
+/// (REALITY or TLS, over tcp, ws, grpc or xhttp) become servers; every other
+/// entry is reported in `errors` instead of failing the whole file.
+pub fn parse_clash_yaml(content: &str) -> Result<ParsedImport, AppError> {
+    let profile: Value = serde_yaml_ng::from_str(content)
+        .map_err(|e| AppError::Config(format!("Invalid YAML: {e}")))?;
+    let proxies = profile
+        .get("proxies")
+        .and_then(Value::as_array)
+        .ok_or_else(|| AppError::Config("Profile has no 'proxies' list".to_string()))?;
+
+    let mut parsed = ParsedImport::default();
+    for (index, proxy) in proxies.iter().enumerate() {
+        match proxy_to_server(proxy) {
+            Ok(server) => parsed.servers.push(server),
+            Err(error) => parsed.errors.push(ImportEntryError {
+                index,
+                name: field(proxy, "name"),
+                error,
+            }),
+        }
+    }
+    Ok(parsed)
+}
+
+/// A scalar field as a string; Clash files mix quoted and bare numbers.
+fn field(value: &Value, key: &str) -> String {
+    match value.get(key) {
+        Some(Value::String(s)) => s.trim().to_string(),
+        Some(Value::Number(n)) => n.to_string(),
+        Some(Value::Bool(b)) => b.to_string(),
+        _ => String::new(),
+    }
+}
+
+fn flag(value: &Value, key: &str) -> bool {
+    matches!(field(value, key).as_str(), "true" | "1")
+}
+
+fn proxy_to_server(proxy: &Value) -> Result<ServerConfig, String> {
+    if !proxy.is_object() {
+        return Err("Entry is not a mapping".to_string());
+    }
+    let kind = field(proxy, "type");
+    if kind != "vless" {
+        return Err(format!("Unsupported proxy type '{kind}'"));
+    }
+
+    let address = field(proxy, "server");
+    if address.is_empty() {
+        return Err("Missing server".to_string());
+    }
+    let port_str = field(proxy, "port");
+    let port: u16 = port_str
+        .parse()
+        .map_err(|_| format!("Invalid port: {port_str}"))?;
+
+    let mut server = ServerConfig {
+        name: field(proxy, "name"),
+        address,
+        port,
+        uuid: field(proxy, "uuid"),
+        flow: field(proxy, "flow"),
+        ..ServerConfig::default()
+    };
+    if server.name.is_empty() {
+        server.name = server.address.clone();
+    }
+    let encryption = field(proxy, "encryption");
+    if !encryption.is_empty() {
+        server.vless_encryption = encryption;
+    }
+
+    let empty = Value::Null;
+    let reality = proxy.get("reality-opts").unwrap_or(&empty);
+    server.security = if reality.is_object() {
+        server.reality = RealitySettings {
+            public_key: field(reality, "public-key"),
+            short_id: field(reality, "short-id"),
+            ..RealitySettings::default()
+        };
+        "reality".to_string()
+    } else if flag(proxy, "tls") {
+        "tls".to_string()
+    } else {
+        return Err("VLESS without TLS or REALITY is not supported".to_string());
+    };
+
+    let mut host = String::new();
+    server.network = match field(proxy, "network").as_str() {
+        "" | "tcp" => "tcp".to_string(),
+        "ws" => {
+            let opts = proxy.get("ws-opts").unwrap_or(&empty);
+            server.ws_path = field(opts, "path");
+            host = opts
+                .get("headers")
+                .map(|h| field(h, "Host"))
+                .unwrap_or_default();
+            server.ws_host = host.clone();
+            // xray only does early data through the Sec-WebSocket-Protocol
+            // header, which is also Mihomo's default.
+            let header = field(opts, "early-data-header-name");
+            if header.is_empty() || header.eq_ignore_ascii_case("Sec-WebSocket-Protocol") {
+                server.ws_early_data = field(opts, "max-early-data").parse().unwrap_or(0);
+            }
+            "ws".to_string()
+        }
+        "grpc" => {
+            let opts = proxy.get("grpc-opts").unwrap_or(&empty);
+            server.grpc_service_name = field(opts, "grpc-service-name");
+            "grpc".to_string()
+        }
+        "xhttp" => {
+            let opts = proxy.get("xhttp-opts").unwrap_or(&empty);
+            server.xhttp_path = field(opts, "path");
+            host = field(opts, "host");
+            let mode = field(opts, "mode");
+            if !mode.is_empty() {
+                server.xhttp_mode = mode;
+            }
+            "xhttp".to_string()
+        }
+        other => return Err(format!("Unsupported transport '{other}'")),
+    };
+    // XTLS-Vision only runs over raw TCP.
+    if server.network != "tcp" {
+        server.flow.clear();
+    }
+
+    let mut sni = field(proxy, "servername");
+    // REALITY borrows the certificate of the site it names; a Host header
+    // is no stand-in for it.
+    if server.security == "reality" && sni.is_empty() {
+        return Err("REALITY needs a servername".to_string());
+    }
+    if sni.is_empty() {
+        sni = host;
+    }
+    let fingerprint = field(proxy, "client-fingerprint");
+    server.tls = TlsSettings {
+        server_name: sni,
+        alpn: proxy
+            .get("alpn")
+            .and_then(Value::as_array)
+            .map(|list| {
+                list.iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        allow_insecure: flag(proxy, "skip-cert-verify"),
+        ..TlsSettings::default()
+    };
+    if !fingerprint.is_empty() {
+        server.tls.fingerprint = fingerprint;
+    }
+
+    server.validate()?;
+    Ok(server)
+}
+
+#[tauri::command]
+pub fn parse_clash_yaml_cmd(content: String) -> Result<ParsedImport, String> {
+    parse_clash_yaml(&content).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROFILE: &str = r#"
+mixed-port: 7890
+proxies:
+  - name: "reality"
+    type: vless
+    server: 1.2.3.4
+    port: 443
+    uuid: aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee
+    network: tcp
+    tls: true
+    udp: true
+    flow: xtls-rprx-vision
+    servername: www.microsoft.com
+    client-fingerprint: firefox
+    reality-opts:
+      public-key: abc123
+      short-id: def456
+  - name: cdn-ws
+    type: vless
+    server: cdn.example.com
+    port: "8443"
+    uuid: aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee
+    tls: true
+    network: ws
+    alpn: [http/1.1]
+    ws-opts:
+      path: /ray
+      headers:
+        Host: edge.example.com
+      max-early-data: 2048
+  - name: xhttp
+    type: vless
+    server: 5.6.7.8
+    port: 443
+    uuid: aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee
+    network: xhttp
+    tls: true
+    flow: xtls-rprx-vision
+    servername: sni.example.com
+    reality-opts:
+      public-key: abc123
+      short-id: "01"
+    xhttp-opts:
+      path: /xh
+      mode: stream-one
+  - name: ss
+    type: ss
+    server: 9.9.9.9
+    port: 8388
+    cipher: aes-256-gcm
+    password: secret
+  - name: plain
+    type: vless
+    server: 9.9.9.9
+    port: 80
+    uuid: aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee
+proxy-groups: []
+"#;
+
+    #[test]
+    fn parse_profile_with_report() {
+        let parsed = parse_clash_yaml(PROFILE).unwrap();
+        assert_eq!(parsed.servers.len(), 3);
+
+        let reality = &parsed.servers[0];
+        assert_eq!(reality.name, "reality");
+        assert_eq!(reality.security, "reality");
+        assert_eq!(reality.flow, "xtls-rprx-vision");
+        assert_eq!(reality.reality.public_key, "abc123");
+        assert_eq!(reality.reality.short_id, "def456");
+        assert_eq!(reality.tls.server_name, "www.microsoft.com");
+        assert_eq!(reality.tls.fingerprint, "firefox");
+
+        let ws = &parsed.servers[1];
+        assert_eq!(ws.port, 8443);
+        assert_eq!(ws.security, "tls");
+        assert_eq!(ws.network, "ws");
+        assert_eq!(ws.ws_path, "/ray");
+        assert_eq!(ws.ws_host, "edge.example.com");
+        assert_eq!(ws.ws_early_data, 2048);
+        assert_eq!(ws.tls.server_name, "edge.example.com");
+        assert_eq!(ws.tls.alpn, vec!["http/1.1"]);
+
+        let xhttp = &parsed.servers[2];
+        assert_eq!(xhttp.network, "xhttp");
+        assert_eq!(xhttp.xhttp_path, "/xh");
+        assert_eq!(xhttp.xhttp_mode, "stream-one");
+        assert!(xhttp.flow.is_empty());
+
+        assert_eq!(parsed.errors.len(), 2);
+        assert_eq!(parsed.errors[0].index, 3);
+        assert_eq!(parsed.errors[0].name, "ss");
+        assert!(parsed.errors[0].error.contains("'ss'"));
+        assert_eq!(parsed.errors[1].name, "plain");
+        assert!(parsed.errors[1].error.contains("REALITY"));
+    }
+
+    #[test]
+    fn parse_rejects_reality_without_servername() {
+        let yaml = r#"
+proxies:
+  - name: no-sni
+    type: vless
+    server: 1.2.3.4
+    port: 443
+    uuid: aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee
+    network: xhttp
+    tls: true
+    reality-opts:
+      public-key: abc123
+    xhttp-opts:
+      host: cdn.example.com
+"#;
+        let parsed = parse_clash_yaml(yaml).unwrap();
+        assert!(parsed.servers.is_empty());
+        assert_eq!(parsed.errors.len(), 1);
+        assert_eq!(parsed.errors[0].name, "no-sni");
+        assert!(parsed.errors[0].error.contains("servername"));
+    }
+
+    #[test]
+    fn parse_rejects_non_profiles() {
+        assert!(parse_clash_yaml("proxies: [").is_err());
+        let err = parse_clash_yaml("port: 7890\n").unwrap_err().to_string();
+        assert!(err.contains("proxies"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn invalid_entry_is_reported() {
+        let yaml = "proxies:\n  - {name: bad, type: vless, server: 1.2.3.4, port: 443, uuid: nope, tls: true, servername: a.com}\n  - just-a-string\n";
+        let parsed = parse_clash_yaml(yaml).unwrap();
+        assert!(parsed.servers.is_empty());
+        assert!(parsed.errors[0].error.contains("UUID"));
+        assert_eq!(parsed.errors[1].index, 1);
+        assert!(parsed.errors[1].name.is_empty());
+    }
+}
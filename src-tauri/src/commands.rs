@@ -1,11 +1,13 @@
 use tauri::{AppHandle, Runtime, State};
 
 use crate::models::{
-    AppSettings, ConnectionInfo, ConnectionStatus, DetectedVpn, LogEntry, ServerConfig, SpeedStats,
+    self, AppSettings, ConnectionInfo, ConnectionStatus, DetectedVpn, ImportReport, LogEntry,
+    MergeStrategy, ServerConfig, SpeedStats, Subscription, SubscriptionQuota,
 };
 #[cfg(desktop)]
 use crate::network;
 use crate::storage;
+use crate::subscription;
 use crate::xray::XrayManager;
 
 #[tauri::command]
@@ -120,6 +122,12 @@ pub fn delete_server<R: Runtime>(app: AppHandle<R>, id: String) -> Result<(), St
     if servers.len() == len_before {
         return Err(format!("Server with id {id} not found"));
     }
+    // Servers chained through the deleted one fall back to connecting directly.
+    for server in &mut servers {
+        if server.via.as_deref() == Some(id.as_str()) {
+            server.via = None;
+        }
+    }
     storage::save_servers(&app, &servers).map_err(|e| e.to_string())
 }
 
@@ -129,25 +137,113 @@ pub fn export_servers<R: Runtime>(app: AppHandle<R>) -> Result<String, String> {
     serde_json::to_string_pretty(&servers).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub fn find_import_duplicates<R: Runtime>(
+    app: AppHandle<R>,
+    json: String,
+) -> Result<Vec<String>, String> {
+    let imported = models::servers_from_json(&json).map_err(|e| format!("Invalid JSON: {e}"))?;
+    let servers = storage::load_servers(&app).map_err(|e| e.to_string())?;
+    Ok(models::find_duplicates(&servers, &imported))
+}
+
 #[tauri::command]
 pub fn import_servers<R: Runtime>(
     app: AppHandle<R>,
     json: String,
-) -> Result<Vec<ServerConfig>, String> {
-    let imported: Vec<ServerConfig> =
-        serde_json::from_str(&json).map_err(|e| format!("Invalid JSON: {e}"))?;
+    strategy: Option<MergeStrategy>,
+) -> Result<ImportReport, String> {
+    let imported = models::servers_from_json(&json).map_err(|e| format!("Invalid JSON: {e}"))?;
     let mut servers = storage::load_servers(&app).map_err(|e| e.to_string())?;
-    // Assign fresh ids to imported servers to avoid collisions
-    let new_servers: Vec<ServerConfig> = imported
-        .into_iter()
-        .map(|mut s| {
-            s.id = uuid::Uuid::new_v4().to_string();
-            s
-        })
-        .collect();
-    servers.extend(new_servers.clone());
+    let report = models::merge_import(&mut servers, imported, strategy.unwrap_or_default());
     storage::save_servers(&app, &servers).map_err(|e| e.to_string())?;
-    Ok(new_servers)
+    Ok(report)
+}
+
+// Subscriptions
+#[tauri::command]
+pub fn get_subscriptions<R: Runtime>(app: AppHandle<R>) -> Result<Vec<Subscription>, String> {
+    storage::load_subscriptions(&app).map_err(|e| e.to_string())
+}
+
+/// Save a new subscription. Its servers arrive with the first
+/// `refresh_subscription` call.
+#[tauri::command]
+pub fn add_subscription<R: Runtime>(
+    app: AppHandle<R>,
+    subscription: Subscription,
+) -> Result<Subscription, String> {
+    subscription.validate()?;
+    let mut subscriptions = storage::load_subscriptions(&app).map_err(|e| e.to_string())?;
+    let mut new_subscription = subscription;
+    new_subscription.id = uuid::Uuid::new_v4().to_string();
+    new_subscription.last_updated = None;
+    new_subscription.usage = None;
+    subscriptions.push(new_subscription.clone());
+    storage::save_subscriptions(&app, &subscriptions).map_err(|e| e.to_string())?;
+    Ok(new_subscription)
+}
+
+#[tauri::command]
+pub fn update_subscription<R: Runtime>(
+    app: AppHandle<R>,
+    subscription: Subscription,
+) -> Result<(), String> {
+    subscription.validate()?;
+    let mut subscriptions = storage::load_subscriptions(&app).map_err(|e| e.to_string())?;
+    let pos = subscriptions
+        .iter()
+        .position(|s| s.id == subscription.id)
+        .ok_or_else(|| format!("Subscription with id {} not found", subscription.id))?;
+    subscriptions[pos] = subscription;
+    storage::save_subscriptions(&app, &subscriptions).map_err(|e| e.to_string())
+}
+
+/// Delete a subscription together with the servers it provided.
+#[tauri::command]
+pub fn delete_subscription<R: Runtime>(app: AppHandle<R>, id: String) -> Result<(), String> {
+    let mut subscriptions = storage::load_subscriptions(&app).map_err(|e| e.to_string())?;
+    let len_before = subscriptions.len();
+    subscriptions.retain(|s| s.id != id);
+    if subscriptions.len() == len_before {
+        return Err(format!("Subscription with id {id} not found"));
+    }
+    let mut servers = storage::load_servers(&app).map_err(|e| e.to_string())?;
+    subscription::merge_servers(&mut servers, &id, Vec::new());
+    storage::save_servers(&app, &servers).map_err(|e| e.to_string())?;
+    storage::save_subscriptions(&app, &subscriptions).map_err(|e| e.to_string())
+}
+
+/// Traffic left and expiry of a subscription, as its provider last reported
+/// them. `None` if the provider doesn't send `subscription-userinfo`.
+#[tauri::command]
+pub fn get_subscription_quota<R: Runtime>(
+    app: AppHandle<R>,
+    id: String,
+) -> Result<Option<SubscriptionQuota>, String> {
+    let subscriptions = storage::load_subscriptions(&app).map_err(|e| e.to_string())?;
+    let subscription = subscriptions
+        .iter()
+        .find(|s| s.id == id)
+        .ok_or_else(|| format!("Subscription with id {id} not found"))?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    Ok(subscription.quota(now))
+}
+
+/// Fetch a subscription now and return its servers. Runs off the main thread
+/// since the provider may take a while to answer.
+#[tauri::command]
+pub async fn refresh_subscription<R: Runtime>(
+    app: AppHandle<R>,
+    id: String,
+) -> Result<Vec<ServerConfig>, String> {
+    tauri::async_runtime::spawn_blocking(move || subscription::refresh(&app, &id))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -291,7 +387,7 @@ pub fn detect_vpn_interfaces() -> Result<Vec<DetectedVpn>, String> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::RealitySettings;
+    use crate::models::{RealitySettings, TlsSettings};
 
     fn valid_config() -> ServerConfig {
         ServerConfig {
@@ -304,13 +400,18 @@ mod tests {
             reality: RealitySettings {
                 public_key: "abc123".to_string(),
                 short_id: "def456".to_string(),
+                ..RealitySettings::default()
+            },
+            tls: TlsSettings {
                 server_name: "example.com".to_string(),
                 fingerprint: "chrome".to_string(),
+                ..TlsSettings::default()
             },
             network: "tcp".to_string(),
             xhttp_path: String::new(),
             security: "reality".to_string(),
             xhttp_mode: "auto".to_string(),
+            ..ServerConfig::default()
         }
     }
 
@@ -1,9 +1,38 @@
+use std::net::IpAddr;
+
 use serde_json::{json, Value};
 
-use crate::models::{AppError, ServerConfig};
+use crate::models::{outbound_endpoint, AppError, ServerConfig, TlsSettings, NON_PROXY_OUTBOUNDS};
+use crate::uri::format_host;
 
 pub const STATS_API_ADDR: &str = "127.0.0.1:10085";
 
+/// Tag of the first hop when a server is chained through another one.
+const VIA_TAG: &str = "proxy-via";
+
+/// Tag of the freedom outbound that fragments / pads the first hop's packets.
+const FRAGMENT_TAG: &str = "fragment";
+
+/// Tag of the IPv6 counterpart of "direct" in TUN mode.
+const DIRECT6_TAG: &str = "direct6";
+
+/// Local addresses xray binds its own connections to in TUN mode, so that
+/// `ip rule from <addr> lookup main` sends them out the physical interface
+/// instead of back into the TUN. `ipv4` set means TUN mode; `ipv6` is set
+/// when the host has a global IPv6 address.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SendThrough<'a> {
+    pub ipv4: Option<&'a str>,
+    pub ipv6: Option<&'a str>,
+    /// IPv6 is blackholed rather than tunnelled ("block IPv6"): there is no
+    /// direct IPv6 outbound, and `ipv6` only sources the dial to an IPv6
+    /// entry server.
+    pub block_ipv6: bool,
+    /// The first hop's server is reached over IPv6. Resolved by the caller,
+    /// since the address may be a hostname with only AAAA records.
+    pub entry_ipv6: bool,
+}
+
 pub fn generate_client_config(
     server: &ServerConfig,
     socks_port: u16,
@@ -12,6 +41,39 @@ pub fn generate_client_config(
     send_through: Option<&str>,
     vpn_dns_servers: &[String],
 ) -> Result<String, AppError> {
+    generate_chained_client_config(
+        server,
+        None,
+        socks_port,
+        bypass_domains,
+        bypass_subnets,
+        SendThrough {
+            ipv4: send_through,
+            ..SendThrough::default()
+        },
+        vpn_dns_servers,
+    )
+}
+
+/// Like [`generate_client_config`], but when `via` is set the "proxy"
+/// outbound dials `server` through a second "proxy-via" outbound for `via`
+/// (xray's `sockopt.dialerProxy`), so the exit server only ever sees the
+/// entry server's address.
+pub fn generate_chained_client_config(
+    server: &ServerConfig,
+    via: Option<&ServerConfig>,
+    socks_port: u16,
+    bypass_domains: &[String],
+    bypass_subnets: &[String],
+    send_through: SendThrough,
+    vpn_dns_servers: &[String],
+) -> Result<String, AppError> {
+    // The IPv6 address only matters in TUN mode, i.e. alongside the IPv4 one.
+    let local_ip6 = send_through.ipv4.and(send_through.ipv6);
+    let direct_ip6 = local_ip6.filter(|_| !send_through.block_ipv6);
+    let entry_ipv6 = send_through.entry_ipv6;
+    let send_through = send_through.ipv4;
+
     // In TUN mode, skip localhost DNS entirely. The system resolver calls getaddrinfo()
     // which goes through /etc/resolv.conf — corporate VPNs push their own DNS server
     // there, and in TUN mode that DNS traffic may be unroutable, causing a 30-second
@@ -52,18 +114,6 @@ pub fn generate_client_config(
         vec![json!("localhost"), json!("1.1.1.1"), json!("8.8.8.8")]
     };
 
-    // REALITY settings are identical across transports; only the stream wrapper differs.
-    let reality_settings = json!({
-        "show": false,
-        "fingerprint": server.reality.fingerprint,
-        "serverName": server.reality.server_name,
-        "publicKey": server.reality.public_key,
-        "shortId": server.reality.short_id
-    });
-
-    let is_xhttp = server.network == "xhttp";
-    let is_tls = server.security == "tls";
-
     // TCP keepalive so xray detects a server connection that has silently gone
     // dead (e.g. after an ISP daily session/IP reset) instead of hanging on a
     // black-holed socket forever — the main cause of "connected but 0 KB/s".
@@ -72,70 +122,40 @@ pub fn generate_client_config(
         "tcpKeepAliveInterval": 15
     });
 
-    // XHTTP disguises the tunnel as ordinary HTTP, which survives the TCP
-    // session-freezing that DPI applies to raw-TCP REALITY. XTLS-Vision flow
-    // only works over raw TCP, so XHTTP must connect with an empty flow.
-    let (stream_settings, user_flow): (Value, &str) = if is_xhttp {
-        let path = if server.xhttp_path.trim().is_empty() {
-            "/"
-        } else {
-            server.xhttp_path.trim()
-        };
-        let mode = if server.xhttp_mode.trim().is_empty() {
-            "auto"
-        } else {
-            server.xhttp_mode.trim()
-        };
-        if is_tls {
-            // xHTTP + real TLS for CDN fronting (Cloudflare): the DPI sees the
-            // CDN's whitelisted IPs instead of the throttled origin. REALITY is
-            // impossible here because the CDN terminates TLS. serverName / Host
-            // is the CDN domain (stored in reality.server_name); alpn h2 matches
-            // Cloudflare's HTTP/2 edge.
-            let cdn_host = server.reality.server_name.as_str();
-            let tls_settings = json!({
-                "serverName": cdn_host,
-                "alpn": ["h2"],
-                "fingerprint": server.reality.fingerprint
-            });
-            (
-                json!({
-                    "network": "xhttp",
-                    "security": "tls",
-                    "tlsSettings": tls_settings,
-                    "xhttpSettings": {
-                        "path": path,
-                        "mode": mode,
-                        "host": cdn_host
-                    },
-                    "sockopt": sockopt
-                }),
-                "",
-            )
-        } else {
-            (
-                json!({
-                    "network": "xhttp",
-                    "security": "reality",
-                    "realitySettings": reality_settings,
-                    "xhttpSettings": { "path": path, "mode": mode },
-                    "sockopt": sockopt
-                }),
-                "",
-            )
+    let mut proxy_outbound = build_outbound(server, sockopt.clone())?;
+    let mut outbounds = Vec::new();
+    let mut via_outbound = match via {
+        Some(via) => {
+            let mut via_outbound = build_outbound(via, sockopt.clone())?;
+            via_outbound["tag"] = json!(VIA_TAG);
+            dial_through(&mut proxy_outbound, VIA_TAG);
+            Some(via_outbound)
         }
-    } else {
-        (
-            json!({
-                "network": "tcp",
-                "security": "reality",
-                "realitySettings": reality_settings,
-                "sockopt": sockopt
-            }),
-            server.flow.as_str(),
-        )
+        None => None,
     };
 
+    // Only the entry hop's handshake is visible on the wire, so its
+    // fragment/noise settings are the ones that apply.
+    let entry = via.unwrap_or(server);
+    let fragment = fragment_outbound(entry, sockopt);
+    if fragment.is_some() {
+        dial_through(
+            via_outbound.as_mut().unwrap_or(&mut proxy_outbound),
+            FRAGMENT_TAG,
+        );
+    }
+    outbounds.push(proxy_outbound);
+    outbounds.extend(via_outbound);
+    outbounds.extend(fragment);
+    outbounds.push(json!({
+        "tag": "direct",
+        "protocol": "freedom"
+    }));
+    outbounds.push(json!({
+        "tag": "block",
+        "protocol": "blackhole"
+    }));
+
     let mut config: Value = json!({
         "log": {
             "loglevel": "info"
@@ -180,36 +200,7 @@ pub fn generate_client_config(
                 }
             }
         ],
-        "outbounds": [
-            {
-                "tag": "proxy",
-                "protocol": "vless",
-                "settings": {
-                    "vnext": [
-                        {
-                            "address": server.address,
-                            "port": server.port,
-                            "users": [
-                                {
-                                    "id": server.uuid,
-                                    "flow": user_flow,
-                                    "encryption": "none"
-                                }
-                            ]
-                        }
-                    ]
-                },
-                "streamSettings": stream_settings
-            },
-            {
-                "tag": "direct",
-                "protocol": "freedom"
-            },
-            {
-                "tag": "block",
-                "protocol": "blackhole"
-            }
-        ],
+        "outbounds": outbounds,
         "routing": {
             "domainStrategy": "IPIfNonMatch",
             "rules": []
@@ -219,17 +210,45 @@ pub fn generate_client_config(
     // On Linux with TUN mode, bind outbound connections to the physical interface IP
     // so that `ip rule add from <local_ip> lookup main` routes them through the
     // physical interface, bypassing the TUN default route and preventing loops.
+    // Only the outbound that opens the socket needs it: the fragment outbound
+    // if there is one, else the first hop of a chain (the exit rides inside it).
+    let first_hop = if entry.fragment.is_enabled() || !entry.noises.is_empty() {
+        FRAGMENT_TAG
+    } else if via.is_some() {
+        VIA_TAG
+    } else {
+        "proxy"
+    };
     if let Some(local_ip) = send_through {
         if let Some(outbounds) = config.get_mut("outbounds").and_then(|o| o.as_array_mut()) {
             for outbound in outbounds.iter_mut() {
                 let tag = outbound.get("tag").and_then(|t| t.as_str()).unwrap_or("");
-                if tag == "proxy" || tag == "direct" {
-                    if let Some(obj) = outbound.as_object_mut() {
-                        obj.insert("sendThrough".to_string(), json!(local_ip));
-                    }
+                // An IPv6 entry server has to be dialled from an IPv6 source;
+                // without one, leave the choice to the kernel rather than
+                // bind a socket that can't reach it.
+                let source = if tag == first_hop && entry_ipv6 {
+                    local_ip6
+                } else if tag == first_hop || tag == "direct" {
+                    Some(local_ip)
+                } else {
+                    None
+                };
+                if let (Some(source), Some(obj)) = (source, outbound.as_object_mut()) {
+                    obj.insert("sendThrough".to_string(), json!(source));
                 }
             }
 
+            // "direct" is bound to the IPv4 address and can't reach IPv6
+            // destinations; with IPv6 tunnelled, those get their own freedom
+            // outbound bound to the IPv6 address.
+            if let Some(local_ip6) = direct_ip6 {
+                outbounds.push(json!({
+                    "tag": DIRECT6_TAG,
+                    "protocol": "freedom",
+                    "sendThrough": local_ip6
+                }));
+            }
+
             // Add a separate "direct-vpn" outbound WITHOUT sendThrough for corporate
             // VPN subnet traffic. When a corporate VPN is active, traffic to VPN-routed
             // subnets must use the kernel's default source IP (VPN-assigned) rather than
@@ -317,12 +336,28 @@ pub fn generate_client_config(
         "ff00::/8".to_string(), // IPv6 multicast
     ];
 
-    // Defense-in-depth: route VPN server IP directly (alongside helper's ip route add)
-    if !server.address.is_empty() {
-        let server_cidr = if server.address.contains('/') {
-            server.address.clone()
+    // Defense-in-depth: route VPN server IPs directly (alongside helper's ip route add)
+    for address in std::iter::once(server)
+        .chain(via)
+        .flat_map(ServerConfig::dial_addresses)
+    {
+        if address.is_empty() {
+            continue;
+        }
+        let server_cidr = if address.contains('/') {
+            address.to_string()
         } else {
-            format!("{}/32", server.address)
+            match address
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<IpAddr>()
+            {
+                Ok(IpAddr::V4(ip)) => format!("{ip}/32"),
+                Ok(IpAddr::V6(ip)) => format!("{ip}/128"),
+                // A hostname can't go into an ip rule; xray dials it itself
+                // and in TUN mode the helper routes its resolved addresses.
+                Err(_) => continue,
+            }
         };
         if !direct_ips.contains(&server_cidr) {
             direct_ips.push(server_cidr);
@@ -338,6 +373,19 @@ pub fn generate_client_config(
             }
         }
     }
+    let (direct_ips, direct_ips6): (Vec<String>, Vec<String>) = if direct_ip6.is_some() {
+        direct_ips.into_iter().partition(|ip| !ip.contains(':'))
+    } else {
+        (direct_ips, Vec::new())
+    };
+    if !direct_ips6.is_empty() {
+        let ip_values: Vec<Value> = direct_ips6.into_iter().map(Value::String).collect();
+        rules.push(json!({
+            "type": "field",
+            "outboundTag": DIRECT6_TAG,
+            "ip": ip_values
+        }));
+    }
     let ip_values: Vec<Value> = direct_ips.into_iter().map(Value::String).collect();
     rules.push(json!({
         "type": "field",
@@ -348,6 +396,492 @@ pub fn generate_client_config(
     serde_json::to_string_pretty(&config).map_err(AppError::from)
 }
 
+/// Build the `proxy` outbound for `server`'s protocol, plus its mux settings.
+fn build_outbound(server: &ServerConfig, sockopt: Value) -> Result<Value, AppError> {
+    let mut outbound = match server.protocol.as_str() {
+        "vmess" => vmess_outbound(server, sockopt),
+        "trojan" => trojan_outbound(server, sockopt),
+        "shadowsocks" => shadowsocks_outbound(server, sockopt)?,
+        "hysteria2" => hysteria2_outbound(server, sockopt),
+        "wireguard" => wireguard_outbound(server),
+        "raw" => raw_outbound(server)?,
+        _ => vless_outbound(server, sockopt),
+    };
+    if server.mux.enabled {
+        outbound["mux"] = json!({
+            "enabled": true,
+            "concurrency": server.mux.concurrency,
+            "xudpConcurrency": server.mux.xudp_concurrency,
+            "xudpProxyUDP443": server.mux.xudp_proxy_udp_443
+        });
+    }
+    Ok(outbound)
+}
+
+/// A freedom outbound applying `server`'s anti-DPI fragment and noise
+/// settings, for the first hop to dial through. `None` when neither is set.
+fn fragment_outbound(server: &ServerConfig, sockopt: Value) -> Option<Value> {
+    if !server.fragment.is_enabled() && server.noises.is_empty() {
+        return None;
+    }
+    let mut settings = json!({});
+    if server.fragment.is_enabled() {
+        settings["fragment"] = json!({
+            "packets": server.fragment.packets.trim(),
+            "length": server.fragment.length.trim(),
+            "interval": server.fragment.interval.trim()
+        });
+    }
+    if !server.noises.is_empty() {
+        let noises: Vec<Value> = server
+            .noises
+            .iter()
+            .map(|noise| {
+                let mut entry = json!({
+                    "type": noise.noise_type,
+                    "packet": noise.packet
+                });
+                if !noise.delay.trim().is_empty() {
+                    entry["delay"] = json!(noise.delay.trim());
+                }
+                entry
+            })
+            .collect();
+        settings["noises"] = json!(noises);
+    }
+    Some(json!({
+        "tag": FRAGMENT_TAG,
+        "protocol": "freedom",
+        "settings": settings,
+        "streamSettings": {
+            "sockopt": sockopt
+        }
+    }))
+}
+
+/// Route an outbound's own connection through the outbound tagged `tag`.
+/// WireGuard has no streamSettings of its own, so create them when missing.
+fn dial_through(outbound: &mut Value, tag: &str) {
+    let Some(obj) = outbound.as_object_mut() else {
+        return;
+    };
+    let stream = obj.entry("streamSettings").or_insert_with(|| json!({}));
+    if stream.get("sockopt").is_none() {
+        stream["sockopt"] = json!({});
+    }
+    stream["sockopt"]["dialerProxy"] = json!(tag);
+}
+
+/// Build the VLESS `proxy` outbound (REALITY or TLS, over raw TCP, XHTTP,
+/// gRPC or WebSocket).
+fn vless_outbound(server: &ServerConfig, sockopt: Value) -> Value {
+    // REALITY settings are identical across transports; only the stream wrapper differs.
+    // Each generated config (i.e. each connect) uses one of the server's
+    // short IDs at random.
+    let mut reality_settings = json!({
+        "show": false,
+        "fingerprint": server.tls.fingerprint,
+        "serverName": server.tls.server_name,
+        "publicKey": server.reality.public_key,
+        "shortId": server.reality.pick_short_id()
+    });
+    if !server.reality.spider_x.is_empty() {
+        reality_settings["spiderX"] = json!(server.reality.spider_x);
+    }
+
+    let is_tls = server.security == "tls";
+
+    // XHTTP disguises the tunnel as ordinary HTTP, which survives the TCP
+    // session-freezing that DPI applies to raw-TCP REALITY. XTLS-Vision flow
+    // only works over raw TCP, so XHTTP (like gRPC and WebSocket, which frame
+    // the stream themselves) must connect with an empty flow.
+    let (stream_settings, user_flow): (Value, &str) = match server.network.as_str() {
+        "xhttp" => {
+            let path = if server.xhttp_path.trim().is_empty() {
+                "/"
+            } else {
+                server.xhttp_path.trim()
+            };
+            let mode = if server.xhttp_mode.trim().is_empty() {
+                "auto"
+            } else {
+                server.xhttp_mode.trim()
+            };
+            let mut stream_settings = if is_tls {
+                // xHTTP + real TLS for CDN fronting (Cloudflare): the DPI sees the
+                // CDN's whitelisted IPs instead of the throttled origin. REALITY is
+                // impossible here because the CDN terminates TLS. serverName / Host
+                // is the CDN domain (tls.server_name); alpn defaults to h2 to match
+                // Cloudflare's HTTP/2 edge.
+                let cdn_host = tls_server_name(server);
+                json!({
+                    "network": "xhttp",
+                    "security": "tls",
+                    "tlsSettings": tls_settings(server, &["h2"]),
+                    "xhttpSettings": {
+                        "path": path,
+                        "mode": mode,
+                        "host": cdn_host
+                    },
+                    "sockopt": sockopt
+                })
+            } else {
+                json!({
+                    "network": "xhttp",
+                    "security": "reality",
+                    "realitySettings": reality_settings,
+                    "xhttpSettings": { "path": path, "mode": mode },
+                    "sockopt": sockopt
+                })
+            };
+            if !server.xhttp_extra.is_empty() {
+                let mut extra = server.xhttp_extra.to_xray();
+                if let Some(download) = extra.get_mut("downloadSettings") {
+                    // The download half is a second connection: it must leave
+                    // through the same interface and, unless overridden, use
+                    // the upload path the server listens on.
+                    if download.get("xhttpSettings").is_none() {
+                        download["xhttpSettings"] = json!({ "path": path });
+                    }
+                    download["sockopt"] = stream_settings["sockopt"].clone();
+                }
+                stream_settings["xhttpSettings"]["extra"] = extra;
+            }
+            (stream_settings, "")
+        }
+        "grpc" | "ws" if is_tls => (tls_stream_settings(server, sockopt), ""),
+        // Plain VLESS over raw TCP + TLS to the server itself (no CDN). Vision
+        // works over real TLS just as over REALITY.
+        _ if is_tls => (tls_stream_settings(server, sockopt), server.flow.as_str()),
+        // REALITY over gRPC; ws can't carry REALITY and is rejected by validate().
+        "grpc" => (
+            json!({
+                "network": "grpc",
+                "security": "reality",
+                "realitySettings": reality_settings,
+                "grpcSettings": grpc_settings(server),
+                "sockopt": sockopt
+            }),
+            "",
+        ),
+        _ => (
+            json!({
+                "network": "tcp",
+                "security": "reality",
+                "realitySettings": reality_settings,
+                "sockopt": sockopt
+            }),
+            server.flow.as_str(),
+        ),
+    };
+
+    json!({
+        "tag": "proxy",
+        "protocol": "vless",
+        "settings": {
+            "vnext": [
+                {
+                    "address": server.address,
+                    "port": server.port,
+                    "users": [
+                        {
+                            "id": server.uuid,
+                            "flow": user_flow,
+                            "encryption": server.vless_encryption()
+                        }
+                    ]
+                }
+            ]
+        },
+        "streamSettings": stream_settings
+    })
+}
+
+/// Stream settings for plain TLS (or none) over raw TCP, WebSocket or gRPC:
+/// VMess, Trojan, and VLESS when it isn't using REALITY.
+fn tls_stream_settings(server: &ServerConfig, sockopt: Value) -> Value {
+    let mut stream_settings = json!({
+        "network": server.network,
+        "security": if server.security == "tls" { "tls" } else { "none" },
+        "sockopt": sockopt
+    });
+    if server.security == "tls" {
+        stream_settings["tlsSettings"] = tls_settings(server, &[]);
+    }
+    match server.network.as_str() {
+        "ws" => stream_settings["wsSettings"] = ws_settings(server),
+        "grpc" => stream_settings["grpcSettings"] = grpc_settings(server),
+        _ => {}
+    }
+    stream_settings
+}
+
+/// Build the VMess `proxy` outbound.
+fn vmess_outbound(server: &ServerConfig, sockopt: Value) -> Value {
+    let stream_settings = tls_stream_settings(server, sockopt);
+
+    json!({
+        "tag": "proxy",
+        "protocol": "vmess",
+        "settings": {
+            "vnext": [
+                {
+                    "address": server.address,
+                    "port": server.port,
+                    "users": [
+                        {
+                            "id": server.uuid,
+                            "alterId": server.alter_id,
+                            "security": server.vmess_cipher
+                        }
+                    ]
+                }
+            ]
+        },
+        "streamSettings": stream_settings
+    })
+}
+
+/// Build the Trojan `proxy` outbound. The password replaces VLESS's UUID;
+/// everything else is ordinary TLS.
+fn trojan_outbound(server: &ServerConfig, sockopt: Value) -> Value {
+    json!({
+        "tag": "proxy",
+        "protocol": "trojan",
+        "settings": {
+            "servers": [
+                {
+                    "address": server.address,
+                    "port": server.port,
+                    "password": server.password
+                }
+            ]
+        },
+        "streamSettings": tls_stream_settings(server, sockopt)
+    })
+}
+
+/// Build the Shadowsocks `proxy` outbound. A v2ray-plugin in websocket mode is
+/// reproduced with xray's own ws (+TLS) transport, which speaks the same wire
+/// format; other SIP003 plugins can't be expressed and are an error.
+fn shadowsocks_outbound(server: &ServerConfig, sockopt: Value) -> Result<Value, AppError> {
+    let stream_settings = match server.ss_plugin_transport().map_err(AppError::Config)? {
+        Some(plugin) => {
+            let carrier = ServerConfig {
+                network: "ws".to_string(),
+                security: if plugin.tls { "tls" } else { "none" }.to_string(),
+                ws_path: plugin.path,
+                ws_host: plugin.host.clone(),
+                tls: TlsSettings {
+                    server_name: plugin.host,
+                    ..server.tls.clone()
+                },
+                ..server.clone()
+            };
+            tls_stream_settings(&carrier, sockopt)
+        }
+        None => json!({
+            "network": "tcp",
+            "sockopt": sockopt
+        }),
+    };
+
+    Ok(json!({
+        "tag": "proxy",
+        "protocol": "shadowsocks",
+        "settings": {
+            "servers": [
+                {
+                    "address": server.address,
+                    "port": server.port,
+                    "method": server.ss_method,
+                    "password": server.password
+                }
+            ]
+        },
+        "streamSettings": stream_settings
+    }))
+}
+
+/// Build the Hysteria2 `proxy` outbound. xray (since v26) implements
+/// Hysteria2 as the `hysteria` protocol paired with a `hysteria` stream
+/// network; it runs over QUIC, so the TCP-freezing DPI that REALITY and XHTTP
+/// work around never sees a TCP session at all. Salamander obfuscation is a
+/// UDP finalmask rather than part of the protocol settings.
+fn hysteria2_outbound(server: &ServerConfig, sockopt: Value) -> Value {
+    let mut hysteria_settings = json!({
+        "version": 2,
+        "auth": server.password
+    });
+    // Without bandwidth hints the client uses BBR; with them, Brutal.
+    if server.hy2_up_mbps > 0 {
+        hysteria_settings["up"] = json!(format!("{} mbps", server.hy2_up_mbps));
+    }
+    if server.hy2_down_mbps > 0 {
+        hysteria_settings["down"] = json!(format!("{} mbps", server.hy2_down_mbps));
+    }
+
+    // QUIC negotiates HTTP/3; no uTLS fingerprint applies to it.
+    let mut tls = tls_settings(server, &["h3"]);
+    if let Some(tls) = tls.as_object_mut() {
+        tls.remove("fingerprint");
+    }
+
+    let mut stream_settings = json!({
+        "network": "hysteria",
+        "security": "tls",
+        "tlsSettings": tls,
+        "hysteriaSettings": hysteria_settings,
+        "sockopt": sockopt
+    });
+    if !server.hy2_obfs_password.is_empty() {
+        stream_settings["finalmask"] = json!({
+            "udp": [
+                {
+                    "type": "salamander",
+                    "settings": { "password": server.hy2_obfs_password }
+                }
+            ]
+        });
+    }
+
+    json!({
+        "tag": "proxy",
+        "protocol": "hysteria",
+        "settings": {
+            "version": 2,
+            "address": server.address,
+            "port": server.port
+        },
+        "streamSettings": stream_settings
+    })
+}
+
+/// Build the WireGuard `proxy` outbound. WireGuard carries its own UDP
+/// transport, so there are no streamSettings (and the TCP keepalive sockopt
+/// would be meaningless).
+fn wireguard_outbound(server: &ServerConfig) -> Value {
+    let endpoint = format!("{}:{}", format_host(&server.address), server.port);
+    let allowed_ips = if server.wg_allowed_ips.is_empty() {
+        vec!["0.0.0.0/0".to_string(), "::/0".to_string()]
+    } else {
+        server.wg_allowed_ips.clone()
+    };
+
+    let mut peer = json!({
+        "publicKey": server.wg_peer_public_key,
+        "endpoint": endpoint,
+        "allowedIPs": allowed_ips
+    });
+    if !server.wg_preshared_key.is_empty() {
+        peer["preSharedKey"] = json!(server.wg_preshared_key);
+    }
+    if server.wg_keepalive > 0 {
+        peer["keepAlive"] = json!(server.wg_keepalive);
+    }
+
+    let mut settings = json!({
+        "secretKey": server.wg_private_key,
+        "address": server.wg_local_addresses,
+        "peers": [peer],
+        // xray runs unprivileged; use the userspace (gVisor) stack instead of
+        // probing for a kernel TUN it can't create.
+        "noKernelTun": true
+    });
+    if server.wg_mtu > 0 {
+        settings["mtu"] = json!(server.wg_mtu);
+    }
+    if !server.wg_reserved.is_empty() {
+        settings["reserved"] = json!(server.wg_reserved);
+    }
+
+    json!({
+        "tag": "proxy",
+        "protocol": "wireguard",
+        "settings": settings
+    })
+}
+
+/// The user's own outbound object, retagged as "proxy". Its stream settings
+/// are left alone, so it doesn't get the keepalive sockopt either.
+fn raw_outbound(server: &ServerConfig) -> Result<Value, AppError> {
+    let mut outbound = server
+        .raw_outbound
+        .clone()
+        .filter(Value::is_object)
+        .ok_or_else(|| AppError::Config("Raw server has no outbound object".to_string()))?;
+    outbound["tag"] = json!("proxy");
+    Ok(outbound)
+}
+
+/// `tlsSettings` for real TLS. `default_alpn` applies when the profile sets
+/// no ALPN of its own.
+fn tls_settings(server: &ServerConfig, default_alpn: &[&str]) -> Value {
+    let mut tls = json!({
+        "serverName": tls_server_name(server),
+        "fingerprint": server.tls.fingerprint
+    });
+    if !server.tls.alpn.is_empty() {
+        tls["alpn"] = json!(server.tls.alpn);
+    } else if !default_alpn.is_empty() {
+        tls["alpn"] = json!(default_alpn);
+    }
+    if server.tls.allow_insecure {
+        tls["allowInsecure"] = json!(true);
+    }
+    let pins = server.tls.pinned_hashes();
+    if !pins.is_empty() {
+        tls["pinnedPeerCertSha256"] = json!(pins.join(","));
+    }
+    if let Some(ech) = server.tls.ech.to_xray() {
+        tls["echConfigList"] = json!(ech);
+    }
+    tls
+}
+
+/// TLS SNI for a non-REALITY profile: the configured server name, falling
+/// back to the server address when a share link carried no `sni`.
+fn tls_server_name(server: &ServerConfig) -> &str {
+    let sni = server.tls.server_name.trim();
+    if sni.is_empty() {
+        server.address.as_str()
+    } else {
+        sni
+    }
+}
+
+/// `wsSettings` for the WebSocket transport. An empty Host header falls back
+/// to the TLS server name so CDN-fronted profiles need only one domain.
+fn ws_settings(server: &ServerConfig) -> Value {
+    let path = if server.ws_path.trim().is_empty() {
+        "/"
+    } else {
+        server.ws_path.trim()
+    };
+    let host = if server.ws_host.trim().is_empty() {
+        tls_server_name(server)
+    } else {
+        server.ws_host.trim()
+    };
+    // xray reads the early-data size from an `ed` query parameter on the
+    // path; don't add a second one if the path already carries it.
+    let path = if server.ws_early_data > 0 && !path.contains("ed=") {
+        let sep = if path.contains('?') { '&' } else { '?' };
+        format!("{path}{sep}ed={}", server.ws_early_data)
+    } else {
+        path.to_string()
+    };
+    json!({ "path": path, "host": host })
+}
+
+/// `grpcSettings` for the gRPC transport.
+fn grpc_settings(server: &ServerConfig) -> Value {
+    json!({
+        "serviceName": server.grpc_service_name,
+        "multiMode": server.grpc_multi_mode
+    })
+}
+
 /// Modify xray config JSON for Android:
 /// - Remove HTTP inbound (unnecessary with TUN)
 ///
@@ -368,10 +902,73 @@ pub fn modify_config_for_android(config_json: &str) -> Result<String, AppError>
     serde_json::to_string_pretty(&config).map_err(AppError::from)
 }
 
+/// Turn the first proxy outbound of an xray client `config.json` (or a bare
+/// outbound object) into a "raw" server. Its tag names the server unless it
+/// is the generic "proxy", in which case `name` (the file name) does.
+pub fn parse_xray_config(content: &str, name: &str) -> Result<ServerConfig, AppError> {
+    let root: Value = serde_json::from_str(content)
+        .map_err(|e| AppError::Config(format!("Invalid JSON: {e}")))?;
+    let protocol = |o: &Value| {
+        o.get("protocol")
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_string()
+    };
+    let outbound = match root.get("outbounds") {
+        Some(Value::Array(outbounds)) => outbounds
+            .iter()
+            .find(|o| !NON_PROXY_OUTBOUNDS.contains(&protocol(o).as_str()))
+            .ok_or_else(|| AppError::Config("Config has no proxy outbound".to_string()))?,
+        Some(_) => return Err(AppError::Config("'outbounds' must be an array".to_string())),
+        None if root.get("protocol").is_some() => &root,
+        None => return Err(AppError::Config("Config has no 'outbounds'".to_string())),
+    };
+
+    let (address, port) = outbound_endpoint(outbound).ok_or_else(|| {
+        AppError::Config(format!(
+            "Can't find the server address in the '{}' outbound",
+            protocol(outbound)
+        ))
+    })?;
+    let mut outbound = outbound.clone();
+    let tag = outbound
+        .as_object_mut()
+        .and_then(|o| o.remove("tag"))
+        .and_then(|t| t.as_str().map(str::to_string))
+        .unwrap_or_default();
+    let name = [tag.trim(), name.trim()]
+        .into_iter()
+        .find(|n| !n.is_empty() && *n != "proxy")
+        .map(str::to_string)
+        .unwrap_or_else(|| address.clone());
+
+    let server = ServerConfig {
+        name,
+        address,
+        port,
+        uuid: String::new(),
+        flow: String::new(),
+        security: "none".to_string(),
+        protocol: "raw".to_string(),
+        raw_outbound: Some(outbound),
+        ..ServerConfig::default()
+    };
+    server.validate().map_err(AppError::Config)?;
+    Ok(server)
+}
+
+#[tauri::command]
+pub fn parse_xray_config_cmd(content: String, name: String) -> Result<ServerConfig, String> {
+    parse_xray_config(&content, &name).map_err(|e| e.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::RealitySettings;
+    use crate::models::{
+        EchSettings, FragmentSettings, NoiseSettings, RealitySettings, TlsSettings, XhttpDownload,
+        XhttpExtra, XmuxSettings,
+    };
 
     #[test]
     fn test_generate_config() {
@@ -385,13 +982,18 @@ mod tests {
             reality: RealitySettings {
                 public_key: "kieJgZYLW9ZiKbGLpKnv4XyVo6_42inSONJrr-96tUU".to_string(),
                 short_id: "d64736262cd50811".to_string(),
+                ..RealitySettings::default()
+            },
+            tls: TlsSettings {
                 server_name: "www.microsoft.com".to_string(),
                 fingerprint: "chrome".to_string(),
+                ..TlsSettings::default()
             },
             network: "tcp".to_string(),
             xhttp_path: String::new(),
             security: "reality".to_string(),
             xhttp_mode: "auto".to_string(),
+            ..ServerConfig::default()
         };
 
         let config_str = generate_client_config(&server, 10808, &[], &[], None, &[]).unwrap();
@@ -477,11 +1079,51 @@ mod tests {
 
         let user = &config["outbounds"][0]["settings"]["vnext"][0]["users"][0];
         assert_eq!(user["encryption"], "none");
-    }
 
-    #[test]
-    fn test_config_routing_rules() {
-        let server = ServerConfig::default();
+        let pq = ServerConfig {
+            vless_encryption: "mlkem768x25519plus.native.0rtt.key".to_string(),
+            ..ServerConfig::default()
+        };
+        let config_str = generate_client_config(&pq, 10808, &[], &[], None, &[]).unwrap();
+        let config: Value = serde_json::from_str(&config_str).unwrap();
+        let user = &config["outbounds"][0]["settings"]["vnext"][0]["users"][0];
+        assert_eq!(user["encryption"], "mlkem768x25519plus.native.0rtt.key");
+    }
+
+    #[test]
+    fn test_config_direct_rule_for_ipv6_and_hostname_servers() {
+        let direct_ips = |address: &str| -> Vec<Value> {
+            let server = ServerConfig {
+                address: address.to_string(),
+                ..ServerConfig::default()
+            };
+            let config_str = generate_client_config(&server, 10808, &[], &[], None, &[]).unwrap();
+            let config: Value = serde_json::from_str(&config_str).unwrap();
+            config["routing"]["rules"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|r| {
+                    r["ip"]
+                        .as_array()
+                        .is_some_and(|ips| ips.contains(&json!("10.0.0.0/8")))
+                })
+                .unwrap()["ip"]
+                .as_array()
+                .unwrap()
+                .clone()
+        };
+        assert!(direct_ips("2001:db8::1").contains(&json!("2001:db8::1/128")));
+        assert!(direct_ips("[2001:db8::1]").contains(&json!("2001:db8::1/128")));
+        let ips = direct_ips("vpn.example.com");
+        assert!(!ips
+            .iter()
+            .any(|ip| ip.as_str().unwrap().contains("example")));
+    }
+
+    #[test]
+    fn test_config_routing_rules() {
+        let server = ServerConfig::default();
         let config_str = generate_client_config(&server, 10808, &[], &[], None, &[]).unwrap();
         let config: Value = serde_json::from_str(&config_str).unwrap();
 
@@ -525,13 +1167,18 @@ mod tests {
             reality: RealitySettings {
                 public_key: "pubkey".to_string(),
                 short_id: "shortid".to_string(),
+                ..RealitySettings::default()
+            },
+            tls: TlsSettings {
                 server_name: "example.com".to_string(),
                 fingerprint: "chrome".to_string(),
+                ..TlsSettings::default()
             },
             network: "tcp".to_string(),
             xhttp_path: String::new(),
             security: "reality".to_string(),
             xhttp_mode: "auto".to_string(),
+            ..ServerConfig::default()
         };
         let config_str = generate_client_config(&server, 10808, &[], &[], None, &[]).unwrap();
         let parsed: Result<Value, _> = serde_json::from_str(&config_str);
@@ -555,6 +1202,7 @@ mod tests {
             xhttp_path: String::new(),
             security: "reality".to_string(),
             xhttp_mode: "auto".to_string(),
+            ..ServerConfig::default()
         };
         let config_str = generate_client_config(&server, 10808, &[], &[], None, &[]).unwrap();
         let config: Value = serde_json::from_str(&config_str).unwrap();
@@ -580,13 +1228,18 @@ mod tests {
             reality: RealitySettings {
                 public_key: "mypublickey".to_string(),
                 short_id: "myshortid".to_string(),
+                ..RealitySettings::default()
+            },
+            tls: TlsSettings {
                 server_name: "www.cloudflare.com".to_string(),
                 fingerprint: "safari".to_string(),
+                ..TlsSettings::default()
             },
             network: "tcp".to_string(),
             xhttp_path: String::new(),
             security: "reality".to_string(),
             xhttp_mode: "auto".to_string(),
+            ..ServerConfig::default()
         };
         let config_str = generate_client_config(&server, 10808, &[], &[], None, &[]).unwrap();
         let config: Value = serde_json::from_str(&config_str).unwrap();
@@ -803,6 +1456,7 @@ mod tests {
             xhttp_path: String::new(),
             security: "reality".to_string(),
             xhttp_mode: "auto".to_string(),
+            ..ServerConfig::default()
         };
         let config_str = generate_client_config(&server, 10808, &[], &[], None, &[]).unwrap();
         let config: Value = serde_json::from_str(&config_str).unwrap();
@@ -830,6 +1484,375 @@ mod tests {
         assert!(outbounds[2].get("sendThrough").is_none());
     }
 
+    #[test]
+    fn test_config_send_through_ipv6() {
+        let server = ServerConfig {
+            address: "2001:db8::10".to_string(),
+            ..ServerConfig::default()
+        };
+        let config_str = generate_chained_client_config(
+            &server,
+            None,
+            10808,
+            &[],
+            &[],
+            SendThrough {
+                ipv4: Some("192.168.1.50"),
+                ipv6: Some("2001:db8::50"),
+                block_ipv6: false,
+                entry_ipv6: true,
+            },
+            &[],
+        )
+        .unwrap();
+        let config: Value = serde_json::from_str(&config_str).unwrap();
+
+        let outbounds = config["outbounds"].as_array().unwrap();
+        let outbound = |tag: &str| outbounds.iter().find(|o| o["tag"] == tag).unwrap();
+        // IPv6 server is dialled from the IPv6 address
+        assert_eq!(outbound("proxy")["sendThrough"], "2001:db8::50");
+        assert_eq!(outbound("direct")["sendThrough"], "192.168.1.50");
+        assert_eq!(outbound("direct6")["sendThrough"], "2001:db8::50");
+
+        let rules = config["routing"]["rules"].as_array().unwrap();
+        let direct6 = rules
+            .iter()
+            .find(|r| r["outboundTag"] == "direct6")
+            .unwrap();
+        let ips: Vec<&str> = direct6["ip"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().unwrap())
+            .collect();
+        assert!(ips.contains(&"::1/128"));
+        assert!(ips.contains(&"2001:db8::10/128"));
+        let direct = rules
+            .iter()
+            .rfind(|r| r["outboundTag"] == "direct" && r.get("ip").is_some())
+            .unwrap();
+        assert!(direct["ip"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|v| !v.as_str().unwrap().contains(':')));
+
+        // Without TUN mode the IPv6 address is ignored
+        let config_str = generate_chained_client_config(
+            &server,
+            None,
+            10808,
+            &[],
+            &[],
+            SendThrough {
+                ipv4: None,
+                ipv6: Some("2001:db8::50"),
+                block_ipv6: false,
+                entry_ipv6: true,
+            },
+            &[],
+        )
+        .unwrap();
+        assert!(!config_str.contains("direct6"));
+        assert!(!config_str.contains("sendThrough"));
+
+        // With no IPv6 source known, the IPv6 server isn't bound to the IPv4 one
+        let config_str = generate_chained_client_config(
+            &server,
+            None,
+            10808,
+            &[],
+            &[],
+            SendThrough {
+                ipv4: Some("192.168.1.50"),
+                ipv6: None,
+                block_ipv6: false,
+                entry_ipv6: true,
+            },
+            &[],
+        )
+        .unwrap();
+        let config: Value = serde_json::from_str(&config_str).unwrap();
+        let outbounds = config["outbounds"].as_array().unwrap();
+        let outbound = |tag: &str| outbounds.iter().find(|o| o["tag"] == tag).unwrap();
+        assert!(outbound("proxy").get("sendThrough").is_none());
+        assert_eq!(outbound("direct")["sendThrough"], "192.168.1.50");
+    }
+
+    #[test]
+    fn test_config_block_ipv6_keeps_ipv6_first_hop() {
+        let server = ServerConfig {
+            address: "2001:db8::10".to_string(),
+            ..ServerConfig::default()
+        };
+        let config_str = generate_chained_client_config(
+            &server,
+            None,
+            10808,
+            &[],
+            &[],
+            SendThrough {
+                ipv4: Some("192.168.1.50"),
+                ipv6: Some("2001:db8::50"),
+                block_ipv6: true,
+                entry_ipv6: true,
+            },
+            &[],
+        )
+        .unwrap();
+        let config: Value = serde_json::from_str(&config_str).unwrap();
+
+        // Blocking stops tunnelled IPv6, not the uplink to an IPv6 server.
+        let outbounds = config["outbounds"].as_array().unwrap();
+        let outbound = |tag: &str| outbounds.iter().find(|o| o["tag"] == tag);
+        assert_eq!(outbound("proxy").unwrap()["sendThrough"], "2001:db8::50");
+        assert_eq!(outbound("direct").unwrap()["sendThrough"], "192.168.1.50");
+        assert!(outbound("direct6").is_none());
+        assert!(!config_str.contains("direct6"));
+    }
+
+    #[test]
+    fn test_config_chained_via_dialer_proxy() {
+        let entry = ServerConfig {
+            address: "10.20.30.40".to_string(),
+            ..ServerConfig::default()
+        };
+        let exit = ServerConfig {
+            address: "50.60.70.80".to_string(),
+            via: Some(entry.id.clone()),
+            ..ServerConfig::default()
+        };
+        let config_str = generate_chained_client_config(
+            &exit,
+            Some(&entry),
+            10808,
+            &[],
+            &[],
+            SendThrough {
+                ipv4: Some("192.168.1.50"),
+                ipv6: None,
+                block_ipv6: false,
+                entry_ipv6: false,
+            },
+            &[],
+        )
+        .unwrap();
+        let config: Value = serde_json::from_str(&config_str).unwrap();
+
+        let outbounds = config["outbounds"].as_array().unwrap();
+        let proxy = &outbounds[0];
+        let via = &outbounds[1];
+        assert_eq!(proxy["tag"], "proxy");
+        assert_eq!(proxy["settings"]["vnext"][0]["address"], "50.60.70.80");
+        assert_eq!(
+            proxy["streamSettings"]["sockopt"]["dialerProxy"],
+            "proxy-via"
+        );
+        // Keepalive settings survive alongside dialerProxy
+        assert_eq!(proxy["streamSettings"]["sockopt"]["tcpKeepAliveIdle"], 30);
+        assert_eq!(via["tag"], "proxy-via");
+        assert_eq!(via["settings"]["vnext"][0]["address"], "10.20.30.40");
+        assert!(via["streamSettings"]["sockopt"]
+            .get("dialerProxy")
+            .is_none());
+
+        // Only the first hop is bound to the physical interface
+        assert_eq!(via["sendThrough"], "192.168.1.50");
+        assert!(proxy.get("sendThrough").is_none());
+
+        let rules = config["routing"]["rules"].as_array().unwrap();
+        let direct_ips: Vec<&str> = rules
+            .iter()
+            .filter(|r| r["outboundTag"] == "direct")
+            .filter_map(|r| r["ip"].as_array())
+            .flatten()
+            .filter_map(|v| v.as_str())
+            .collect();
+        assert!(direct_ips.contains(&"10.20.30.40/32"));
+        assert!(direct_ips.contains(&"50.60.70.80/32"));
+    }
+
+    #[test]
+    fn test_config_chained_wireguard_exit_gets_stream_settings() {
+        let entry = ServerConfig::default();
+        let exit = ServerConfig {
+            protocol: "wireguard".to_string(),
+            address: "162.159.192.1".to_string(),
+            wg_local_addresses: vec!["172.16.0.2/32".to_string()],
+            ..ServerConfig::default()
+        };
+        let config_str = generate_chained_client_config(
+            &exit,
+            Some(&entry),
+            10808,
+            &[],
+            &[],
+            SendThrough::default(),
+            &[],
+        )
+        .unwrap();
+        let config: Value = serde_json::from_str(&config_str).unwrap();
+        assert_eq!(
+            config["outbounds"][0]["streamSettings"]["sockopt"]["dialerProxy"],
+            "proxy-via"
+        );
+    }
+
+    const XRAY_CLIENT_CONFIG: &str = r#"{
+        "inbounds": [{ "port": 1080, "protocol": "socks" }],
+        "outbounds": [
+            { "tag": "direct", "protocol": "freedom" },
+            {
+                "tag": "proxy",
+                "protocol": "vless",
+                "settings": {
+                    "vnext": [{
+                        "address": "203.0.113.9",
+                        "port": 443,
+                        "users": [{ "id": "aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee", "encryption": "none" }]
+                    }]
+                },
+                "streamSettings": { "network": "kcp", "kcpSettings": { "seed": "s" } }
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_xray_config_first_proxy_outbound() {
+        let server = parse_xray_config(XRAY_CLIENT_CONFIG, "office").unwrap();
+        assert_eq!(server.protocol, "raw");
+        assert_eq!(server.name, "office");
+        assert_eq!(server.address, "203.0.113.9");
+        assert_eq!(server.port, 443);
+        let outbound = server.raw_outbound.unwrap();
+        assert_eq!(outbound["protocol"], "vless");
+        assert!(outbound.get("tag").is_none());
+
+        let err = parse_xray_config(r#"{"outbounds":[{"protocol":"freedom"}]}"#, "x")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("no proxy outbound"), "unexpected error: {err}");
+        let err = parse_xray_config(r#"{"protocol":"vless","settings":{}}"#, "x")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("server address"), "unexpected error: {err}");
+        assert!(parse_xray_config("{}", "x").is_err());
+    }
+
+    #[test]
+    fn test_config_raw_outbound_spliced() {
+        let server = parse_xray_config(XRAY_CLIENT_CONFIG, "office").unwrap();
+        let config_str =
+            generate_client_config(&server, 10808, &[], &[], Some("192.168.1.5"), &[]).unwrap();
+        let config: Value = serde_json::from_str(&config_str).unwrap();
+
+        let proxy = &config["outbounds"][0];
+        assert_eq!(proxy["tag"], "proxy");
+        assert_eq!(proxy["protocol"], "vless");
+        assert_eq!(proxy["streamSettings"]["network"], "kcp");
+        assert_eq!(proxy["sendThrough"], "192.168.1.5");
+
+        // The app still owns inbounds, stats and routing.
+        assert_eq!(config["inbounds"][0]["tag"], "socks-in");
+        assert_eq!(config["api"]["listen"], STATS_API_ADDR);
+        let rules = config["routing"]["rules"].as_array().unwrap();
+        assert!(rules.iter().any(|r| r["ip"]
+            .as_array()
+            .is_some_and(|ips| ips.contains(&json!("203.0.113.9/32")))));
+    }
+
+    #[test]
+    fn test_config_mux() {
+        let mut server = ServerConfig::default();
+        let config_str = generate_client_config(&server, 10808, &[], &[], None, &[]).unwrap();
+        let config: Value = serde_json::from_str(&config_str).unwrap();
+        assert!(config["outbounds"][0].get("mux").is_none());
+
+        server.mux.enabled = true;
+        server.mux.concurrency = -1;
+        server.mux.xudp_proxy_udp_443 = "skip".to_string();
+        let config_str = generate_client_config(&server, 10808, &[], &[], None, &[]).unwrap();
+        let config: Value = serde_json::from_str(&config_str).unwrap();
+        let mux = &config["outbounds"][0]["mux"];
+        assert_eq!(mux["enabled"], true);
+        assert_eq!(mux["concurrency"], -1);
+        assert_eq!(mux["xudpConcurrency"], 16);
+        assert_eq!(mux["xudpProxyUDP443"], "skip");
+    }
+
+    #[test]
+    fn test_config_fragment_outbound() {
+        let server = ServerConfig {
+            fragment: FragmentSettings {
+                packets: "tlshello".to_string(),
+                length: "100-200".to_string(),
+                interval: "10-20".to_string(),
+            },
+            noises: vec![NoiseSettings {
+                noise_type: "rand".to_string(),
+                packet: "10-20".to_string(),
+                delay: String::new(),
+            }],
+            ..ServerConfig::default()
+        };
+        let config_str =
+            generate_client_config(&server, 10808, &[], &[], Some("192.168.1.50"), &[]).unwrap();
+        let config: Value = serde_json::from_str(&config_str).unwrap();
+
+        let outbounds = config["outbounds"].as_array().unwrap();
+        let proxy = &outbounds[0];
+        let fragment = &outbounds[1];
+        assert_eq!(
+            proxy["streamSettings"]["sockopt"]["dialerProxy"],
+            "fragment"
+        );
+        assert_eq!(fragment["tag"], "fragment");
+        assert_eq!(fragment["protocol"], "freedom");
+        assert_eq!(fragment["settings"]["fragment"]["packets"], "tlshello");
+        assert_eq!(fragment["settings"]["fragment"]["length"], "100-200");
+        assert_eq!(fragment["settings"]["fragment"]["interval"], "10-20");
+        assert_eq!(fragment["settings"]["noises"][0]["type"], "rand");
+        assert!(fragment["settings"]["noises"][0].get("delay").is_none());
+
+        // The fragment outbound opens the socket, so it owns sendThrough.
+        assert_eq!(fragment["sendThrough"], "192.168.1.50");
+        assert!(proxy.get("sendThrough").is_none());
+    }
+
+    #[test]
+    fn test_config_fragment_applies_to_chain_entry() {
+        let entry = ServerConfig {
+            fragment: FragmentSettings {
+                packets: "1-3".to_string(),
+                length: "5".to_string(),
+                interval: "1".to_string(),
+            },
+            ..ServerConfig::default()
+        };
+        let exit = ServerConfig::default();
+        let config_str = generate_chained_client_config(
+            &exit,
+            Some(&entry),
+            10808,
+            &[],
+            &[],
+            SendThrough::default(),
+            &[],
+        )
+        .unwrap();
+        let config: Value = serde_json::from_str(&config_str).unwrap();
+        let outbounds = config["outbounds"].as_array().unwrap();
+        assert_eq!(
+            outbounds[0]["streamSettings"]["sockopt"]["dialerProxy"],
+            "proxy-via"
+        );
+        assert_eq!(
+            outbounds[1]["streamSettings"]["sockopt"]["dialerProxy"],
+            "fragment"
+        );
+        assert_eq!(outbounds[2]["settings"]["fragment"]["packets"], "1-3");
+    }
+
     #[test]
     fn test_config_has_stats_policy() {
         let server = ServerConfig::default();
@@ -854,13 +1877,18 @@ mod tests {
             reality: RealitySettings {
                 public_key: "pbk".to_string(),
                 short_id: "713a3828823be899".to_string(),
+                ..RealitySettings::default()
+            },
+            tls: TlsSettings {
                 server_name: "ya.ru".to_string(),
                 fingerprint: "chrome".to_string(),
+                ..TlsSettings::default()
             },
             network: "xhttp".to_string(),
             xhttp_path: "/xhttp".to_string(),
             security: "reality".to_string(),
             xhttp_mode: "auto".to_string(),
+            ..ServerConfig::default()
         };
         let config_str = generate_client_config(&server, 10808, &[], &[], None, &[]).unwrap();
         let config: Value = serde_json::from_str(&config_str).unwrap();
@@ -894,6 +1922,76 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_config_xhttp_extra_split_download() {
+        let server = ServerConfig {
+            address: "1.2.3.4".to_string(),
+            network: "xhttp".to_string(),
+            xhttp_path: "/up".to_string(),
+            xhttp_extra: XhttpExtra {
+                x_padding_bytes: "100-1000".to_string(),
+                xmux: XmuxSettings {
+                    max_concurrency: "16-32".to_string(),
+                    ..XmuxSettings::default()
+                },
+                download: Some(XhttpDownload {
+                    address: "5.6.7.8".to_string(),
+                    port: 8443,
+                    security: "reality".to_string(),
+                    path: String::new(),
+                    tls: TlsSettings::default(),
+                    reality: RealitySettings {
+                        public_key: "pbk".to_string(),
+                        short_id: "ab".to_string(),
+                        ..RealitySettings::default()
+                    },
+                }),
+                ..XhttpExtra::default()
+            },
+            ..ServerConfig::default()
+        };
+        let config_str =
+            generate_client_config(&server, 10808, &[], &[], Some("192.168.1.5"), &[]).unwrap();
+        let config: Value = serde_json::from_str(&config_str).unwrap();
+        let stream = &config["outbounds"][0]["streamSettings"];
+        let extra = &stream["xhttpSettings"]["extra"];
+        assert_eq!(extra["xPaddingBytes"], "100-1000");
+        assert_eq!(extra["xmux"]["maxConcurrency"], "16-32");
+        let download = &extra["downloadSettings"];
+        assert_eq!(download["address"], "5.6.7.8");
+        assert_eq!(download["port"], 8443);
+        // Empty download path falls back to the upload path.
+        assert_eq!(download["xhttpSettings"]["path"], "/up");
+        assert_eq!(download["sockopt"], stream["sockopt"]);
+
+        // Both endpoints bypass the proxy.
+        let direct_ips = config["routing"]["rules"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|r| {
+                r["ip"]
+                    .as_array()
+                    .is_some_and(|ips| ips.contains(&json!("1.2.3.4/32")))
+            })
+            .expect("direct rule for server");
+        assert!(direct_ips["ip"]
+            .as_array()
+            .unwrap()
+            .contains(&json!("5.6.7.8/32")));
+
+        // Without extra settings nothing is emitted.
+        let plain = ServerConfig {
+            network: "xhttp".to_string(),
+            ..ServerConfig::default()
+        };
+        let config_str = generate_client_config(&plain, 10808, &[], &[], None, &[]).unwrap();
+        let config: Value = serde_json::from_str(&config_str).unwrap();
+        assert!(config["outbounds"][0]["streamSettings"]["xhttpSettings"]
+            .get("extra")
+            .is_none());
+    }
+
     #[test]
     fn test_config_has_tcp_keepalive() {
         let server = ServerConfig::default();
@@ -915,10 +2013,10 @@ mod tests {
             security: "tls".to_string(),
             xhttp_path: "/assets".to_string(),
             xhttp_mode: "stream-one".to_string(),
-            reality: RealitySettings {
+            tls: TlsSettings {
                 server_name: "cdn.example.com".to_string(),
                 fingerprint: "chrome".to_string(),
-                ..RealitySettings::default()
+                ..TlsSettings::default()
             },
             ..ServerConfig::default()
         };
@@ -944,6 +2042,63 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_config_reality_short_ids_and_spider_x() {
+        let server = ServerConfig {
+            reality: RealitySettings {
+                public_key: "pbk".to_string(),
+                short_id: "ab".to_string(),
+                extra_short_ids: vec!["cd".to_string()],
+                spider_x: "/news".to_string(),
+            },
+            ..ServerConfig::default()
+        };
+        let config = generate_client_config(&server, 1080, &[], &[], None, &[]).unwrap();
+        let parsed: Value = serde_json::from_str(&config).unwrap();
+        let reality = &parsed["outbounds"][0]["streamSettings"]["realitySettings"];
+        assert_eq!(reality["spiderX"], "/news");
+        let short_id = reality["shortId"].as_str().unwrap();
+        assert!(short_id == "ab" || short_id == "cd", "{short_id}");
+
+        let plain =
+            generate_client_config(&ServerConfig::default(), 1080, &[], &[], None, &[]).unwrap();
+        let parsed: Value = serde_json::from_str(&plain).unwrap();
+        assert!(parsed["outbounds"][0]["streamSettings"]["realitySettings"]
+            .get("spiderX")
+            .is_none());
+    }
+
+    #[test]
+    fn test_config_tls_ech() {
+        let mut server = ServerConfig {
+            network: "xhttp".to_string(),
+            security: "tls".to_string(),
+            tls: TlsSettings {
+                server_name: "cdn.example.com".to_string(),
+                ech: EchSettings {
+                    doh_server: "https://1.1.1.1/dns-query".to_string(),
+                    query_domain: "cloudflare-ech.com".to_string(),
+                    ..EchSettings::default()
+                },
+                ..TlsSettings::default()
+            },
+            ..ServerConfig::default()
+        };
+        let config = generate_client_config(&server, 1080, &[], &[], None, &[]).unwrap();
+        let parsed: Value = serde_json::from_str(&config).unwrap();
+        assert_eq!(
+            parsed["outbounds"][0]["streamSettings"]["tlsSettings"]["echConfigList"],
+            "cloudflare-ech.com+https://1.1.1.1/dns-query"
+        );
+
+        server.tls.ech = EchSettings::default();
+        let config = generate_client_config(&server, 1080, &[], &[], None, &[]).unwrap();
+        let parsed: Value = serde_json::from_str(&config).unwrap();
+        assert!(parsed["outbounds"][0]["streamSettings"]["tlsSettings"]
+            .get("echConfigList")
+            .is_none());
+    }
+
     #[test]
     fn test_config_xhttp_mode_passthrough() {
         // A non-default xhttp mode on a REALITY xhttp server must reach the
@@ -958,4 +2113,410 @@ mod tests {
         let xhttp = &parsed["outbounds"][0]["streamSettings"]["xhttpSettings"];
         assert_eq!(xhttp["mode"], "packet-up");
     }
+
+    #[test]
+    fn test_config_vmess_ws_tls() {
+        let server = ServerConfig {
+            protocol: "vmess".to_string(),
+            address: "vm.example.com".to_string(),
+            uuid: "aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee".to_string(),
+            flow: String::new(),
+            network: "ws".to_string(),
+            security: "tls".to_string(),
+            ws_path: "/ray".to_string(),
+            alter_id: 0,
+            vmess_cipher: "aes-128-gcm".to_string(),
+            tls: TlsSettings {
+                server_name: "cdn.example.com".to_string(),
+                ..TlsSettings::default()
+            },
+            ..ServerConfig::default()
+        };
+        let config = generate_client_config(&server, 1080, &[], &[], None, &[]).unwrap();
+        let parsed: Value = serde_json::from_str(&config).unwrap();
+        let proxy = &parsed["outbounds"][0];
+        assert_eq!(proxy["tag"], "proxy");
+        assert_eq!(proxy["protocol"], "vmess");
+        let user = &proxy["settings"]["vnext"][0]["users"][0];
+        assert_eq!(user["id"], "aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee");
+        assert_eq!(user["alterId"], 0);
+        assert_eq!(user["security"], "aes-128-gcm");
+        // VMess users carry no VLESS-only fields.
+        assert!(user.get("flow").is_none());
+        let stream = &proxy["streamSettings"];
+        assert_eq!(stream["network"], "ws");
+        assert_eq!(stream["security"], "tls");
+        assert!(stream.get("realitySettings").is_none());
+        assert_eq!(stream["tlsSettings"]["serverName"], "cdn.example.com");
+        assert_eq!(stream["wsSettings"]["path"], "/ray");
+        // Empty ws_host falls back to the TLS server name.
+        assert_eq!(stream["wsSettings"]["host"], "cdn.example.com");
+    }
+
+    #[test]
+    fn test_config_vmess_plain_tcp() {
+        let server = ServerConfig {
+            protocol: "vmess".to_string(),
+            flow: String::new(),
+            network: "tcp".to_string(),
+            security: "none".to_string(),
+            ..ServerConfig::default()
+        };
+        let config = generate_client_config(&server, 1080, &[], &[], None, &[]).unwrap();
+        let parsed: Value = serde_json::from_str(&config).unwrap();
+        let stream = &parsed["outbounds"][0]["streamSettings"];
+        assert_eq!(stream["network"], "tcp");
+        assert_eq!(stream["security"], "none");
+        assert!(stream.get("tlsSettings").is_none());
+        assert!(stream.get("wsSettings").is_none());
+        assert_eq!(stream["sockopt"]["tcpKeepAliveIdle"], 30);
+    }
+
+    #[test]
+    fn test_config_trojan_grpc_tls() {
+        let server = ServerConfig {
+            protocol: "trojan".to_string(),
+            address: "tj.example.com".to_string(),
+            port: 8443,
+            uuid: String::new(),
+            flow: String::new(),
+            network: "grpc".to_string(),
+            security: "tls".to_string(),
+            password: "hunter2".to_string(),
+            grpc_service_name: "TunService".to_string(),
+            tls: TlsSettings {
+                server_name: String::new(),
+                alpn: vec!["h2".to_string()],
+                ..TlsSettings::default()
+            },
+            ..ServerConfig::default()
+        };
+        let config = generate_client_config(&server, 1080, &[], &[], None, &[]).unwrap();
+        let parsed: Value = serde_json::from_str(&config).unwrap();
+        let proxy = &parsed["outbounds"][0];
+        assert_eq!(proxy["tag"], "proxy");
+        assert_eq!(proxy["protocol"], "trojan");
+        let target = &proxy["settings"]["servers"][0];
+        assert_eq!(target["address"], "tj.example.com");
+        assert_eq!(target["port"], 8443);
+        assert_eq!(target["password"], "hunter2");
+        let stream = &proxy["streamSettings"];
+        assert_eq!(stream["network"], "grpc");
+        assert_eq!(stream["security"], "tls");
+        // Empty SNI falls back to the server address.
+        assert_eq!(stream["tlsSettings"]["serverName"], "tj.example.com");
+        assert_eq!(stream["tlsSettings"]["alpn"][0], "h2");
+        assert_eq!(stream["grpcSettings"]["serviceName"], "TunService");
+    }
+
+    #[test]
+    fn test_config_trojan_tcp_omits_empty_alpn() {
+        let server = ServerConfig {
+            protocol: "trojan".to_string(),
+            flow: String::new(),
+            security: "tls".to_string(),
+            password: "pw".to_string(),
+            ..ServerConfig::default()
+        };
+        let config = generate_client_config(&server, 1080, &[], &[], None, &[]).unwrap();
+        let parsed: Value = serde_json::from_str(&config).unwrap();
+        let stream = &parsed["outbounds"][0]["streamSettings"];
+        assert_eq!(stream["network"], "tcp");
+        assert!(stream["tlsSettings"].get("alpn").is_none());
+        assert!(stream.get("grpcSettings").is_none());
+    }
+
+    #[test]
+    fn test_config_shadowsocks_2022() {
+        let server = ServerConfig {
+            protocol: "shadowsocks".to_string(),
+            address: "ss.example.com".to_string(),
+            port: 8388,
+            flow: String::new(),
+            security: "none".to_string(),
+            ss_method: "2022-blake3-aes-128-gcm".to_string(),
+            password: "AAAAAAAAAAAAAAAAAAAAAA==".to_string(),
+            ..ServerConfig::default()
+        };
+        let config = generate_client_config(&server, 1080, &[], &[], None, &[]).unwrap();
+        let parsed: Value = serde_json::from_str(&config).unwrap();
+        let proxy = &parsed["outbounds"][0];
+        assert_eq!(proxy["tag"], "proxy");
+        assert_eq!(proxy["protocol"], "shadowsocks");
+        let target = &proxy["settings"]["servers"][0];
+        assert_eq!(target["address"], "ss.example.com");
+        assert_eq!(target["port"], 8388);
+        assert_eq!(target["method"], "2022-blake3-aes-128-gcm");
+        assert_eq!(target["password"], "AAAAAAAAAAAAAAAAAAAAAA==");
+        let stream = &proxy["streamSettings"];
+        assert_eq!(stream["network"], "tcp");
+        assert!(stream.get("realitySettings").is_none());
+    }
+
+    #[test]
+    fn test_config_shadowsocks_v2ray_plugin_becomes_ws() {
+        let server = ServerConfig {
+            protocol: "shadowsocks".to_string(),
+            address: "1.2.3.4".to_string(),
+            flow: String::new(),
+            security: "none".to_string(),
+            ss_method: "aes-256-gcm".to_string(),
+            password: "pw".to_string(),
+            ss_plugin: "v2ray-plugin;tls;host=ss.example.com;path=/ws".to_string(),
+            ..ServerConfig::default()
+        };
+        let config = generate_client_config(&server, 1080, &[], &[], None, &[]).unwrap();
+        let parsed: Value = serde_json::from_str(&config).unwrap();
+        let stream = &parsed["outbounds"][0]["streamSettings"];
+        assert_eq!(stream["network"], "ws");
+        assert_eq!(stream["security"], "tls");
+        assert_eq!(stream["tlsSettings"]["serverName"], "ss.example.com");
+        assert_eq!(stream["wsSettings"]["path"], "/ws");
+        assert_eq!(stream["wsSettings"]["host"], "ss.example.com");
+    }
+
+    #[test]
+    fn test_config_shadowsocks_unsupported_plugin_errors() {
+        let server = ServerConfig {
+            protocol: "shadowsocks".to_string(),
+            ss_method: "aes-256-gcm".to_string(),
+            password: "pw".to_string(),
+            ss_plugin: "obfs-local;obfs=tls".to_string(),
+            ..ServerConfig::default()
+        };
+        assert!(generate_client_config(&server, 1080, &[], &[], None, &[]).is_err());
+    }
+
+    #[test]
+    fn test_config_hysteria2() {
+        let server = ServerConfig {
+            protocol: "hysteria2".to_string(),
+            address: "hy.example.com".to_string(),
+            port: 8443,
+            flow: String::new(),
+            security: "tls".to_string(),
+            password: "auth-secret".to_string(),
+            hy2_obfs_password: "salty".to_string(),
+            hy2_up_mbps: 50,
+            hy2_down_mbps: 200,
+            tls: TlsSettings {
+                server_name: String::new(),
+                ..TlsSettings::default()
+            },
+            ..ServerConfig::default()
+        };
+        let config = generate_client_config(&server, 1080, &[], &[], None, &[]).unwrap();
+        let parsed: Value = serde_json::from_str(&config).unwrap();
+        let proxy = &parsed["outbounds"][0];
+        assert_eq!(proxy["tag"], "proxy");
+        assert_eq!(proxy["protocol"], "hysteria");
+        assert_eq!(proxy["settings"]["version"], 2);
+        assert_eq!(proxy["settings"]["address"], "hy.example.com");
+        assert_eq!(proxy["settings"]["port"], 8443);
+
+        let stream = &proxy["streamSettings"];
+        assert_eq!(stream["network"], "hysteria");
+        assert_eq!(stream["security"], "tls");
+        // Empty SNI falls back to the server address.
+        assert_eq!(stream["tlsSettings"]["serverName"], "hy.example.com");
+        assert_eq!(stream["tlsSettings"]["alpn"], json!(["h3"]));
+        assert_eq!(stream["hysteriaSettings"]["auth"], "auth-secret");
+        assert_eq!(stream["hysteriaSettings"]["up"], "50 mbps");
+        assert_eq!(stream["hysteriaSettings"]["down"], "200 mbps");
+        let mask = &stream["finalmask"]["udp"][0];
+        assert_eq!(mask["type"], "salamander");
+        assert_eq!(mask["settings"]["password"], "salty");
+    }
+
+    #[test]
+    fn test_config_hysteria2_without_obfs_or_bandwidth() {
+        let server = ServerConfig {
+            protocol: "hysteria2".to_string(),
+            address: "1.2.3.4".to_string(),
+            security: "tls".to_string(),
+            password: "pw".to_string(),
+            ..ServerConfig::default()
+        };
+        let config = generate_client_config(&server, 1080, &[], &[], None, &[]).unwrap();
+        let parsed: Value = serde_json::from_str(&config).unwrap();
+        let stream = &parsed["outbounds"][0]["streamSettings"];
+        assert!(stream.get("finalmask").is_none());
+        assert!(stream["hysteriaSettings"].get("up").is_none());
+        assert!(stream["hysteriaSettings"].get("down").is_none());
+    }
+
+    #[test]
+    fn test_config_wireguard() {
+        let server = ServerConfig {
+            protocol: "wireguard".to_string(),
+            address: "2001:db8::1".to_string(),
+            port: 51820,
+            flow: String::new(),
+            security: "none".to_string(),
+            wg_private_key: "cHJpdmF0ZQ==".to_string(),
+            wg_peer_public_key: "cHVibGlj".to_string(),
+            wg_preshared_key: "cHNr".to_string(),
+            wg_local_addresses: vec!["10.8.0.2/32".to_string()],
+            wg_allowed_ips: vec!["10.0.0.0/8".to_string()],
+            wg_mtu: 1380,
+            wg_reserved: vec![1, 2, 3],
+            wg_keepalive: 25,
+            ..ServerConfig::default()
+        };
+        let config = generate_client_config(&server, 1080, &[], &[], None, &[]).unwrap();
+        let parsed: Value = serde_json::from_str(&config).unwrap();
+        let proxy = &parsed["outbounds"][0];
+        assert_eq!(proxy["tag"], "proxy");
+        assert_eq!(proxy["protocol"], "wireguard");
+        assert!(proxy.get("streamSettings").is_none());
+
+        let settings = &proxy["settings"];
+        assert_eq!(settings["secretKey"], "cHJpdmF0ZQ==");
+        assert_eq!(settings["address"], json!(["10.8.0.2/32"]));
+        assert_eq!(settings["mtu"], 1380);
+        assert_eq!(settings["reserved"], json!([1, 2, 3]));
+        let peer = &settings["peers"][0];
+        assert_eq!(peer["publicKey"], "cHVibGlj");
+        assert_eq!(peer["preSharedKey"], "cHNr");
+        assert_eq!(peer["endpoint"], "[2001:db8::1]:51820");
+        assert_eq!(peer["allowedIPs"], json!(["10.0.0.0/8"]));
+        assert_eq!(peer["keepAlive"], 25);
+
+        // An address that already has its brackets isn't wrapped twice.
+        let bracketed = ServerConfig {
+            address: "[2001:db8::1]".to_string(),
+            ..server
+        };
+        let config = generate_client_config(&bracketed, 1080, &[], &[], None, &[]).unwrap();
+        let parsed: Value = serde_json::from_str(&config).unwrap();
+        let peer = &parsed["outbounds"][0]["settings"]["peers"][0];
+        assert_eq!(peer["endpoint"], "[2001:db8::1]:51820");
+    }
+
+    #[test]
+    fn test_config_wireguard_defaults() {
+        let server = ServerConfig {
+            protocol: "wireguard".to_string(),
+            address: "wg.example.com".to_string(),
+            port: 51820,
+            wg_local_addresses: vec!["10.8.0.2/32".to_string()],
+            ..ServerConfig::default()
+        };
+        let config = generate_client_config(&server, 1080, &[], &[], None, &[]).unwrap();
+        let parsed: Value = serde_json::from_str(&config).unwrap();
+        let settings = &parsed["outbounds"][0]["settings"];
+        assert!(settings.get("mtu").is_none());
+        assert!(settings.get("reserved").is_none());
+        let peer = &settings["peers"][0];
+        assert_eq!(peer["endpoint"], "wg.example.com:51820");
+        assert_eq!(peer["allowedIPs"], json!(["0.0.0.0/0", "::/0"]));
+        assert!(peer.get("preSharedKey").is_none());
+        assert!(peer.get("keepAlive").is_none());
+    }
+
+    #[test]
+    fn test_config_vless_grpc_reality() {
+        let mut server = ServerConfig {
+            address: "1.2.3.4".to_string(),
+            uuid: "d6c5bf01-c90d-4094-bb27-cb8f966af8e4".to_string(),
+            reality: RealitySettings {
+                public_key: "pbk".to_string(),
+                short_id: "abcd".to_string(),
+                ..RealitySettings::default()
+            },
+            tls: TlsSettings {
+                server_name: "ya.ru".to_string(),
+                fingerprint: "chrome".to_string(),
+                ..TlsSettings::default()
+            },
+            ..ServerConfig::default()
+        };
+        server.network = "grpc".to_string();
+        server.grpc_service_name = "tunnel".to_string();
+        server.grpc_multi_mode = true;
+        let config = generate_client_config(&server, 1080, &[], &[], None, &[]).unwrap();
+        let parsed: Value = serde_json::from_str(&config).unwrap();
+        let proxy = &parsed["outbounds"][0];
+        let stream = &proxy["streamSettings"];
+        assert_eq!(stream["network"], "grpc");
+        assert_eq!(stream["security"], "reality");
+        assert_eq!(
+            stream["realitySettings"]["publicKey"],
+            server.reality.public_key
+        );
+        assert_eq!(stream["grpcSettings"]["serviceName"], "tunnel");
+        assert_eq!(stream["grpcSettings"]["multiMode"], true);
+        // Vision can't run inside gRPC framing.
+        assert_eq!(proxy["settings"]["vnext"][0]["users"][0]["flow"], "");
+    }
+
+    #[test]
+    fn test_config_vless_ws_tls_early_data() {
+        let mut server = ServerConfig {
+            address: "1.2.3.4".to_string(),
+            uuid: "d6c5bf01-c90d-4094-bb27-cb8f966af8e4".to_string(),
+            reality: RealitySettings {
+                public_key: "pbk".to_string(),
+                short_id: "abcd".to_string(),
+                ..RealitySettings::default()
+            },
+            tls: TlsSettings {
+                server_name: "ya.ru".to_string(),
+                fingerprint: "chrome".to_string(),
+                ..TlsSettings::default()
+            },
+            ..ServerConfig::default()
+        };
+        server.network = "ws".to_string();
+        server.security = "tls".to_string();
+        server.tls.server_name = "cdn.example.com".to_string();
+        server.ws_path = "/ws".to_string();
+        server.ws_early_data = 2048;
+        let config = generate_client_config(&server, 1080, &[], &[], None, &[]).unwrap();
+        let parsed: Value = serde_json::from_str(&config).unwrap();
+        let proxy = &parsed["outbounds"][0];
+        let stream = &proxy["streamSettings"];
+        assert_eq!(stream["network"], "ws");
+        assert_eq!(stream["security"], "tls");
+        assert!(stream.get("realitySettings").is_none());
+        assert_eq!(stream["tlsSettings"]["serverName"], "cdn.example.com");
+        assert_eq!(stream["wsSettings"]["path"], "/ws?ed=2048");
+        assert_eq!(stream["wsSettings"]["host"], "cdn.example.com");
+        assert_eq!(proxy["settings"]["vnext"][0]["users"][0]["flow"], "");
+    }
+
+    #[test]
+    fn test_config_vless_tcp_tls_keeps_vision() {
+        let server = ServerConfig {
+            address: "vpn.example.com".to_string(),
+            uuid: "d6c5bf01-c90d-4094-bb27-cb8f966af8e4".to_string(),
+            flow: "xtls-rprx-vision".to_string(),
+            security: "tls".to_string(),
+            tls: TlsSettings {
+                server_name: "vpn.example.com".to_string(),
+                alpn: vec!["h2".to_string(), "http/1.1".to_string()],
+                allow_insecure: true,
+                pinned_cert_sha256: vec!["AB:CD".to_string(), "ef01".to_string()],
+                ..TlsSettings::default()
+            },
+            ..ServerConfig::default()
+        };
+        let config = generate_client_config(&server, 1080, &[], &[], None, &[]).unwrap();
+        let parsed: Value = serde_json::from_str(&config).unwrap();
+        let proxy = &parsed["outbounds"][0];
+        let stream = &proxy["streamSettings"];
+        assert_eq!(stream["network"], "tcp");
+        assert_eq!(stream["security"], "tls");
+        assert!(stream.get("realitySettings").is_none());
+        let tls = &stream["tlsSettings"];
+        assert_eq!(tls["serverName"], "vpn.example.com");
+        assert_eq!(tls["fingerprint"], "chrome");
+        assert_eq!(tls["alpn"], json!(["h2", "http/1.1"]));
+        assert_eq!(tls["allowInsecure"], true);
+        assert_eq!(tls["pinnedPeerCertSha256"], "abcd,ef01");
+        assert_eq!(
+            proxy["settings"]["vnext"][0]["users"][0]["flow"],
+            "xtls-rprx-vision"
+        );
+    }
 }
@@ -1,3 +1,5 @@
+pub mod bulk;
+pub mod clash;
 pub mod commands;
 pub mod config;
 pub mod models;
@@ -5,12 +7,16 @@ pub mod models;
 pub mod network;
 #[cfg(desktop)]
 pub mod proxy;
+pub mod qr;
+pub mod singbox;
 pub mod storage;
+pub mod subscription;
 #[cfg(desktop)]
 pub mod tray;
 #[cfg(target_os = "linux")]
 pub mod tun;
 pub mod uri;
+pub mod wireguard;
 pub mod xray;
 
 use tauri::Manager;
@@ -56,6 +62,8 @@ pub fn run() {
 
             let handle = app.handle().clone();
 
+            subscription::spawn_refresh_loop(handle.clone());
+
             // Setup system tray (desktop only — mobile has no tray)
             #[cfg(desktop)]
             {
@@ -132,6 +140,7 @@ pub fn run() {
             commands::update_server,
             commands::delete_server,
             commands::export_servers,
+            commands::find_import_duplicates,
             commands::import_servers,
             commands::get_speed_stats,
             commands::get_logs,
@@ -139,8 +148,23 @@ pub fn run() {
             commands::get_settings,
             commands::update_settings,
             commands::apply_bypass_domains,
+            commands::get_subscriptions,
+            commands::add_subscription,
+            commands::update_subscription,
+            commands::delete_subscription,
+            commands::refresh_subscription,
+            commands::get_subscription_quota,
             uri::parse_vless_uri_cmd,
+            bulk::parse_share_text_cmd,
             uri::export_vless_uri,
+            singbox::export_singbox_outbound,
+            qr::export_qr_code,
+            qr::export_qr_bundle,
+            wireguard::parse_wireguard_conf_cmd,
+            clash::parse_clash_yaml_cmd,
+            singbox::parse_singbox_json_cmd,
+            config::parse_xray_config_cmd,
+            qr::parse_qr_image_cmd,
             commands::detect_vpn_interfaces,
             commands::is_battery_optimization_ignored,
             commands::request_ignore_battery_optimization,
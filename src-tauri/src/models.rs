@@ -1,3 +1,5 @@
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -17,6 +19,64 @@ fn default_xhttp_mode() -> String {
     "auto".to_string()
 }
 
+fn default_protocol() -> String {
+    "vless".to_string()
+}
+
+fn default_fingerprint() -> String {
+    "chrome".to_string()
+}
+
+fn default_vmess_cipher() -> String {
+    "auto".to_string()
+}
+
+fn default_vless_encryption() -> String {
+    "none".to_string()
+}
+
+fn default_mux_concurrency() -> i16 {
+    8
+}
+
+fn default_xudp_concurrency() -> i16 {
+    16
+}
+
+fn default_xudp_proxy_udp_443() -> String {
+    "reject".to_string()
+}
+
+/// Accepted `xudpProxyUDP443` policies for QUIC (UDP/443) under mux.
+const XUDP_UDP_443_POLICIES: [&str; 3] = ["reject", "allow", "skip"];
+
+/// Payload encodings xray's freedom outbound accepts for `noises[].type`.
+const NOISE_TYPES: [&str; 4] = ["rand", "str", "base64", "hex"];
+
+/// User ciphers accepted by xray's VMess outbound (`users[].security`).
+const VMESS_CIPHERS: [&str; 5] = ["auto", "aes-128-gcm", "chacha20-poly1305", "none", "zero"];
+
+/// Outbound protocols that don't proxy anything, so can't be a raw server.
+pub(crate) const NON_PROXY_OUTBOUNDS: [&str; 4] = ["freedom", "blackhole", "dns", "loopback"];
+
+/// Shadowsocks methods xray implements, legacy AEAD and SIP022 (2022) alike.
+/// First wait before retrying a subscription whose refresh failed; it doubles
+/// with each further failure up to [`REFRESH_RETRY_MAX_SECS`].
+const REFRESH_RETRY_BASE_SECS: u64 = 5 * 60;
+const REFRESH_RETRY_MAX_SECS: u64 = 6 * 3600;
+
+const SS_METHODS: [&str; 9] = [
+    "aes-128-gcm",
+    "aes-256-gcm",
+    "chacha20-poly1305",
+    "chacha20-ietf-poly1305",
+    "xchacha20-poly1305",
+    "xchacha20-ietf-poly1305",
+    "2022-blake3-aes-128-gcm",
+    "2022-blake3-aes-256-gcm",
+    "2022-blake3-chacha20-poly1305",
+];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerConfig {
     #[serde(default = "generate_id")]
@@ -27,8 +87,17 @@ pub struct ServerConfig {
     pub uuid: String,
     pub flow: String,
     pub reality: RealitySettings,
-    /// Transport: "tcp" (REALITY + XTLS-Vision) or "xhttp" (REALITY over XHTTP,
-    /// which disguises traffic as plain HTTP to survive DPI session-freezing).
+    /// ClientHello parameters (SNI, uTLS fingerprint, ALPN) plus certificate
+    /// checks. SNI and fingerprint apply to REALITY as well; the rest only to
+    /// real TLS.
+    #[serde(default)]
+    pub tls: TlsSettings,
+    /// Transport: "tcp" (raw TCP; the only one XTLS-Vision runs over), "xhttp"
+    /// (XHTTP, which disguises traffic as plain HTTP to survive DPI
+    /// session-freezing), "grpc" or "ws" (WebSocket). VLESS allows REALITY or
+    /// TLS over "tcp", "xhttp" and "grpc", but "ws" only with TLS, since
+    /// REALITY needs the raw TLS handshake. VMess and Trojan use "tcp", "ws"
+    /// or "grpc" with their own security rules (see `security`).
     #[serde(default = "default_network")]
     pub network: String,
     /// XHTTP request path; only meaningful when `network == "xhttp"`. Empty → "/".
@@ -36,23 +105,303 @@ pub struct ServerConfig {
     pub xhttp_path: String,
     /// Transport security: "reality" (default) or "tls". TLS is required for CDN
     /// fronting (Cloudflare terminates TLS, so REALITY can't be used through a
-    /// CDN). When "tls", `reality.server_name` is the TLS SNI / HTTP Host (the
-    /// CDN domain) and `reality.fingerprint` is the uTLS fingerprint;
-    /// `public_key`/`short_id` are unused.
+    /// CDN, though it also works directly over raw TCP). When "tls",
+    /// `tls.server_name` is the TLS SNI / HTTP Host and the `reality` keypair is
+    /// unused. VMess servers use "tls" or "none",
+    /// Trojan servers always "tls" (REALITY is VLESS-only).
     #[serde(default = "default_security")]
     pub security: String,
     /// XHTTP mode: "auto" (default), "stream-one", "stream-up", "packet-up".
     /// CDN (TLS) profiles default to "stream-one"; only meaningful for xhttp.
     #[serde(default = "default_xhttp_mode")]
     pub xhttp_mode: String,
+    /// Outbound protocol: "vless" (default), "vmess", "trojan", "shadowsocks",
+    /// "hysteria2", "wireguard" or "raw" (a hand-written xray outbound in
+    /// `raw_outbound`). Entries saved before this field existed are VLESS.
+    #[serde(default = "default_protocol")]
+    pub protocol: String,
+    /// VMess alterId. 0 selects AEAD authentication, the only mode xray still
+    /// speaks; non-zero values from old share links are passed through as-is.
+    #[serde(default)]
+    pub alter_id: u16,
+    /// VMess user cipher (`security` in xray's user object): "auto" (default),
+    /// "aes-128-gcm", "chacha20-poly1305", "none" or "zero".
+    #[serde(default = "default_vmess_cipher")]
+    pub vmess_cipher: String,
+    /// VLESS user `encryption`: "none" (default) or a post-quantum
+    /// `mlkem768x25519plus.<mode>.<rtt>[.padding...].<key>...` string copied
+    /// from the server's `xray vlessenc` output.
+    #[serde(default = "default_vless_encryption")]
+    pub vless_encryption: String,
+    /// WebSocket path; only meaningful when `network == "ws"`. Empty → "/".
+    #[serde(default)]
+    pub ws_path: String,
+    /// WebSocket Host header; empty → the TLS server name, or the address.
+    #[serde(default)]
+    pub ws_host: String,
+    /// Trojan / Shadowsocks password; used instead of `uuid`. For Shadowsocks
+    /// 2022 this is the base64 PSK, or `server_psk:user_psk` on multi-user
+    /// servers.
+    #[serde(default)]
+    pub password: String,
+    /// gRPC service name; only meaningful when `network == "grpc"`.
+    #[serde(default)]
+    pub grpc_service_name: String,
+    /// gRPC "multi" mode (several streams packed per HTTP/2 request) instead
+    /// of the default "gun" mode; only meaningful when `network == "grpc"`.
+    #[serde(default)]
+    pub grpc_multi_mode: bool,
+    /// WebSocket early data in bytes, sent with the upgrade request to save a
+    /// round trip (xray's `?ed=` path parameter); 0 = off.
+    #[serde(default)]
+    pub ws_early_data: u32,
+    /// Shadowsocks method, e.g. "aes-256-gcm" or "2022-blake3-aes-128-gcm".
+    #[serde(default)]
+    pub ss_method: String,
+    /// SIP003 plugin as it appears in a SIP002 link: `name;opt=value;...`.
+    /// Only the WebSocket mode of v2ray-plugin / xray-plugin can be expressed
+    /// in xray (as a ws transport); empty = no plugin.
+    #[serde(default)]
+    pub ss_plugin: String,
+    /// Hysteria2 salamander obfuscation password; empty = no obfuscation.
+    /// The auth string itself lives in `password`.
+    #[serde(default)]
+    pub hy2_obfs_password: String,
+    /// Hysteria2 bandwidth hints in Mbit/s. 0 lets the client fall back to
+    /// BBR congestion control instead of Brutal.
+    #[serde(default)]
+    pub hy2_up_mbps: u32,
+    #[serde(default)]
+    pub hy2_down_mbps: u32,
+    /// WireGuard interface private key (base64). The peer endpoint is
+    /// `address:port`.
+    #[serde(default)]
+    pub wg_private_key: String,
+    #[serde(default)]
+    pub wg_peer_public_key: String,
+    /// Optional peer preshared key (base64); empty = none.
+    #[serde(default)]
+    pub wg_preshared_key: String,
+    /// Interface addresses, e.g. ["10.8.0.2/32", "fd00::2/128"].
+    #[serde(default)]
+    pub wg_local_addresses: Vec<String>,
+    /// Peer AllowedIPs; empty → everything (0.0.0.0/0, ::/0).
+    #[serde(default)]
+    pub wg_allowed_ips: Vec<String>,
+    /// Tunnel MTU; 0 → xray's default (1420).
+    #[serde(default)]
+    pub wg_mtu: u16,
+    /// The three "reserved" header bytes some servers (Cloudflare WARP) use
+    /// as a client id; empty = zeros.
+    #[serde(default)]
+    pub wg_reserved: Vec<u8>,
+    /// PersistentKeepalive in seconds; 0 = off.
+    #[serde(default)]
+    pub wg_keepalive: u16,
+    /// Id of another saved server to dial this one through (a two-hop chain:
+    /// client → via → this server). `None` connects directly.
+    #[serde(default)]
+    pub via: Option<String>,
+    #[serde(default)]
+    pub mux: MuxSettings,
+    /// Split the first TCP packets (the TLS ClientHello) before they leave
+    /// the machine; off while `packets` is empty.
+    #[serde(default)]
+    pub fragment: FragmentSettings,
+    /// Junk UDP packets sent ahead of the real ones.
+    #[serde(default)]
+    pub noises: Vec<NoiseSettings>,
+    /// Advanced xHTTP tuning (xray's `xhttpSettings.extra`).
+    #[serde(default)]
+    pub xhttp_extra: XhttpExtra,
+    /// Id of the subscription this server was fetched from; a refresh
+    /// replaces these entries. `None` for servers added by hand.
+    #[serde(default)]
+    pub subscription_id: Option<String>,
+    /// The xray outbound object of a `protocol == "raw"` server, spliced into
+    /// the generated config as-is apart from its tag. `address` / `port`
+    /// mirror its server so routing can keep that connection out of the
+    /// tunnel.
+    #[serde(default)]
+    pub raw_outbound: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The WebSocket carrier described by a `v2ray-plugin` / `xray-plugin`
+/// option string, which xray reproduces with its own ws (+TLS) transport.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SsPluginTransport {
+    pub tls: bool,
+    pub host: String,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RealitySettings {
+    #[serde(default)]
     pub public_key: String,
+    #[serde(default)]
     pub short_id: String,
+    /// Further short IDs the server accepts. Each connect picks one of these
+    /// or `short_id` at random, so the profile keeps working when the
+    /// operator revokes one of them.
+    #[serde(default)]
+    pub extra_short_ids: Vec<String>,
+    /// spiderX: the path the client pretends to crawl on the camouflage site,
+    /// e.g. "/". Empty = xray's default.
+    #[serde(default)]
+    pub spider_x: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TlsSettings {
+    /// SNI; for TLS/CDN profiles also the HTTP Host (the CDN domain). Empty →
+    /// the server address.
+    #[serde(default)]
     pub server_name: String,
+    /// uTLS fingerprint, e.g. "chrome".
+    #[serde(default = "default_fingerprint")]
     pub fingerprint: String,
+    /// ALPN list (e.g. ["h2", "http/1.1"]); empty → transport default.
+    #[serde(default)]
+    pub alpn: Vec<String>,
+    /// Skip certificate verification. Only for self-signed test servers;
+    /// prefer `pinned_cert_sha256`.
+    #[serde(default)]
+    pub allow_insecure: bool,
+    /// Hex SHA-256 hashes of accepted server certificates. When set, a
+    /// matching certificate is trusted even if it doesn't chain to a CA.
+    #[serde(default)]
+    pub pinned_cert_sha256: Vec<String>,
+    #[serde(default)]
+    pub ech: EchSettings,
+}
+
+/// Encrypted Client Hello: hides the real SNI from on-path observers behind
+/// the CDN's public name. Off unless one of the two sources is set.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EchSettings {
+    /// Static base64 ECHConfigList, as published in the domain's HTTPS record.
+    #[serde(default)]
+    pub config_list: String,
+    /// DNS server to fetch the ECHConfigList from at connect time: DoH
+    /// ("https://1.1.1.1/dns-query") or plain DNS ("udp://1.1.1.1").
+    #[serde(default)]
+    pub doh_server: String,
+    /// Domain whose HTTPS record is queried; empty → the TLS server name.
+    #[serde(default)]
+    pub query_domain: String,
+}
+
+/// xray's Mux.Cool multiplexing: many client streams share one proxied
+/// connection, so opening a tab doesn't cost a fresh handshake.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MuxSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Max TCP streams per mux connection (1–1024); -1 leaves TCP un-muxed so
+    /// only UDP goes through XUDP, which is the only mode XTLS Vision allows.
+    #[serde(default = "default_mux_concurrency")]
+    pub concurrency: i16,
+    /// Max UDP sessions per XUDP connection (1–1024); -1 disables XUDP.
+    #[serde(default = "default_xudp_concurrency")]
+    pub xudp_concurrency: i16,
+    /// QUIC (UDP/443) handling: "reject" makes browsers fall back to TCP,
+    /// "allow" muxes it, "skip" sends it over a plain connection.
+    #[serde(default = "default_xudp_proxy_udp_443")]
+    pub xudp_proxy_udp_443: String,
+}
+
+/// freedom-outbound `fragment` options. Ranges are xray's "min-max" strings.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FragmentSettings {
+    /// "tlshello" to split only the ClientHello, or a packet range like "1-3".
+    #[serde(default)]
+    pub packets: String,
+    /// Fragment size in bytes, e.g. "100-200".
+    #[serde(default)]
+    pub length: String,
+    /// Pause between fragments in ms, e.g. "10-20".
+    #[serde(default)]
+    pub interval: String,
+}
+
+impl FragmentSettings {
+    pub fn is_enabled(&self) -> bool {
+        !self.packets.trim().is_empty()
+    }
+}
+
+/// The `extra` block of xray's xhttpSettings. Numeric knobs are xray range
+/// strings ("100-1000" or "16"); empty leaves xray's default.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct XhttpExtra {
+    /// Random padding added to each request, e.g. "100-1000".
+    #[serde(default)]
+    pub x_padding_bytes: String,
+    #[serde(default)]
+    pub no_grpc_header: bool,
+    /// Max body size of one upload POST (packet-up mode).
+    #[serde(default)]
+    pub sc_max_each_post_bytes: String,
+    /// Min gap between upload POSTs in ms (packet-up mode).
+    #[serde(default)]
+    pub sc_min_posts_interval_ms: String,
+    #[serde(default)]
+    pub xmux: XmuxSettings,
+    /// Fetch the download half from a different endpoint, e.g. upload through
+    /// the CDN and download over REALITY directly.
+    #[serde(default)]
+    pub download: Option<XhttpDownload>,
+}
+
+/// xHTTP connection reuse limits (`extra.xmux`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct XmuxSettings {
+    #[serde(default)]
+    pub max_concurrency: String,
+    #[serde(default)]
+    pub max_connections: String,
+    #[serde(default)]
+    pub c_max_reuse_times: String,
+    #[serde(default)]
+    pub h_max_request_times: String,
+    #[serde(default)]
+    pub h_max_reusable_secs: String,
+    /// HTTP keep-alive ping period in seconds; 0 = xray's default.
+    #[serde(default)]
+    pub h_keep_alive_period: i64,
+}
+
+/// Endpoint for the download half of a split xHTTP connection
+/// (`extra.downloadSettings`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct XhttpDownload {
+    pub address: String,
+    pub port: u16,
+    /// "reality" or "tls".
+    #[serde(default = "default_security")]
+    pub security: String,
+    /// Empty = the upload path.
+    #[serde(default)]
+    pub path: String,
+    #[serde(default)]
+    pub tls: TlsSettings,
+    #[serde(default)]
+    pub reality: RealitySettings,
+}
+
+/// One freedom-outbound `noises` entry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoiseSettings {
+    /// "rand", "str", "base64" or "hex".
+    #[serde(rename = "type")]
+    pub noise_type: String,
+    /// A byte-length range for "rand", otherwise the payload itself.
+    #[serde(default)]
+    pub packet: String,
+    /// Delay in ms after sending it, e.g. "10-16"; empty = none.
+    #[serde(default)]
+    pub delay: String,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -72,6 +421,10 @@ pub struct ConnectionInfo {
     pub server_address: Option<String>,
     pub connected_since: Option<u64>,
     pub error_message: Option<String>,
+    /// Server names from the first hop to the exit; a single entry when the
+    /// server isn't chained.
+    #[serde(default)]
+    pub chain: Vec<String>,
 }
 
 impl Default for ConnectionInfo {
@@ -82,6 +435,7 @@ impl Default for ConnectionInfo {
             server_address: None,
             connected_since: None,
             error_message: None,
+            chain: Vec::new(),
         }
     }
 }
@@ -96,6 +450,135 @@ impl ServerConfig {
             return Err("Server port must be greater than 0".to_string());
         }
 
+        if self.security == "tls" {
+            self.tls.validate()?;
+        }
+
+        if self.mux.enabled {
+            self.validate_mux()?;
+        }
+
+        self.validate_fragment()?;
+
+        match self.protocol.as_str() {
+            "vless" => self.validate_vless(),
+            "vmess" => self.validate_vmess(),
+            "trojan" => self.validate_trojan(),
+            "shadowsocks" => self.validate_shadowsocks(),
+            "hysteria2" => self.validate_hysteria2(),
+            "wireguard" => self.validate_wireguard(),
+            "raw" => self.validate_raw(),
+            other => Err(format!(
+                "Unsupported protocol '{other}' (expected 'vless', 'vmess', 'trojan', 'shadowsocks', 'hysteria2', 'wireguard' or 'raw')"
+            )),
+        }
+    }
+
+    fn validate_mux(&self) -> Result<(), String> {
+        if matches!(self.protocol.as_str(), "hysteria2" | "wireguard") {
+            return Err(format!("Mux is not supported for {}", self.protocol));
+        }
+        if self.protocol == "raw" {
+            return Err("Set mux inside the raw outbound JSON instead".to_string());
+        }
+        let in_range = |n: i16| n == -1 || (1..=1024).contains(&n);
+        if !in_range(self.mux.concurrency) {
+            return Err("Mux concurrency must be between 1 and 1024, or -1".to_string());
+        }
+        if !in_range(self.mux.xudp_concurrency) {
+            return Err("XUDP concurrency must be between 1 and 1024, or -1".to_string());
+        }
+        if !XUDP_UDP_443_POLICIES.contains(&self.mux.xudp_proxy_udp_443.as_str()) {
+            return Err(format!(
+                "Unsupported xudpProxyUDP443 '{}' (expected 'reject', 'allow' or 'skip')",
+                self.mux.xudp_proxy_udp_443
+            ));
+        }
+        // xray refuses to start a Vision outbound that muxes TCP.
+        if self.uses_vision() && self.mux.concurrency != -1 {
+            return Err(
+                "XTLS Vision flow can't multiplex TCP; set mux concurrency to -1 (XUDP only)"
+                    .to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    fn validate_fragment(&self) -> Result<(), String> {
+        let fragment = &self.fragment;
+        if fragment.is_enabled() {
+            let packets = fragment.packets.trim();
+            if packets != "tlshello" && !is_range(packets) {
+                return Err(format!(
+                    "Fragment packets '{packets}' must be 'tlshello' or a range like '1-3'"
+                ));
+            }
+            for (label, value) in [
+                ("length", &fragment.length),
+                ("interval", &fragment.interval),
+            ] {
+                if !is_range(value.trim()) {
+                    return Err(format!(
+                        "Fragment {label} '{value}' must be a number or range like '10-20'"
+                    ));
+                }
+            }
+        }
+        for noise in &self.noises {
+            if !NOISE_TYPES.contains(&noise.noise_type.as_str()) {
+                return Err(format!(
+                    "Unsupported noise type '{}' (expected 'rand', 'str', 'base64' or 'hex')",
+                    noise.noise_type
+                ));
+            }
+            if noise.noise_type == "rand" && !is_range(noise.packet.trim()) {
+                return Err(format!(
+                    "Random noise length '{}' must be a number or range like '10-20'",
+                    noise.packet
+                ));
+            }
+            if noise.packet.trim().is_empty() {
+                return Err("Noise packet must not be empty".to_string());
+            }
+            if !noise.delay.trim().is_empty() && !is_range(noise.delay.trim()) {
+                return Err(format!(
+                    "Noise delay '{}' must be a number or range like '10-16'",
+                    noise.delay
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The VLESS `encryption` value as xray expects it; blank means "none".
+    pub fn vless_encryption(&self) -> &str {
+        match self.vless_encryption.trim() {
+            "" => "none",
+            encryption => encryption,
+        }
+    }
+
+    /// Every address this server opens connections to: the server itself,
+    /// plus the download endpoint of a split xHTTP profile.
+    pub fn dial_addresses(&self) -> Vec<&str> {
+        let mut addresses = vec![self.address.as_str()];
+        if self.network == "xhttp" {
+            if let Some(download) = &self.xhttp_extra.download {
+                addresses.push(download.address.as_str());
+            }
+        }
+        addresses
+    }
+
+    /// Whether the VLESS Vision flow is actually in effect; it only applies
+    /// over raw TCP and is dropped for the other transports.
+    pub fn uses_vision(&self) -> bool {
+        self.protocol == "vless"
+            && self.network == "tcp"
+            && self.flow.starts_with("xtls-rprx-vision")
+    }
+
+    fn validate_vless(&self) -> Result<(), String> {
         if !Self::is_valid_uuid(&self.uuid) {
             return Err(
                 "UUID must match format xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx (hex characters)"
@@ -103,10 +586,12 @@ impl ServerConfig {
             );
         }
 
+        Self::validate_vless_encryption(self.vless_encryption.trim())?;
+
         if self.security == "tls" {
             // CDN/TLS mode: server_name is the SNI/Host (CDN domain) and is
             // required; the REALITY keypair is unused, so it is not validated.
-            if self.reality.server_name.trim().is_empty() {
+            if self.tls.server_name.trim().is_empty() {
                 return Err("TLS server name (CDN domain) must not be empty".to_string());
             }
         } else {
@@ -117,11 +602,75 @@ impl ServerConfig {
             if self.reality.short_id.trim().is_empty() {
                 return Err("Reality short_id must not be empty".to_string());
             }
+
+            for short_id in &self.reality.extra_short_ids {
+                let short_id = short_id.trim();
+                if short_id.len() > 16
+                    || short_id.len() % 2 != 0
+                    || !short_id.chars().all(|c| c.is_ascii_hexdigit())
+                {
+                    return Err(format!(
+                        "Reality short ID '{short_id}' must be hex, even-length and at most 16 characters"
+                    ));
+                }
+            }
+
+            if !self.reality.spider_x.is_empty() && !self.reality.spider_x.starts_with('/') {
+                return Err("Reality spiderX must start with '/'".to_string());
+            }
+        }
+
+        if self.network == "xhttp" {
+            self.xhttp_extra.validate()?;
+        }
+
+        match self.network.as_str() {
+            "tcp" | "xhttp" | "grpc" => {}
+            // REALITY needs the raw TLS handshake, which a WebSocket upgrade
+            // can't provide.
+            "ws" if self.security == "tls" => {}
+            "ws" => {
+                return Err(
+                    "WebSocket transport requires TLS security (REALITY does not support ws)"
+                        .to_string(),
+                )
+            }
+            other => {
+                return Err(format!(
+                    "Unsupported transport '{other}' (expected 'tcp', 'xhttp', 'grpc' or 'ws')"
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_vmess(&self) -> Result<(), String> {
+        if !Self::is_valid_uuid(&self.uuid) {
+            return Err(
+                "UUID must match format xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx (hex characters)"
+                    .to_string(),
+            );
+        }
+
+        if !VMESS_CIPHERS.contains(&self.vmess_cipher.as_str()) {
+            return Err(format!(
+                "Unsupported VMess cipher '{}' (expected one of: {})",
+                self.vmess_cipher,
+                VMESS_CIPHERS.join(", ")
+            ));
+        }
+
+        if self.security != "tls" && self.security != "none" {
+            return Err(format!(
+                "Unsupported VMess security '{}' (expected 'tls' or 'none'; REALITY requires VLESS)",
+                self.security
+            ));
         }
 
-        if self.network != "tcp" && self.network != "xhttp" {
+        if !matches!(self.network.as_str(), "tcp" | "ws" | "grpc") {
             return Err(format!(
-                "Unsupported transport '{}' (expected 'tcp' or 'xhttp')",
+                "Unsupported VMess transport '{}' (expected 'tcp', 'ws' or 'grpc')",
                 self.network
             ));
         }
@@ -129,80 +678,984 @@ impl ServerConfig {
         Ok(())
     }
 
-    fn is_valid_uuid(s: &str) -> bool {
-        // Expected format: 8-4-4-4-12 hex chars separated by hyphens
-        let parts: Vec<&str> = s.split('-').collect();
-        if parts.len() != 5 {
-            return false;
+    fn validate_trojan(&self) -> Result<(), String> {
+        if self.password.is_empty() {
+            return Err("Trojan password must not be empty".to_string());
         }
 
-        let expected_lengths = [8, 4, 4, 4, 12];
-        for (part, &expected_len) in parts.iter().zip(&expected_lengths) {
-            if part.len() != expected_len || !part.chars().all(|c| c.is_ascii_hexdigit()) {
-                return false;
-            }
+        if self.security != "tls" {
+            return Err(format!(
+                "Unsupported Trojan security '{}' (expected 'tls')",
+                self.security
+            ));
         }
 
-        true
+        if !matches!(self.network.as_str(), "tcp" | "ws" | "grpc") {
+            return Err(format!(
+                "Unsupported Trojan transport '{}' (expected 'tcp', 'ws' or 'grpc')",
+                self.network
+            ));
+        }
+
+        Ok(())
     }
-}
 
-impl Default for RealitySettings {
-    fn default() -> Self {
-        Self {
-            public_key: String::new(),
-            short_id: String::new(),
-            server_name: "www.google.com".to_string(),
-            fingerprint: "chrome".to_string(),
+    fn validate_shadowsocks(&self) -> Result<(), String> {
+        if !SS_METHODS.contains(&self.ss_method.as_str()) {
+            return Err(format!(
+                "Unsupported Shadowsocks method '{}' (expected one of: {})",
+                self.ss_method,
+                SS_METHODS.join(", ")
+            ));
+        }
+
+        if self.password.is_empty() {
+            return Err("Shadowsocks password must not be empty".to_string());
         }
+
+        // SIP022 keys are raw PSKs, not passphrases: each colon-separated key
+        // (server PSK, then the user PSK on multi-user servers) must decode to
+        // exactly the cipher's key size.
+        if let Some(cipher) = self.ss_method.strip_prefix("2022-blake3-") {
+            let key_len = if cipher == "aes-128-gcm" { 16 } else { 32 };
+            for key in self.password.split(':') {
+                match STANDARD.decode(key) {
+                    Ok(bytes) if bytes.len() == key_len => {}
+                    _ => {
+                        return Err(format!(
+                            "Shadowsocks 2022 key '{key}' must be base64 of {key_len} bytes"
+                        ))
+                    }
+                }
+            }
+        }
+
+        self.ss_plugin_transport().map(|_| ())
     }
-}
 
-impl Default for ServerConfig {
-    fn default() -> Self {
-        Self {
-            id: generate_id(),
-            name: String::new(),
-            address: String::new(),
-            port: 443,
-            uuid: String::new(),
-            flow: "xtls-rprx-vision".to_string(),
-            reality: RealitySettings::default(),
-            network: default_network(),
-            xhttp_path: String::new(),
-            security: default_security(),
-            xhttp_mode: default_xhttp_mode(),
+    fn validate_hysteria2(&self) -> Result<(), String> {
+        if self.password.is_empty() {
+            return Err("Hysteria2 auth password must not be empty".to_string());
+        }
+
+        // Hysteria2 is QUIC, which always carries TLS 1.3; there is no
+        // plaintext or REALITY variant.
+        if self.security != "tls" {
+            return Err(format!(
+                "Unsupported Hysteria2 security '{}' (expected 'tls')",
+                self.security
+            ));
         }
+
+        Ok(())
     }
-}
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
-pub struct SpeedStats {
-    pub upload_speed: u64,
-    pub download_speed: u64,
-    pub total_upload: u64,
-    pub total_download: u64,
-}
+    fn validate_wireguard(&self) -> Result<(), String> {
+        if !Self::is_wg_key(&self.wg_private_key) {
+            return Err("WireGuard private key must be base64 of 32 bytes".to_string());
+        }
 
-/// A detected VPN interface with its routed subnets.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DetectedVpn {
-    /// Interface name (e.g. "tun0", "wg0")
-    pub interface: String,
-    /// Human-readable VPN type (e.g. "OpenVPN", "WireGuard")
-    pub vpn_type: String,
-    /// Subnets routed through this interface (e.g. ["10.8.0.0/24", "172.20.0.0/16"])
-    pub subnets: Vec<String>,
-    /// VPN server endpoint IP if detected
-    pub server_ip: Option<String>,
-}
+        if !Self::is_wg_key(&self.wg_peer_public_key) {
+            return Err("WireGuard peer public key must be base64 of 32 bytes".to_string());
+        }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+        if !self.wg_preshared_key.is_empty() && !Self::is_wg_key(&self.wg_preshared_key) {
+            return Err("WireGuard preshared key must be base64 of 32 bytes".to_string());
+        }
+
+        if self.wg_local_addresses.is_empty() {
+            return Err("WireGuard interface address must not be empty".to_string());
+        }
+
+        if !self.wg_reserved.is_empty() && self.wg_reserved.len() != 3 {
+            return Err(format!(
+                "WireGuard reserved must be exactly 3 bytes (got {})",
+                self.wg_reserved.len()
+            ));
+        }
+
+        // 1280 is the IPv6 minimum; anything smaller can't carry the tunnel.
+        if self.wg_mtu != 0 && self.wg_mtu < 1280 {
+            return Err(format!(
+                "WireGuard MTU {} is too small (minimum 1280)",
+                self.wg_mtu
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn validate_raw(&self) -> Result<(), String> {
+        let Some(outbound) = self.raw_outbound.as_ref().and_then(|o| o.as_object()) else {
+            return Err("Raw outbound must be a JSON object".to_string());
+        };
+        let protocol = outbound
+            .get("protocol")
+            .and_then(|p| p.as_str())
+            .unwrap_or_default()
+            .trim();
+        if protocol.is_empty() {
+            return Err("Raw outbound must have a 'protocol'".to_string());
+        }
+        if NON_PROXY_OUTBOUNDS.contains(&protocol) {
+            return Err(format!("'{protocol}' is not a proxy outbound"));
+        }
+        for key in ["settings", "streamSettings", "mux"] {
+            if outbound.get(key).is_some_and(|v| !v.is_object()) {
+                return Err(format!("Raw outbound '{key}' must be an object"));
+            }
+        }
+        // Other outbounds' tags don't exist in the generated config.
+        if outbound.contains_key("proxySettings")
+            || outbound
+                .get("streamSettings")
+                .and_then(|s| s.get("sockopt"))
+                .and_then(|s| s.get("dialerProxy"))
+                .is_some()
+        {
+            return Err(
+                "Raw outbound must not dial through another outbound (proxySettings / dialerProxy)"
+                    .to_string(),
+            );
+        }
+        if let Some((address, port)) = self.raw_outbound.as_ref().and_then(outbound_endpoint) {
+            if address != self.address.trim() || port != self.port {
+                return Err(format!(
+                    "Server address must match the raw outbound's {address}:{port}"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn is_wg_key(s: &str) -> bool {
+        matches!(STANDARD.decode(s.trim()), Ok(bytes) if bytes.len() == 32)
+    }
+
+    /// Translate `ss_plugin` into the ws transport xray uses in its place.
+    /// `Ok(None)` means no plugin; unsupported plugins are an error.
+    pub fn ss_plugin_transport(&self) -> Result<Option<SsPluginTransport>, String> {
+        let plugin = self.ss_plugin.trim();
+        if plugin.is_empty() {
+            return Ok(None);
+        }
+        let mut parts = plugin.split(';').map(str::trim);
+        let name = parts.next().unwrap_or_default();
+        if name != "v2ray-plugin" && name != "xray-plugin" {
+            return Err(format!(
+                "Unsupported Shadowsocks plugin '{name}' (only v2ray-plugin/xray-plugin in websocket mode)"
+            ));
+        }
+
+        let mut transport = SsPluginTransport {
+            tls: false,
+            host: String::new(),
+            path: String::new(),
+        };
+        for opt in parts.filter(|o| !o.is_empty()) {
+            match opt.split_once('=') {
+                Some(("mode", "websocket")) => {}
+                Some(("mode", mode)) => {
+                    return Err(format!("Unsupported {name} mode '{mode}' (only websocket)"))
+                }
+                Some(("host", host)) => transport.host = host.to_string(),
+                Some(("path", path)) => transport.path = path.to_string(),
+                None if opt == "tls" => transport.tls = true,
+                // mux, loglevel etc. have no xray equivalent and don't change
+                // the wire format enough to matter.
+                _ => {}
+            }
+        }
+        Ok(Some(transport))
+    }
+
+    /// Check a VLESS `encryption` string: "none", or xray's
+    /// `mlkem768x25519plus.<native|xorpub|random>.<1rtt|0rtt>` followed by
+    /// optional padding specs ("100-111-1111") and at least one base64url
+    /// key (X25519 password or ML-KEM-768 client key).
+    fn validate_vless_encryption(encryption: &str) -> Result<(), String> {
+        if encryption.is_empty() || encryption == "none" {
+            return Ok(());
+        }
+        let invalid = || {
+            format!(
+                "VLESS encryption must be 'none' or 'mlkem768x25519plus.<mode>.<rtt>...<key>', got '{encryption}'"
+            )
+        };
+        let mut parts = encryption.split('.');
+        if parts.next() != Some("mlkem768x25519plus") {
+            return Err(invalid());
+        }
+        if !matches!(parts.next(), Some("native" | "xorpub" | "random")) {
+            return Err(invalid());
+        }
+        if !matches!(parts.next(), Some("1rtt" | "0rtt")) {
+            return Err(invalid());
+        }
+        let rest: Vec<&str> = parts.collect();
+        let is_padding =
+            |p: &str| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit() || c == '-');
+        let keys: Vec<&str> = rest.iter().copied().skip_while(|p| is_padding(p)).collect();
+        if keys.is_empty() {
+            return Err("VLESS encryption is missing the server key".to_string());
+        }
+        for key in keys {
+            if URL_SAFE_NO_PAD.decode(key).is_err() {
+                return Err(format!(
+                    "VLESS encryption key '{key}' is not valid base64url"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn is_valid_uuid(s: &str) -> bool {
+        // Expected format: 8-4-4-4-12 hex chars separated by hyphens
+        let parts: Vec<&str> = s.split('-').collect();
+        if parts.len() != 5 {
+            return false;
+        }
+
+        let expected_lengths = [8, 4, 4, 4, 12];
+        for (part, &expected_len) in parts.iter().zip(&expected_lengths) {
+            if part.len() != expected_len || !part.chars().all(|c| c.is_ascii_hexdigit()) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+impl RealitySettings {
+    /// Every configured short ID, `short_id` first.
+    pub fn short_ids(&self) -> Vec<&str> {
+        std::iter::once(&self.short_id)
+            .chain(&self.extra_short_ids)
+            .map(|id| id.trim())
+            .filter(|id| !id.is_empty())
+            .collect()
+    }
+
+    /// A short ID for one connection attempt, picked at random.
+    pub fn pick_short_id(&self) -> &str {
+        let ids = self.short_ids();
+        match ids.len() {
+            0 => "",
+            1 => ids[0],
+            n => ids[(uuid::Uuid::new_v4().as_u128() % n as u128) as usize],
+        }
+    }
+}
+
+impl TlsSettings {
+    fn validate(&self) -> Result<(), String> {
+        for pin in self.pinned_hashes() {
+            if pin.len() != 64 || !pin.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!(
+                    "Pinned certificate hash '{pin}' must be a hex SHA-256 (64 characters)"
+                ));
+            }
+        }
+        self.ech.validate()
+    }
+
+    /// `pinned_cert_sha256` normalized to bare lowercase hex, so hashes pasted
+    /// from `openssl x509 -fingerprint` (colon-separated) work as-is.
+    pub fn pinned_hashes(&self) -> Vec<String> {
+        self.pinned_cert_sha256
+            .iter()
+            .map(|pin| pin.trim().replace(':', "").to_ascii_lowercase())
+            .filter(|pin| !pin.is_empty())
+            .collect()
+    }
+}
+
+impl Default for MuxSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            concurrency: default_mux_concurrency(),
+            xudp_concurrency: default_xudp_concurrency(),
+            xudp_proxy_udp_443: default_xudp_proxy_udp_443(),
+        }
+    }
+}
+
+impl Default for TlsSettings {
+    fn default() -> Self {
+        Self {
+            server_name: "www.google.com".to_string(),
+            fingerprint: default_fingerprint(),
+            alpn: Vec::new(),
+            allow_insecure: false,
+            pinned_cert_sha256: Vec::new(),
+            ech: EchSettings::default(),
+        }
+    }
+}
+
+impl EchSettings {
+    /// xray's `echConfigList` value: the static list, or
+    /// `[domain+]https://resolver` (or `udp://`) to fetch it over DNS. None
+    /// when ECH is off.
+    pub fn to_xray(&self) -> Option<String> {
+        let config_list = self.config_list.trim();
+        let doh_server = self.doh_server.trim();
+        let query_domain = self.query_domain.trim();
+        if !config_list.is_empty() {
+            Some(config_list.to_string())
+        } else if doh_server.is_empty() {
+            None
+        } else if query_domain.is_empty() {
+            Some(doh_server.to_string())
+        } else {
+            Some(format!("{query_domain}+{doh_server}"))
+        }
+    }
+
+    /// Inverse of [`EchSettings::to_xray`], as found in share links' `ech=`.
+    pub fn from_xray(value: &str) -> Self {
+        let value = value.trim();
+        if !value.contains("://") {
+            return Self {
+                config_list: value.to_string(),
+                ..Self::default()
+            };
+        }
+        match value.split_once('+') {
+            Some((domain, server)) if !domain.contains("://") => Self {
+                doh_server: server.to_string(),
+                query_domain: domain.to_string(),
+                ..Self::default()
+            },
+            _ => Self {
+                doh_server: value.to_string(),
+                ..Self::default()
+            },
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let config_list = self.config_list.trim();
+        let doh_server = self.doh_server.trim();
+        if !config_list.is_empty() && !doh_server.is_empty() {
+            return Err("Set either an ECH config list or an ECH DNS server, not both".to_string());
+        }
+        if !config_list.is_empty() && STANDARD.decode(config_list).is_err() {
+            return Err("ECH config list must be base64".to_string());
+        }
+        if !doh_server.is_empty()
+            && !doh_server.starts_with("https://")
+            && !doh_server.starts_with("udp://")
+        {
+            return Err(format!(
+                "ECH DNS server '{doh_server}' must be an https:// or udp:// URL"
+            ));
+        }
+        if !self.query_domain.trim().is_empty() && doh_server.is_empty() {
+            return Err("ECH query domain needs a DNS server".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            id: generate_id(),
+            name: String::new(),
+            address: String::new(),
+            port: 443,
+            uuid: String::new(),
+            flow: "xtls-rprx-vision".to_string(),
+            reality: RealitySettings::default(),
+            tls: TlsSettings::default(),
+            network: default_network(),
+            xhttp_path: String::new(),
+            security: default_security(),
+            xhttp_mode: default_xhttp_mode(),
+            protocol: default_protocol(),
+            alter_id: 0,
+            vmess_cipher: default_vmess_cipher(),
+            vless_encryption: default_vless_encryption(),
+            ws_path: String::new(),
+            ws_host: String::new(),
+            password: String::new(),
+            grpc_service_name: String::new(),
+            grpc_multi_mode: false,
+            ws_early_data: 0,
+            ss_method: String::new(),
+            ss_plugin: String::new(),
+            hy2_obfs_password: String::new(),
+            hy2_up_mbps: 0,
+            hy2_down_mbps: 0,
+            wg_private_key: String::new(),
+            wg_peer_public_key: String::new(),
+            wg_preshared_key: String::new(),
+            wg_local_addresses: Vec::new(),
+            wg_allowed_ips: Vec::new(),
+            wg_mtu: 0,
+            wg_reserved: Vec::new(),
+            wg_keepalive: 0,
+            via: None,
+            mux: MuxSettings::default(),
+            fragment: FragmentSettings::default(),
+            noises: Vec::new(),
+            xhttp_extra: XhttpExtra::default(),
+            subscription_id: None,
+            raw_outbound: None,
+        }
+    }
+}
+
+impl XhttpExtra {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let ranges = [
+            ("xPaddingBytes", &self.x_padding_bytes),
+            ("scMaxEachPostBytes", &self.sc_max_each_post_bytes),
+            ("scMinPostsIntervalMs", &self.sc_min_posts_interval_ms),
+            ("xmux.maxConcurrency", &self.xmux.max_concurrency),
+            ("xmux.maxConnections", &self.xmux.max_connections),
+            ("xmux.cMaxReuseTimes", &self.xmux.c_max_reuse_times),
+            ("xmux.hMaxRequestTimes", &self.xmux.h_max_request_times),
+            ("xmux.hMaxReusableSecs", &self.xmux.h_max_reusable_secs),
+        ];
+        for (key, value) in ranges {
+            if !value.trim().is_empty() && !is_range(value.trim()) {
+                return Err(format!(
+                    "xHTTP {key} '{value}' must be a number or range like '100-1000'"
+                ));
+            }
+        }
+        if let Some(download) = &self.download {
+            if download.address.trim().is_empty() || download.port == 0 {
+                return Err("xHTTP download address and port must be set".to_string());
+            }
+            match download.security.as_str() {
+                "tls" => download.tls.validate()?,
+                "reality" => {
+                    if download.reality.public_key.trim().is_empty()
+                        || download.reality.short_id.trim().is_empty()
+                    {
+                        return Err(
+                            "xHTTP REALITY download needs a public_key and short_id".to_string()
+                        );
+                    }
+                }
+                other => {
+                    return Err(format!(
+                    "Unsupported xHTTP download security '{other}' (expected 'reality' or 'tls')"
+                ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The block in xray's own camelCase form, as it goes into
+    /// `xhttpSettings.extra` and share links' `extra=` parameter. Unset knobs
+    /// are left out.
+    pub fn to_xray(&self) -> serde_json::Value {
+        use serde_json::{json, Map, Value};
+
+        fn put_range(obj: &mut Map<String, Value>, key: &str, value: &str) {
+            let value = value.trim();
+            if !value.is_empty() {
+                // xray takes plain numbers or "min-max" strings.
+                let value = value
+                    .parse::<i64>()
+                    .map(Value::from)
+                    .unwrap_or_else(|_| Value::from(value));
+                obj.insert(key.to_string(), value);
+            }
+        }
+
+        let mut extra = Map::new();
+        put_range(&mut extra, "xPaddingBytes", &self.x_padding_bytes);
+        if self.no_grpc_header {
+            extra.insert("noGRPCHeader".to_string(), json!(true));
+        }
+        put_range(
+            &mut extra,
+            "scMaxEachPostBytes",
+            &self.sc_max_each_post_bytes,
+        );
+        put_range(
+            &mut extra,
+            "scMinPostsIntervalMs",
+            &self.sc_min_posts_interval_ms,
+        );
+
+        let mut xmux = Map::new();
+        put_range(&mut xmux, "maxConcurrency", &self.xmux.max_concurrency);
+        put_range(&mut xmux, "maxConnections", &self.xmux.max_connections);
+        put_range(&mut xmux, "cMaxReuseTimes", &self.xmux.c_max_reuse_times);
+        put_range(
+            &mut xmux,
+            "hMaxRequestTimes",
+            &self.xmux.h_max_request_times,
+        );
+        put_range(
+            &mut xmux,
+            "hMaxReusableSecs",
+            &self.xmux.h_max_reusable_secs,
+        );
+        if self.xmux.h_keep_alive_period != 0 {
+            xmux.insert(
+                "hKeepAlivePeriod".to_string(),
+                json!(self.xmux.h_keep_alive_period),
+            );
+        }
+        if !xmux.is_empty() {
+            extra.insert("xmux".to_string(), Value::Object(xmux));
+        }
+
+        if let Some(download) = &self.download {
+            let mut settings = json!({
+                "address": download.address,
+                "port": download.port,
+                "network": "xhttp",
+                "security": download.security
+            });
+            if download.security == "tls" {
+                let mut tls = json!({
+                    "serverName": download.tls.server_name,
+                    "fingerprint": download.tls.fingerprint
+                });
+                if !download.tls.alpn.is_empty() {
+                    tls["alpn"] = json!(download.tls.alpn);
+                }
+                settings["tlsSettings"] = tls;
+            } else {
+                settings["realitySettings"] = json!({
+                    "serverName": download.tls.server_name,
+                    "fingerprint": download.tls.fingerprint,
+                    "publicKey": download.reality.public_key,
+                    "shortId": download.reality.short_id
+                });
+            }
+            if !download.path.trim().is_empty() {
+                settings["xhttpSettings"] = json!({ "path": download.path.trim() });
+            }
+            extra.insert("downloadSettings".to_string(), settings);
+        }
+        Value::Object(extra)
+    }
+
+    /// Read an `extra` block in xray's form (see [`XhttpExtra::to_xray`]).
+    /// Keys this app doesn't model are dropped.
+    pub fn from_xray(extra: &serde_json::Value) -> Result<Self, String> {
+        use serde_json::Value;
+
+        fn range(value: &Value, key: &str) -> Result<String, String> {
+            match value.get(key) {
+                None | Some(Value::Null) => Ok(String::new()),
+                Some(Value::Number(n)) => Ok(n.to_string()),
+                Some(Value::String(s)) => Ok(s.clone()),
+                Some(other) => Err(format!("xHTTP extra {key} has unexpected value {other}")),
+            }
+        }
+        fn text(value: &Value, key: &str) -> String {
+            value
+                .get(key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        }
+
+        if !extra.is_object() {
+            return Err("xHTTP extra must be a JSON object".to_string());
+        }
+        let xmux = extra.get("xmux").cloned().unwrap_or(Value::Null);
+        let download = match extra.get("downloadSettings") {
+            None | Some(Value::Null) => None,
+            Some(settings) => {
+                let security = match text(settings, "security").as_str() {
+                    "" => "reality".to_string(),
+                    other => other.to_string(),
+                };
+                let layer = if security == "tls" {
+                    "tlsSettings"
+                } else {
+                    "realitySettings"
+                };
+                let layer = settings.get(layer).cloned().unwrap_or(Value::Null);
+                let port = settings
+                    .get("port")
+                    .and_then(Value::as_u64)
+                    .and_then(|p| u16::try_from(p).ok())
+                    .ok_or("xHTTP downloadSettings needs a valid port")?;
+                let fingerprint = text(&layer, "fingerprint");
+                Some(XhttpDownload {
+                    address: text(settings, "address"),
+                    port,
+                    security,
+                    path: settings
+                        .get("xhttpSettings")
+                        .map(|x| text(x, "path"))
+                        .unwrap_or_default(),
+                    tls: TlsSettings {
+                        server_name: text(&layer, "serverName"),
+                        fingerprint: if fingerprint.is_empty() {
+                            default_fingerprint()
+                        } else {
+                            fingerprint
+                        },
+                        alpn: layer
+                            .get("alpn")
+                            .and_then(Value::as_array)
+                            .map(|a| {
+                                a.iter()
+                                    .filter_map(Value::as_str)
+                                    .map(str::to_string)
+                                    .collect()
+                            })
+                            .unwrap_or_default(),
+                        ..TlsSettings::default()
+                    },
+                    reality: RealitySettings {
+                        public_key: text(&layer, "publicKey"),
+                        short_id: text(&layer, "shortId"),
+                        ..RealitySettings::default()
+                    },
+                })
+            }
+        };
+
+        Ok(Self {
+            x_padding_bytes: range(extra, "xPaddingBytes")?,
+            no_grpc_header: extra
+                .get("noGRPCHeader")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            sc_max_each_post_bytes: range(extra, "scMaxEachPostBytes")?,
+            sc_min_posts_interval_ms: range(extra, "scMinPostsIntervalMs")?,
+            xmux: XmuxSettings {
+                max_concurrency: range(&xmux, "maxConcurrency")?,
+                max_connections: range(&xmux, "maxConnections")?,
+                c_max_reuse_times: range(&xmux, "cMaxReuseTimes")?,
+                h_max_request_times: range(&xmux, "hMaxRequestTimes")?,
+                h_max_reusable_secs: range(&xmux, "hMaxReusableSecs")?,
+                h_keep_alive_period: xmux
+                    .get("hKeepAlivePeriod")
+                    .and_then(Value::as_i64)
+                    .unwrap_or(0),
+            },
+            download,
+        })
+    }
+}
+
+/// The server an xray outbound connects to: the first `vnext` / `servers`
+/// entry, the first WireGuard peer's `endpoint`, or a flat
+/// `address` / `port` pair in `settings`. `None` when it names none.
+pub fn outbound_endpoint(outbound: &serde_json::Value) -> Option<(String, u16)> {
+    let settings = outbound.get("settings")?;
+    let entry = ["vnext", "servers", "peers"]
+        .iter()
+        .find_map(|key| settings.get(key)?.get(0))
+        .unwrap_or(settings);
+    if let Some(endpoint) = entry.get("endpoint").and_then(|e| e.as_str()) {
+        let (host, port) = endpoint.trim().rsplit_once(':')?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        return Some((host.to_string(), port.parse().ok()?));
+    }
+    let address = entry.get("address")?.as_str()?.trim();
+    let port = entry.get("port")?.as_u64()?;
+    Some((address.to_string(), u16::try_from(port).ok()?))
+}
+
+/// xray's "N" / "min-max" range syntax.
+fn is_range(value: &str) -> bool {
+    let (min, max) = value.split_once('-').unwrap_or((value, value));
+    match (min.parse::<u64>(), max.parse::<u64>()) {
+        (Ok(min), Ok(max)) => min <= max,
+        _ => false,
+    }
+}
+
+/// Look up the hop `server` is chained through. Only one extra hop is
+/// supported, so the via server must itself connect directly.
+pub fn resolve_via<'a>(
+    server: &ServerConfig,
+    servers: &'a [ServerConfig],
+) -> Result<Option<&'a ServerConfig>, String> {
+    let Some(via_id) = server.via.as_deref().filter(|id| !id.is_empty()) else {
+        return Ok(None);
+    };
+    if via_id == server.id {
+        return Err(format!(
+            "'{}' cannot be chained through itself",
+            server.name
+        ));
+    }
+    let hop = servers
+        .iter()
+        .find(|s| s.id == via_id)
+        .ok_or_else(|| format!("Via server for '{}' no longer exists", server.name))?;
+    if hop.via.as_deref().is_some_and(|id| !id.is_empty()) {
+        return Err(format!(
+            "Via server '{}' is itself chained; only two hops are supported",
+            hop.name
+        ));
+    }
+    Ok(Some(hop))
+}
+
+/// Parse a `servers.json`-style array, upgrading entries written before TLS
+/// settings had their own struct: SNI and fingerprint used to live in
+/// `reality` and the ALPN list at the top level.
+pub fn servers_from_json(data: &str) -> Result<Vec<ServerConfig>, serde_json::Error> {
+    let mut entries: Vec<serde_json::Value> = serde_json::from_str(data)?;
+    for entry in &mut entries {
+        migrate_legacy_tls(entry);
+    }
+    entries.into_iter().map(serde_json::from_value).collect()
+}
+
+fn migrate_legacy_tls(entry: &mut serde_json::Value) {
+    let Some(obj) = entry.as_object_mut() else {
+        return;
+    };
+    if obj.contains_key("tls") {
+        return;
+    }
+    let mut tls = serde_json::Map::new();
+    if let Some(reality) = obj.get_mut("reality").and_then(|r| r.as_object_mut()) {
+        for key in ["server_name", "fingerprint"] {
+            if let Some(value) = reality.remove(key) {
+                tls.insert(key.to_string(), value);
+            }
+        }
+    }
+    if let Some(alpn) = obj.remove("alpn") {
+        tls.insert("alpn".to_string(), alpn);
+    }
+    obj.insert("tls".to_string(), serde_json::Value::Object(tls));
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpeedStats {
+    pub upload_speed: u64,
+    pub download_speed: u64,
+    pub total_upload: u64,
+    pub total_download: u64,
+}
+
+/// Servers read from a foreign profile format, plus the entries that could
+/// not be converted. Nothing is saved until the caller imports `servers`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ParsedImport {
+    pub servers: Vec<ServerConfig>,
+    pub errors: Vec<ImportEntryError>,
+}
+
+/// Why one entry of a foreign profile was skipped.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportEntryError {
+    /// Position of the entry in the source (0-based).
+    pub index: usize,
+    /// The entry's name, if it had one.
+    pub name: String,
+    pub error: String,
+}
+
+/// What `import_servers` does with an incoming server that has the same
+/// [`ServerIdentity`] as a saved one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStrategy {
+    /// Leave the saved server alone and drop the incoming one.
+    #[default]
+    Skip,
+    /// Replace the saved server's settings, keeping its id and list position.
+    Overwrite,
+    /// Add the incoming server as a new entry anyway.
+    KeepBoth,
+}
+
+/// The outcome of `import_servers`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportReport {
+    /// New entries, with the ids they were saved under.
+    pub added: Vec<ServerConfig>,
+    /// Saved entries that were overwritten, as they are now.
+    pub updated: Vec<ServerConfig>,
+    /// Incoming servers that were dropped as duplicates, as they were given.
+    pub skipped: Vec<ServerConfig>,
+}
+
+/// What makes two server entries the same server, whatever they are named:
+/// the protocol and endpoint, the user, and the transport and security
+/// layered on top.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ServerIdentity {
+    protocol: String,
+    address: String,
+    port: u16,
+    user: String,
+    network: String,
+    security: String,
+}
+
+impl ServerIdentity {
+    pub fn of(server: &ServerConfig) -> Self {
+        let protocol = server.protocol.trim().to_ascii_lowercase();
+        // Whatever tells accounts on one endpoint apart: WireGuard profiles
+        // (e.g. every WARP account on engage.cloudflareclient.com) differ only
+        // in their keys, and a raw outbound is identified by all of itself.
+        // Trojan, Shadowsocks and Hysteria2 authenticate with a password
+        // instead of a UUID; UUIDs compare case-insensitively.
+        let user = match protocol.as_str() {
+            "wireguard" => server.wg_private_key.trim().to_string(),
+            "raw" => server
+                .raw_outbound
+                .as_ref()
+                .map(|outbound| outbound.to_string())
+                .unwrap_or_default(),
+            _ if server.uuid.trim().is_empty() => server.password.clone(),
+            _ => server.uuid.trim().to_ascii_lowercase(),
+        };
+        Self {
+            protocol,
+            address: server
+                .address
+                .trim()
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_ascii_lowercase(),
+            port: server.port,
+            user,
+            network: server.network.trim().to_ascii_lowercase(),
+            security: server.security.trim().to_ascii_lowercase(),
+        }
+    }
+}
+
+/// Names of the `imported` servers that duplicate one of `servers`.
+pub fn find_duplicates(servers: &[ServerConfig], imported: &[ServerConfig]) -> Vec<String> {
+    let saved: std::collections::HashSet<ServerIdentity> =
+        servers.iter().map(ServerIdentity::of).collect();
+    imported
+        .iter()
+        .filter(|s| saved.contains(&ServerIdentity::of(s)))
+        .map(|s| {
+            if s.name.is_empty() {
+                format!("{}:{}", s.address, s.port)
+            } else {
+                s.name.clone()
+            }
+        })
+        .collect()
+}
+
+/// Add `imported` to `servers`, resolving duplicates with `strategy`. New
+/// entries get fresh ids; a server repeated within `imported` itself is only
+/// taken once unless `strategy` is [`MergeStrategy::KeepBoth`].
+pub fn merge_import(
+    servers: &mut Vec<ServerConfig>,
+    imported: Vec<ServerConfig>,
+    strategy: MergeStrategy,
+) -> ImportReport {
+    use std::collections::HashMap;
+
+    let mut saved: HashMap<ServerIdentity, usize> = HashMap::new();
+    if strategy != MergeStrategy::KeepBoth {
+        for (index, server) in servers.iter().enumerate() {
+            saved.entry(ServerIdentity::of(server)).or_insert(index);
+        }
+    }
+    let mut taken: HashMap<ServerIdentity, String> = HashMap::new();
+    // Incoming id -> the id of the entry it ended up as.
+    let mut ids: HashMap<String, String> = HashMap::new();
+    let mut added = Vec::new();
+    let mut updated = Vec::new();
+    let mut skipped = Vec::new();
+
+    for mut server in imported {
+        let identity = ServerIdentity::of(&server);
+        if let Some(id) = taken.get(&identity) {
+            ids.insert(server.id.clone(), id.clone());
+            skipped.push(server);
+            continue;
+        }
+        let id = match saved.get(&identity) {
+            Some(&index) if strategy == MergeStrategy::Skip => {
+                let id = servers[index].id.clone();
+                ids.insert(server.id.clone(), id.clone());
+                skipped.push(server);
+                id
+            }
+            Some(&index) => {
+                let stored = &mut servers[index];
+                let id = std::mem::replace(&mut server.id, stored.id.clone());
+                ids.insert(id, stored.id.clone());
+                server.subscription_id = stored.subscription_id.clone();
+                *stored = server;
+                updated.push(index);
+                stored.id.clone()
+            }
+            None => {
+                let id = std::mem::replace(&mut server.id, generate_id());
+                ids.entry(id).or_insert_with(|| server.id.clone());
+                // A local subscription with the same id would adopt (and
+                // later overwrite or delete) it; an added server is manual.
+                server.subscription_id = None;
+                servers.push(server);
+                added.push(servers.len() - 1);
+                servers[servers.len() - 1].id.clone()
+            }
+        };
+        if strategy != MergeStrategy::KeepBoth {
+            taken.insert(identity, id);
+        }
+    }
+
+    // Keep chains within the imported batch intact, pointing hops at the
+    // saved entry they were merged into; a hop that wasn't exported alongside
+    // its server can't be resolved here.
+    for &index in added.iter().chain(&updated) {
+        let server = &mut servers[index];
+        server.via = server
+            .via
+            .take()
+            .and_then(|via| ids.get(&via).cloned())
+            .filter(|via| *via != server.id);
+    }
+    ImportReport {
+        added: added.iter().map(|&i| servers[i].clone()).collect(),
+        updated: updated.iter().map(|&i| servers[i].clone()).collect(),
+        skipped,
+    }
+}
+
+/// A detected VPN interface with its routed subnets.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectedVpn {
+    /// Interface name (e.g. "tun0", "wg0")
+    pub interface: String,
+    /// Human-readable VPN type (e.g. "OpenVPN", "WireGuard")
+    pub vpn_type: String,
+    /// Subnets routed through this interface (e.g. ["10.8.0.0/24", "172.20.0.0/16"])
+    pub subnets: Vec<String>,
+    /// VPN server endpoint IP if detected
+    pub server_ip: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppSettings {
     pub auto_connect: bool,
     pub last_server_id: Option<String>,
     #[serde(default = "default_bypass_domains")]
     pub bypass_domains: Vec<String>,
+    /// Drop IPv6 traffic in TUN mode instead of tunnelling it.
+    #[serde(default)]
+    pub block_ipv6: bool,
 }
 
 fn default_bypass_domains() -> Vec<String> {
@@ -215,194 +1668,988 @@ fn default_bypass_domains() -> Vec<String> {
     ]
 }
 
-impl Default for AppSettings {
-    fn default() -> Self {
-        Self {
-            auto_connect: false,
-            last_server_id: None,
-            bypass_domains: default_bypass_domains(),
-        }
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            auto_connect: false,
+            last_server_id: None,
+            bypass_domains: default_bypass_domains(),
+            block_ipv6: false,
+        }
+    }
+}
+
+/// A provider URL serving a server list (base64 or plain share links, one
+/// per line).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subscription {
+    #[serde(default = "generate_id")]
+    pub id: String,
+    pub name: String,
+    pub url: String,
+    /// Seconds between automatic refreshes; 0 = manual only.
+    #[serde(default = "default_refresh_interval")]
+    pub refresh_interval_secs: u64,
+    /// Unix time of the last successful refresh.
+    #[serde(default)]
+    pub last_updated: Option<u64>,
+    /// Traffic and expiry from the provider's `subscription-userinfo` header.
+    #[serde(default)]
+    pub usage: Option<SubscriptionUsage>,
+    /// The provider's name for the profile (`profile-title`); empty if not sent.
+    #[serde(default)]
+    pub profile_title: String,
+    /// Refresh interval the provider suggests (`profile-update-interval`), in
+    /// seconds.
+    #[serde(default)]
+    pub suggested_refresh_secs: Option<u64>,
+    /// Unix time of the last failed refresh, cleared by a successful one.
+    #[serde(default)]
+    pub last_failed: Option<u64>,
+    /// Failed refreshes in a row; automatic retries back off with each one.
+    #[serde(default)]
+    pub failures: u32,
+}
+
+/// Byte counters and expiry as reported by the provider.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubscriptionUsage {
+    #[serde(default)]
+    pub upload: u64,
+    #[serde(default)]
+    pub download: u64,
+    /// Traffic allowance in bytes; 0 = unlimited.
+    #[serde(default)]
+    pub total: u64,
+    /// Unix time the subscription expires; `None` = never.
+    #[serde(default)]
+    pub expire: Option<u64>,
+}
+
+impl SubscriptionUsage {
+    pub fn used(&self) -> u64 {
+        self.upload.saturating_add(self.download)
+    }
+
+    /// Bytes left, or `None` for an unlimited plan.
+    pub fn remaining(&self) -> Option<u64> {
+        (self.total > 0).then(|| self.total.saturating_sub(self.used()))
+    }
+
+    /// Share of the allowance used, capped at 100; `None` for an unlimited plan.
+    pub fn used_percent(&self) -> Option<u8> {
+        (self.total > 0)
+            .then(|| (u128::from(self.used()) * 100 / u128::from(self.total)).min(100) as u8)
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expire.is_some_and(|expire| expire <= now)
+    }
+}
+
+/// What `get_subscription_quota` reports for one subscription.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubscriptionQuota {
+    pub subscription_id: String,
+    pub title: String,
+    pub used: u64,
+    pub total: u64,
+    pub remaining: Option<u64>,
+    pub used_percent: Option<u8>,
+    pub expire: Option<u64>,
+    pub expired: bool,
+}
+
+fn default_refresh_interval() -> u64 {
+    24 * 60 * 60
+}
+
+impl Subscription {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Subscription name must not be empty".to_string());
+        }
+        let url = self.url.trim();
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err("Subscription URL must start with https:// or http://".to_string());
+        }
+        if self.refresh_interval_secs != 0 && self.refresh_interval_secs < 60 {
+            return Err("Subscription refresh interval must be at least 60 seconds".to_string());
+        }
+        Ok(())
+    }
+
+    /// Usage summary at `now`, or `None` if the provider never reported any.
+    pub fn quota(&self, now: u64) -> Option<SubscriptionQuota> {
+        let usage = self.usage.as_ref()?;
+        let title = if self.profile_title.is_empty() {
+            self.name.clone()
+        } else {
+            self.profile_title.clone()
+        };
+        Some(SubscriptionQuota {
+            subscription_id: self.id.clone(),
+            title,
+            used: usage.used(),
+            total: usage.total,
+            remaining: usage.remaining(),
+            used_percent: usage.used_percent(),
+            expire: usage.expire,
+            expired: usage.is_expired(now),
+        })
+    }
+
+    /// Whether an automatic refresh is due at `now` (Unix seconds). After a
+    /// failure the next attempt waits out a backoff first, so an unreachable
+    /// provider isn't polled every minute.
+    pub fn is_due(&self, now: u64) -> bool {
+        if self.refresh_interval_secs == 0 {
+            return false;
+        }
+        if let Some(failed) = self.last_failed {
+            let backoff = REFRESH_RETRY_BASE_SECS
+                .saturating_mul(1 << self.failures.saturating_sub(1).min(16))
+                .min(REFRESH_RETRY_MAX_SECS);
+            if now.saturating_sub(failed) < backoff {
+                return false;
+            }
+        }
+        match self.last_updated {
+            Some(last) => now.saturating_sub(last) >= self.refresh_interval_secs,
+            None => true,
+        }
+    }
+
+    /// Note a failed refresh at `now`, pushing back the next automatic one.
+    pub fn record_failure(&mut self, now: u64) {
+        self.last_failed = Some(now);
+        self.failures = self.failures.saturating_add(1);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub timestamp: u64,
+    pub level: String,
+    pub message: String,
+}
+
+#[derive(Debug, Error)]
+pub enum AppError {
+    #[error("Xray process error: {0}")]
+    XrayProcess(String),
+
+    #[error("Configuration error: {0}")]
+    Config(String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("Network error: {0}")]
+    Network(String),
+
+    #[error("Tauri error: {0}")]
+    Tauri(#[from] tauri::Error),
+}
+
+impl From<AppError> for String {
+    fn from(err: AppError) -> Self {
+        err.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_server_config() -> ServerConfig {
+        ServerConfig {
+            id: "test-id-1234".to_string(),
+            name: "Test".to_string(),
+            address: "1.2.3.4".to_string(),
+            port: 443,
+            uuid: "aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee".to_string(),
+            flow: "xtls-rprx-vision".to_string(),
+            reality: RealitySettings {
+                public_key: "abc123".to_string(),
+                short_id: "def456".to_string(),
+                ..RealitySettings::default()
+            },
+            tls: TlsSettings {
+                server_name: "example.com".to_string(),
+                fingerprint: "chrome".to_string(),
+                ..TlsSettings::default()
+            },
+            network: "tcp".to_string(),
+            xhttp_path: String::new(),
+            security: "reality".to_string(),
+            xhttp_mode: "auto".to_string(),
+            ..ServerConfig::default()
+        }
+    }
+
+    #[test]
+    fn server_config_roundtrip() {
+        let config = sample_server_config();
+        let json = serde_json::to_string(&config).unwrap();
+        let deserialized: ServerConfig = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(deserialized.name, config.name);
+        assert_eq!(deserialized.address, config.address);
+        assert_eq!(deserialized.port, config.port);
+        assert_eq!(deserialized.uuid, config.uuid);
+        assert_eq!(deserialized.flow, config.flow);
+        assert_eq!(deserialized.reality.public_key, config.reality.public_key);
+        assert_eq!(deserialized.reality.short_id, config.reality.short_id);
+        assert_eq!(deserialized.tls.server_name, config.tls.server_name);
+        assert_eq!(deserialized.tls.fingerprint, config.tls.fingerprint);
+    }
+
+    #[test]
+    fn connection_status_serialization() {
+        // Verify snake_case rename
+        let json = serde_json::to_string(&ConnectionStatus::Disconnected).unwrap();
+        assert_eq!(json, "\"disconnected\"");
+
+        let json = serde_json::to_string(&ConnectionStatus::Connecting).unwrap();
+        assert_eq!(json, "\"connecting\"");
+
+        let json = serde_json::to_string(&ConnectionStatus::Connected).unwrap();
+        assert_eq!(json, "\"connected\"");
+
+        let json = serde_json::to_string(&ConnectionStatus::Disconnecting).unwrap();
+        assert_eq!(json, "\"disconnecting\"");
+
+        let json = serde_json::to_string(&ConnectionStatus::Error).unwrap();
+        assert_eq!(json, "\"error\"");
+    }
+
+    #[test]
+    fn connection_status_deserialization() {
+        let status: ConnectionStatus = serde_json::from_str("\"disconnected\"").unwrap();
+        assert_eq!(status, ConnectionStatus::Disconnected);
+
+        let status: ConnectionStatus = serde_json::from_str("\"connected\"").unwrap();
+        assert_eq!(status, ConnectionStatus::Connected);
+
+        let status: ConnectionStatus = serde_json::from_str("\"error\"").unwrap();
+        assert_eq!(status, ConnectionStatus::Error);
+    }
+
+    #[test]
+    fn connection_info_roundtrip() {
+        let info = ConnectionInfo {
+            status: ConnectionStatus::Connected,
+            server_name: Some("My Server".to_string()),
+            server_address: Some("1.2.3.4".to_string()),
+            connected_since: Some(1700000000),
+            error_message: None,
+            chain: vec!["Entry".to_string(), "My Server".to_string()],
+        };
+
+        let json = serde_json::to_string(&info).unwrap();
+        let deserialized: ConnectionInfo = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(deserialized.status, ConnectionStatus::Connected);
+        assert_eq!(deserialized.server_name, Some("My Server".to_string()));
+        assert_eq!(deserialized.server_address, Some("1.2.3.4".to_string()));
+        assert_eq!(deserialized.connected_since, Some(1700000000));
+        assert_eq!(deserialized.error_message, None);
+        assert_eq!(deserialized.chain, vec!["Entry", "My Server"]);
+    }
+
+    #[test]
+    fn connection_info_default() {
+        let info = ConnectionInfo::default();
+        assert_eq!(info.status, ConnectionStatus::Disconnected);
+        assert!(info.server_name.is_none());
+        assert!(info.server_address.is_none());
+        assert!(info.connected_since.is_none());
+        assert!(info.error_message.is_none());
+    }
+
+    #[test]
+    fn server_config_default() {
+        let config = ServerConfig::default();
+        assert_eq!(config.port, 443);
+        assert_eq!(config.flow, "xtls-rprx-vision");
+        assert_eq!(config.tls.server_name, "www.google.com");
+        assert_eq!(config.tls.fingerprint, "chrome");
+        assert_eq!(config.network, "tcp");
+        assert_eq!(config.xhttp_path, "");
+    }
+
+    #[test]
+    fn server_config_legacy_json_defaults_to_tcp() {
+        // Configs persisted before the transport fields existed must still load,
+        // defaulting to plain TCP REALITY.
+        let legacy = r#"{
+            "id": "x",
+            "name": "Old",
+            "address": "1.2.3.4",
+            "port": 443,
+            "uuid": "aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee",
+            "flow": "xtls-rprx-vision",
+            "reality": { "public_key": "k", "short_id": "s", "server_name": "ya.ru", "fingerprint": "chrome" }
+        }"#;
+        let config: ServerConfig = serde_json::from_str(legacy).unwrap();
+        assert_eq!(config.network, "tcp");
+        assert_eq!(config.xhttp_path, "");
+    }
+
+    #[test]
+    fn validate_xhttp_network_ok() {
+        let mut config = sample_server_config();
+        config.network = "xhttp".to_string();
+        config.flow = String::new();
+        config.xhttp_path = "/xhttp".to_string();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_unknown_network_rejected() {
+        let mut config = sample_server_config();
+        config.network = "kcp".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("transport"));
+    }
+
+    #[test]
+    fn validate_vless_encryption() {
+        let mut config = sample_server_config();
+        assert_eq!(config.vless_encryption, "none");
+        // 32-byte X25519 password, base64url without padding.
+        let key = "WUe0I6Pm0A8ZPdwaqGB0BZtfEBEpkJcCvWb7YmiTcXc";
+        for ok in [
+            format!("mlkem768x25519plus.native.0rtt.{key}"),
+            format!("mlkem768x25519plus.xorpub.1rtt.100-111-1111.75-0-111.{key}.{key}"),
+        ] {
+            config.vless_encryption = ok.clone();
+            assert!(config.validate().is_ok(), "{ok}");
+        }
+        for bad in [
+            "aes-128-gcm".to_string(),
+            format!("mlkem768x25519plus.turbo.0rtt.{key}"),
+            "mlkem768x25519plus.native.0rtt".to_string(),
+            "mlkem768x25519plus.native.1rtt.100-111-1111".to_string(),
+            "mlkem768x25519plus.native.1rtt.not+base64url".to_string(),
+        ] {
+            config.vless_encryption = bad.clone();
+            assert!(config.validate().is_err(), "{bad}");
+        }
+
+        // Entries saved before the field existed default to "none".
+        let json = r#"{"id":"x","name":"n","address":"1.2.3.4","port":443,"uuid":"u","flow":"",
+            "reality":{"public_key":"k","short_id":"s"},"tls":{"server_name":"ya.ru"}}"#;
+        let server: ServerConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(server.vless_encryption, "none");
+    }
+
+    #[test]
+    fn validate_vless_grpc_and_ws() {
+        let mut config = sample_server_config();
+        config.network = "grpc".to_string();
+        assert!(config.validate().is_ok(), "REALITY over gRPC is allowed");
+
+        config.network = "ws".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("REALITY"), "unexpected error: {err}");
+
+        config.security = "tls".to_string();
+        config.tls.server_name = "cdn.example.com".to_string();
+        assert!(config.validate().is_ok());
+    }
+
+    fn sample_vmess_config() -> ServerConfig {
+        ServerConfig {
+            protocol: "vmess".to_string(),
+            flow: String::new(),
+            network: "ws".to_string(),
+            security: "tls".to_string(),
+            ws_path: "/ray".to_string(),
+            ..sample_server_config()
+        }
+    }
+
+    #[test]
+    fn server_config_legacy_json_defaults_to_vless() {
+        let legacy = r#"{
+            "name": "Old",
+            "address": "1.2.3.4",
+            "port": 443,
+            "uuid": "aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee",
+            "flow": "xtls-rprx-vision",
+            "reality": { "public_key": "k", "short_id": "s", "server_name": "ya.ru", "fingerprint": "chrome" }
+        }"#;
+        let config: ServerConfig = serde_json::from_str(legacy).unwrap();
+        assert_eq!(config.protocol, "vless");
+        assert_eq!(config.alter_id, 0);
+        assert_eq!(config.vmess_cipher, "auto");
+    }
+
+    #[test]
+    fn validate_vmess_ok() {
+        assert!(sample_vmess_config().validate().is_ok());
+
+        let mut config = sample_vmess_config();
+        config.network = "tcp".to_string();
+        config.security = "none".to_string();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_vmess_rejects_reality() {
+        let mut config = sample_vmess_config();
+        config.security = "reality".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("REALITY"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn validate_vmess_rejects_unknown_cipher() {
+        let mut config = sample_vmess_config();
+        config.vmess_cipher = "rc4".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("cipher"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn validate_vmess_rejects_xhttp() {
+        let mut config = sample_vmess_config();
+        config.network = "xhttp".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("transport"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn validate_vmess_does_not_need_reality_keys() {
+        let mut config = sample_vmess_config();
+        config.reality.public_key = String::new();
+        config.reality.short_id = String::new();
+        assert!(config.validate().is_ok());
+    }
+
+    fn sample_trojan_config() -> ServerConfig {
+        ServerConfig {
+            protocol: "trojan".to_string(),
+            uuid: String::new(),
+            flow: String::new(),
+            security: "tls".to_string(),
+            password: "hunter2".to_string(),
+            ..sample_server_config()
+        }
+    }
+
+    #[test]
+    fn validate_trojan_ok() {
+        let mut config = sample_trojan_config();
+        assert!(config.validate().is_ok());
+        config.network = "grpc".to_string();
+        config.grpc_service_name = "svc".to_string();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_trojan_requires_password() {
+        let mut config = sample_trojan_config();
+        config.password = String::new();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("password"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn validate_trojan_requires_tls() {
+        let mut config = sample_trojan_config();
+        config.security = "none".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("security"), "unexpected error: {err}");
+    }
+
+    fn sample_ss_config() -> ServerConfig {
+        ServerConfig {
+            protocol: "shadowsocks".to_string(),
+            uuid: String::new(),
+            flow: String::new(),
+            security: "none".to_string(),
+            ss_method: "aes-256-gcm".to_string(),
+            password: "passphrase".to_string(),
+            ..sample_server_config()
+        }
+    }
+
+    #[test]
+    fn validate_shadowsocks_ok() {
+        assert!(sample_ss_config().validate().is_ok());
+    }
+
+    #[test]
+    fn validate_shadowsocks_rejects_unknown_method() {
+        let mut config = sample_ss_config();
+        config.ss_method = "rc4-md5".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("method"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn validate_shadowsocks_2022_key_length() {
+        let mut config = sample_ss_config();
+        config.ss_method = "2022-blake3-aes-128-gcm".to_string();
+        // 16 zero bytes
+        config.password = "AAAAAAAAAAAAAAAAAAAAAA==".to_string();
+        assert!(config.validate().is_ok());
+
+        // A passphrase is not a PSK.
+        config.password = "passphrase".to_string();
+        assert!(config.validate().is_err());
+
+        // 32-byte key is wrong for the 128-bit cipher.
+        config.password = STANDARD.encode([0u8; 32]);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn validate_shadowsocks_2022_multi_user_keys() {
+        let mut config = sample_ss_config();
+        config.ss_method = "2022-blake3-aes-256-gcm".to_string();
+        let key = STANDARD.encode([7u8; 32]);
+        config.password = format!("{key}:{key}");
+        assert!(config.validate().is_ok());
+
+        config.password = format!("{key}:short");
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn shadowsocks_plugin_transport() {
+        let mut config = sample_ss_config();
+        assert_eq!(config.ss_plugin_transport(), Ok(None));
+
+        config.ss_plugin = "v2ray-plugin;tls;host=ss.example.com;path=/ws".to_string();
+        assert_eq!(
+            config.ss_plugin_transport(),
+            Ok(Some(SsPluginTransport {
+                tls: true,
+                host: "ss.example.com".to_string(),
+                path: "/ws".to_string(),
+            }))
+        );
+        assert!(config.validate().is_ok());
+
+        config.ss_plugin = "v2ray-plugin;mode=quic".to_string();
+        assert!(config.validate().is_err());
+
+        config.ss_plugin = "obfs-local;obfs=http".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("obfs-local"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn servers_from_json_migrates_legacy_tls_fields() {
+        let legacy = r#"[{
+            "id": "a", "name": "Old", "address": "1.2.3.4", "port": 443,
+            "uuid": "aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee", "flow": "",
+            "reality": {"public_key": "pk", "short_id": "sid",
+                        "server_name": "cdn.example.com", "fingerprint": "firefox"},
+            "security": "tls", "network": "ws", "alpn": ["h2"]
+        }]"#;
+        let servers = servers_from_json(legacy).unwrap();
+        let server = &servers[0];
+        assert_eq!(server.reality.public_key, "pk");
+        assert_eq!(server.reality.short_id, "sid");
+        assert_eq!(server.tls.server_name, "cdn.example.com");
+        assert_eq!(server.tls.fingerprint, "firefox");
+        assert_eq!(server.tls.alpn, vec!["h2"]);
+        assert!(!server.tls.allow_insecure);
+
+        // Re-serialized entries are already migrated and load unchanged.
+        let json = serde_json::to_string(&servers).unwrap();
+        let again = servers_from_json(&json).unwrap();
+        assert_eq!(again[0].tls.server_name, "cdn.example.com");
+        assert_eq!(again[0].tls.alpn, vec!["h2"]);
+    }
+
+    #[test]
+    fn validate_tls_pinned_hashes() {
+        let mut config = sample_vmess_config();
+        config.security = "tls".to_string();
+        let hash = "AB:".repeat(31) + "AB";
+        config.tls.pinned_cert_sha256 = vec![hash];
+        assert!(config.validate().is_ok());
+        assert_eq!(config.tls.pinned_hashes(), vec!["ab".repeat(32)]);
+
+        config.tls.pinned_cert_sha256 = vec!["deadbeef".to_string()];
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("SHA-256"), "unexpected error: {err}");
     }
-}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LogEntry {
-    pub timestamp: u64,
-    pub level: String,
-    pub message: String,
-}
+    #[test]
+    fn validate_hysteria2() {
+        let mut config = ServerConfig {
+            protocol: "hysteria2".to_string(),
+            uuid: String::new(),
+            flow: String::new(),
+            security: "tls".to_string(),
+            password: "auth-secret".to_string(),
+            hy2_obfs_password: "obfs".to_string(),
+            ..sample_server_config()
+        };
+        assert!(config.validate().is_ok());
 
-#[derive(Debug, Error)]
-pub enum AppError {
-    #[error("Xray process error: {0}")]
-    XrayProcess(String),
+        config.security = "reality".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("security"), "unexpected error: {err}");
 
-    #[error("Configuration error: {0}")]
-    Config(String),
+        config.security = "tls".to_string();
+        config.password.clear();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("password"), "unexpected error: {err}");
+    }
 
-    #[error("IO error: {0}")]
-    Io(#[from] std::io::Error),
+    #[test]
+    fn validate_wireguard() {
+        let key = STANDARD.encode([1u8; 32]);
+        let mut config = ServerConfig {
+            protocol: "wireguard".to_string(),
+            uuid: String::new(),
+            flow: String::new(),
+            security: "none".to_string(),
+            wg_private_key: key.clone(),
+            wg_peer_public_key: key.clone(),
+            wg_local_addresses: vec!["10.8.0.2/32".to_string()],
+            wg_reserved: vec![1, 2, 3],
+            ..sample_server_config()
+        };
+        assert!(config.validate().is_ok());
 
-    #[error("Serialization error: {0}")]
-    Serialization(#[from] serde_json::Error),
+        config.wg_preshared_key = "short".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("preshared"), "unexpected error: {err}");
 
-    #[error("Tauri error: {0}")]
-    Tauri(#[from] tauri::Error),
-}
+        config.wg_preshared_key = key;
+        config.wg_reserved = vec![1, 2];
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("reserved"), "unexpected error: {err}");
 
-impl From<AppError> for String {
-    fn from(err: AppError) -> Self {
-        err.to_string()
+        config.wg_reserved.clear();
+        config.wg_mtu = 1000;
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("MTU"), "unexpected error: {err}");
+
+        config.wg_mtu = 1280;
+        config.wg_local_addresses.clear();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("address"), "unexpected error: {err}");
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn validate_raw() {
+        let mut config = ServerConfig {
+            protocol: "raw".to_string(),
+            address: "example.com".to_string(),
+            port: 8443,
+            uuid: String::new(),
+            raw_outbound: Some(serde_json::json!({
+                "protocol": "vless",
+                "settings": {
+                    "vnext": [{ "address": "example.com", "port": 8443, "users": [] }]
+                },
+                "streamSettings": { "network": "splithttp" }
+            })),
+            ..sample_server_config()
+        };
+        assert!(config.validate().is_ok());
 
-    fn sample_server_config() -> ServerConfig {
-        ServerConfig {
-            id: "test-id-1234".to_string(),
-            name: "Test".to_string(),
-            address: "1.2.3.4".to_string(),
-            port: 443,
-            uuid: "aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee".to_string(),
-            flow: "xtls-rprx-vision".to_string(),
-            reality: RealitySettings {
-                public_key: "abc123".to_string(),
-                short_id: "def456".to_string(),
-                server_name: "example.com".to_string(),
-                fingerprint: "chrome".to_string(),
-            },
-            network: "tcp".to_string(),
-            xhttp_path: String::new(),
-            security: "reality".to_string(),
-            xhttp_mode: "auto".to_string(),
-        }
+        config.port = 443;
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("example.com:8443"), "unexpected error: {err}");
+        config.port = 8443;
+
+        let outbound = config.raw_outbound.as_mut().unwrap();
+        outbound["streamSettings"]["sockopt"] = serde_json::json!({ "dialerProxy": "x" });
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("dialerProxy"), "unexpected error: {err}");
+
+        config.raw_outbound = Some(serde_json::json!({ "protocol": "freedom" }));
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("not a proxy"), "unexpected error: {err}");
+
+        config.raw_outbound = Some(serde_json::json!(["vless"]));
+        assert!(config.validate().is_err());
+        config.raw_outbound = None;
+        assert!(config.validate().is_err());
     }
 
     #[test]
-    fn server_config_roundtrip() {
-        let config = sample_server_config();
-        let json = serde_json::to_string(&config).unwrap();
-        let deserialized: ServerConfig = serde_json::from_str(&json).unwrap();
-
-        assert_eq!(deserialized.name, config.name);
-        assert_eq!(deserialized.address, config.address);
-        assert_eq!(deserialized.port, config.port);
-        assert_eq!(deserialized.uuid, config.uuid);
-        assert_eq!(deserialized.flow, config.flow);
-        assert_eq!(deserialized.reality.public_key, config.reality.public_key);
-        assert_eq!(deserialized.reality.short_id, config.reality.short_id);
-        assert_eq!(deserialized.reality.server_name, config.reality.server_name);
-        assert_eq!(deserialized.reality.fingerprint, config.reality.fingerprint);
+    fn outbound_endpoints() {
+        let wg = serde_json::json!({
+            "settings": { "peers": [{ "endpoint": "[2606:4700::1]:2408" }] }
+        });
+        assert_eq!(
+            outbound_endpoint(&wg),
+            Some(("2606:4700::1".to_string(), 2408))
+        );
+        let trojan = serde_json::json!({
+            "settings": { "servers": [{ "address": "1.2.3.4", "port": 443 }] }
+        });
+        assert_eq!(
+            outbound_endpoint(&trojan),
+            Some(("1.2.3.4".to_string(), 443))
+        );
+        let flat = serde_json::json!({ "settings": { "address": "h.example", "port": 8443 } });
+        assert_eq!(
+            outbound_endpoint(&flat),
+            Some(("h.example".to_string(), 8443))
+        );
+        assert_eq!(
+            outbound_endpoint(&serde_json::json!({ "settings": {} })),
+            None
+        );
     }
 
     #[test]
-    fn connection_status_serialization() {
-        // Verify snake_case rename
-        let json = serde_json::to_string(&ConnectionStatus::Disconnected).unwrap();
-        assert_eq!(json, "\"disconnected\"");
+    fn validate_mux() {
+        // sample_server_config uses the Vision flow over raw TCP.
+        let mut config = sample_server_config();
+        config.mux.enabled = true;
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("Vision"), "unexpected error: {err}");
 
-        let json = serde_json::to_string(&ConnectionStatus::Connecting).unwrap();
-        assert_eq!(json, "\"connecting\"");
+        config.mux.concurrency = -1;
+        assert!(config.validate().is_ok());
 
-        let json = serde_json::to_string(&ConnectionStatus::Connected).unwrap();
-        assert_eq!(json, "\"connected\"");
+        // Without Vision TCP may be muxed.
+        config.network = "grpc".to_string();
+        config.mux.concurrency = 8;
+        assert!(config.validate().is_ok());
 
-        let json = serde_json::to_string(&ConnectionStatus::Disconnecting).unwrap();
-        assert_eq!(json, "\"disconnecting\"");
+        config.mux.xudp_concurrency = 2000;
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("XUDP"), "unexpected error: {err}");
 
-        let json = serde_json::to_string(&ConnectionStatus::Error).unwrap();
-        assert_eq!(json, "\"error\"");
+        config.mux.xudp_concurrency = 16;
+        config.mux.xudp_proxy_udp_443 = "drop".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("xudpProxyUDP443"), "unexpected error: {err}");
     }
 
     #[test]
-    fn connection_status_deserialization() {
-        let status: ConnectionStatus = serde_json::from_str("\"disconnected\"").unwrap();
-        assert_eq!(status, ConnectionStatus::Disconnected);
+    fn validate_fragment_and_noises() {
+        let mut config = sample_server_config();
+        config.fragment = FragmentSettings {
+            packets: "tlshello".to_string(),
+            length: "100-200".to_string(),
+            interval: "10".to_string(),
+        };
+        config.noises = vec![NoiseSettings {
+            noise_type: "rand".to_string(),
+            packet: "10-20".to_string(),
+            delay: "10-16".to_string(),
+        }];
+        assert!(config.validate().is_ok());
 
-        let status: ConnectionStatus = serde_json::from_str("\"connected\"").unwrap();
-        assert_eq!(status, ConnectionStatus::Connected);
+        config.fragment.length = "200-100".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("length"), "unexpected error: {err}");
 
-        let status: ConnectionStatus = serde_json::from_str("\"error\"").unwrap();
-        assert_eq!(status, ConnectionStatus::Error);
+        config.fragment.length = "100-200".to_string();
+        config.fragment.packets = "first".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("tlshello"), "unexpected error: {err}");
+
+        config.fragment = FragmentSettings::default();
+        config.noises[0].packet = "hello".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("Random noise"), "unexpected error: {err}");
+
+        config.noises[0].noise_type = "str".to_string();
+        assert!(config.validate().is_ok());
+        config.noises[0].noise_type = "zeros".to_string();
+        assert!(config.validate().is_err());
     }
 
     #[test]
-    fn connection_info_roundtrip() {
-        let info = ConnectionInfo {
-            status: ConnectionStatus::Connected,
-            server_name: Some("My Server".to_string()),
-            server_address: Some("1.2.3.4".to_string()),
-            connected_since: Some(1700000000),
-            error_message: None,
+    fn xhttp_extra_xray_roundtrip() {
+        let extra = XhttpExtra {
+            x_padding_bytes: "100-1000".to_string(),
+            no_grpc_header: true,
+            sc_max_each_post_bytes: "1000000".to_string(),
+            sc_min_posts_interval_ms: "10-50".to_string(),
+            xmux: XmuxSettings {
+                max_concurrency: "16-32".to_string(),
+                c_max_reuse_times: "64".to_string(),
+                h_keep_alive_period: 30,
+                ..XmuxSettings::default()
+            },
+            download: Some(XhttpDownload {
+                address: "5.6.7.8".to_string(),
+                port: 443,
+                security: "reality".to_string(),
+                path: "/down".to_string(),
+                tls: TlsSettings {
+                    server_name: "www.microsoft.com".to_string(),
+                    ..TlsSettings::default()
+                },
+                reality: RealitySettings {
+                    public_key: "pbk".to_string(),
+                    short_id: "ab".to_string(),
+                    ..RealitySettings::default()
+                },
+            }),
         };
+        let json = extra.to_xray();
+        assert_eq!(json["xPaddingBytes"], "100-1000");
+        assert_eq!(json["scMaxEachPostBytes"], 1000000);
+        assert_eq!(json["xmux"]["maxConcurrency"], "16-32");
+        assert!(json["xmux"].get("maxConnections").is_none());
+        assert_eq!(
+            json["downloadSettings"]["realitySettings"]["publicKey"],
+            "pbk"
+        );
+        assert_eq!(XhttpExtra::from_xray(&json).unwrap(), extra);
+
+        assert!(XhttpExtra::default()
+            .to_xray()
+            .as_object()
+            .unwrap()
+            .is_empty());
+        assert!(XhttpExtra::from_xray(&serde_json::json!([])).is_err());
+    }
 
-        let json = serde_json::to_string(&info).unwrap();
-        let deserialized: ConnectionInfo = serde_json::from_str(&json).unwrap();
+    #[test]
+    fn validate_xhttp_extra() {
+        let mut config = sample_server_config();
+        config.network = "xhttp".to_string();
+        config.xhttp_extra.x_padding_bytes = "lots".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("xPaddingBytes"), "unexpected error: {err}");
 
-        assert_eq!(deserialized.status, ConnectionStatus::Connected);
-        assert_eq!(deserialized.server_name, Some("My Server".to_string()));
-        assert_eq!(deserialized.server_address, Some("1.2.3.4".to_string()));
-        assert_eq!(deserialized.connected_since, Some(1700000000));
-        assert_eq!(deserialized.error_message, None);
+        config.xhttp_extra.x_padding_bytes = "100-1000".to_string();
+        config.xhttp_extra.download = Some(XhttpDownload {
+            address: "5.6.7.8".to_string(),
+            port: 443,
+            security: "reality".to_string(),
+            path: String::new(),
+            tls: TlsSettings::default(),
+            reality: RealitySettings::default(),
+        });
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("public_key"), "unexpected error: {err}");
     }
 
     #[test]
-    fn connection_info_default() {
-        let info = ConnectionInfo::default();
-        assert_eq!(info.status, ConnectionStatus::Disconnected);
-        assert!(info.server_name.is_none());
-        assert!(info.server_address.is_none());
-        assert!(info.connected_since.is_none());
-        assert!(info.error_message.is_none());
+    fn reality_short_ids() {
+        let reality = RealitySettings {
+            short_id: "ab".to_string(),
+            extra_short_ids: vec!["cd".to_string(), " ".to_string(), "ef01".to_string()],
+            ..RealitySettings::default()
+        };
+        assert_eq!(reality.short_ids(), ["ab", "cd", "ef01"]);
+        for _ in 0..20 {
+            assert!(reality.short_ids().contains(&reality.pick_short_id()));
+        }
+        let single = RealitySettings {
+            short_id: "ab".to_string(),
+            ..RealitySettings::default()
+        };
+        assert_eq!(single.pick_short_id(), "ab");
     }
 
     #[test]
-    fn server_config_default() {
-        let config = ServerConfig::default();
-        assert_eq!(config.port, 443);
-        assert_eq!(config.flow, "xtls-rprx-vision");
-        assert_eq!(config.reality.server_name, "www.google.com");
-        assert_eq!(config.reality.fingerprint, "chrome");
-        assert_eq!(config.network, "tcp");
-        assert_eq!(config.xhttp_path, "");
+    fn validate_reality_extra_short_ids_and_spider_x() {
+        let mut config = sample_server_config();
+        config.reality.extra_short_ids = vec!["0123456789abcdef".to_string(), "xyz".to_string()];
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("'xyz'"), "unexpected error: {err}");
+
+        config.reality.extra_short_ids.pop();
+        config.reality.spider_x = "index.html".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("spiderX"), "unexpected error: {err}");
+
+        config.reality.spider_x = "/index.html".to_string();
+        assert!(config.validate().is_ok());
     }
 
     #[test]
-    fn server_config_legacy_json_defaults_to_tcp() {
-        // Configs persisted before the transport fields existed must still load,
-        // defaulting to plain TCP REALITY.
-        let legacy = r#"{
-            "id": "x",
-            "name": "Old",
-            "address": "1.2.3.4",
-            "port": 443,
-            "uuid": "aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee",
-            "flow": "xtls-rprx-vision",
-            "reality": { "public_key": "k", "short_id": "s", "server_name": "ya.ru", "fingerprint": "chrome" }
-        }"#;
-        let config: ServerConfig = serde_json::from_str(legacy).unwrap();
-        assert_eq!(config.network, "tcp");
-        assert_eq!(config.xhttp_path, "");
+    fn ech_xray_value_roundtrip() {
+        let cases = [
+            ("AEX+DQBBpQAgACA=", "AEX+DQBBpQAgACA=", "", ""),
+            (
+                "https://1.1.1.1/dns-query",
+                "",
+                "https://1.1.1.1/dns-query",
+                "",
+            ),
+            (
+                "cloudflare-ech.com+https://1.1.1.1/dns-query",
+                "",
+                "https://1.1.1.1/dns-query",
+                "cloudflare-ech.com",
+            ),
+            ("udp://1.1.1.1", "", "udp://1.1.1.1", ""),
+        ];
+        for (value, config_list, doh_server, query_domain) in cases {
+            let ech = EchSettings::from_xray(value);
+            assert_eq!(ech.config_list, config_list);
+            assert_eq!(ech.doh_server, doh_server);
+            assert_eq!(ech.query_domain, query_domain);
+            assert_eq!(ech.to_xray().as_deref(), Some(value));
+        }
+        assert_eq!(EchSettings::default().to_xray(), None);
     }
 
     #[test]
-    fn validate_xhttp_network_ok() {
+    fn validate_ech() {
         let mut config = sample_server_config();
-        config.network = "xhttp".to_string();
-        config.flow = String::new();
-        config.xhttp_path = "/xhttp".to_string();
+        config.security = "tls".to_string();
+        config.tls.ech.config_list = "not base64!".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("base64"), "unexpected error: {err}");
+
+        config.tls.ech.config_list = "AEX+DQBBpQAgACA=".to_string();
+        config.tls.ech.doh_server = "https://1.1.1.1/dns-query".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("not both"), "unexpected error: {err}");
+
+        config.tls.ech.config_list.clear();
+        config.tls.ech.doh_server = "tcp://1.1.1.1".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(
+            err.contains("https:// or udp://"),
+            "unexpected error: {err}"
+        );
+
+        config.tls.ech.doh_server = "udp://1.1.1.1".to_string();
+        assert!(config.validate().is_ok());
+
+        config.tls.ech.doh_server = "https://1.1.1.1/dns-query".to_string();
         assert!(config.validate().is_ok());
     }
 
     #[test]
-    fn validate_unknown_network_rejected() {
+    fn mux_defaults_when_missing_from_json() {
+        let json = serde_json::to_value(sample_server_config()).unwrap();
+        let mut obj = json.as_object().unwrap().clone();
+        obj.remove("mux");
+        let config: ServerConfig = serde_json::from_value(obj.into()).unwrap();
+        assert_eq!(config.mux, MuxSettings::default());
+
+        let partial: MuxSettings = serde_json::from_str(r#"{"enabled":true}"#).unwrap();
+        assert!(partial.enabled);
+        assert_eq!(partial.concurrency, 8);
+        assert_eq!(partial.xudp_concurrency, 16);
+        assert_eq!(partial.xudp_proxy_udp_443, "reject");
+    }
+
+    #[test]
+    fn validate_unknown_protocol_rejected() {
         let mut config = sample_server_config();
-        config.network = "grpc".to_string();
+        config.protocol = "socks".to_string();
         let err = config.validate().unwrap_err();
-        assert!(err.contains("transport"));
+        assert!(err.contains("protocol"), "unexpected error: {err}");
     }
 
     #[test]
@@ -500,4 +2747,276 @@ mod tests {
         let err = config.validate().unwrap_err();
         assert!(err.contains("short_id"));
     }
+
+    #[test]
+    fn resolve_via_hop() {
+        let entry = ServerConfig {
+            id: "entry".to_string(),
+            ..sample_server_config()
+        };
+        let mut exit = sample_server_config();
+        assert!(resolve_via(&exit, &[]).unwrap().is_none());
+
+        exit.via = Some(entry.id.clone());
+        let servers = vec![entry.clone(), exit.clone()];
+        assert_eq!(resolve_via(&exit, &servers).unwrap().unwrap().id, entry.id);
+
+        let err = resolve_via(&exit, &[]).unwrap_err();
+        assert!(err.contains("no longer exists"), "unexpected error: {err}");
+
+        exit.via = Some(exit.id.clone());
+        assert!(resolve_via(&exit, &servers).is_err());
+    }
+
+    #[test]
+    fn resolve_via_rejects_nested_chain() {
+        let first = ServerConfig {
+            id: "first".to_string(),
+            ..sample_server_config()
+        };
+        let mut middle = ServerConfig {
+            id: "middle".to_string(),
+            ..sample_server_config()
+        };
+        middle.via = Some(first.id.clone());
+        let mut exit = sample_server_config();
+        exit.via = Some(middle.id.clone());
+        let err = resolve_via(&exit, &[first, middle]).unwrap_err();
+        assert!(err.contains("only two hops"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn subscription_validate_and_due() {
+        let mut sub = Subscription {
+            id: "sub".to_string(),
+            name: "Provider".to_string(),
+            url: "https://example.com/sub".to_string(),
+            refresh_interval_secs: 3600,
+            last_updated: None,
+            usage: None,
+            profile_title: String::new(),
+            suggested_refresh_secs: None,
+            last_failed: None,
+            failures: 0,
+        };
+        assert!(sub.validate().is_ok());
+        assert!(sub.is_due(1_000));
+
+        sub.last_updated = Some(1_000);
+        assert!(!sub.is_due(1_000 + 3599));
+        assert!(sub.is_due(1_000 + 3600));
+
+        // Failures back off 5, 10, 20 ... minutes, capped at six hours.
+        sub.record_failure(5_000);
+        assert!(!sub.is_due(5_000 + 299));
+        assert!(sub.is_due(5_000 + 300));
+        sub.record_failure(5_300);
+        assert!(!sub.is_due(5_300 + 599));
+        assert!(sub.is_due(5_300 + 600));
+        sub.failures = 40;
+        assert!(!sub.is_due(5_300 + 6 * 3600 - 1));
+        assert!(sub.is_due(5_300 + 6 * 3600));
+        sub.last_failed = None;
+        sub.failures = 0;
+
+        sub.refresh_interval_secs = 0;
+        assert!(sub.validate().is_ok());
+        assert!(!sub.is_due(u64::MAX));
+
+        sub.refresh_interval_secs = 10;
+        assert!(sub.validate().unwrap_err().contains("interval"));
+        sub.refresh_interval_secs = 3600;
+        sub.url = "ftp://example.com/sub".to_string();
+        assert!(sub.validate().unwrap_err().contains("URL"));
+    }
+
+    #[test]
+    fn subscription_quota() {
+        let mut sub = Subscription {
+            id: "sub".to_string(),
+            name: "Provider".to_string(),
+            url: "https://example.com/sub".to_string(),
+            refresh_interval_secs: 3600,
+            last_updated: None,
+            usage: None,
+            profile_title: String::new(),
+            suggested_refresh_secs: None,
+            last_failed: None,
+            failures: 0,
+        };
+        assert!(sub.quota(0).is_none());
+
+        sub.usage = Some(SubscriptionUsage {
+            upload: 1_000,
+            download: 2_000,
+            total: 4_000,
+            expire: Some(500),
+        });
+        let quota = sub.quota(100).unwrap();
+        assert_eq!(quota.title, "Provider");
+        assert_eq!(quota.used, 3_000);
+        assert_eq!(quota.remaining, Some(1_000));
+        assert_eq!(quota.used_percent, Some(75));
+        assert!(!quota.expired);
+        assert!(sub.quota(500).unwrap().expired);
+
+        // Unlimited plan, usage past u64::MAX / 100 doesn't overflow.
+        sub.usage = Some(SubscriptionUsage {
+            upload: u64::MAX,
+            download: 1,
+            total: 0,
+            expire: None,
+        });
+        let quota = sub.quota(100).unwrap();
+        assert_eq!(quota.remaining, None);
+        assert_eq!(quota.used_percent, None);
+        assert_eq!(
+            SubscriptionUsage {
+                upload: u64::MAX / 2,
+                total: u64::MAX / 4,
+                ..SubscriptionUsage::default()
+            }
+            .used_percent(),
+            Some(100)
+        );
+    }
+
+    #[test]
+    fn server_identity_ignores_name_and_case() {
+        let a = sample_server_config();
+        let b = ServerConfig {
+            id: "other".to_string(),
+            name: "Renamed".to_string(),
+            address: " 1.2.3.4".to_string(),
+            uuid: a.uuid.to_ascii_uppercase(),
+            ..a.clone()
+        };
+        assert_eq!(ServerIdentity::of(&a), ServerIdentity::of(&b));
+        for other in [
+            ServerConfig {
+                port: 8443,
+                ..a.clone()
+            },
+            ServerConfig {
+                network: "ws".to_string(),
+                ..a.clone()
+            },
+            ServerConfig {
+                security: "tls".to_string(),
+                ..a.clone()
+            },
+        ] {
+            assert_ne!(ServerIdentity::of(&a), ServerIdentity::of(&other));
+        }
+
+        // Password-authenticated protocols are told apart by the password.
+        let trojan = ServerConfig {
+            protocol: "trojan".to_string(),
+            uuid: String::new(),
+            password: "one".to_string(),
+            ..a.clone()
+        };
+        let other = ServerConfig {
+            password: "two".to_string(),
+            ..trojan.clone()
+        };
+        assert_ne!(ServerIdentity::of(&trojan), ServerIdentity::of(&other));
+        let shadowsocks = ServerConfig {
+            protocol: "shadowsocks".to_string(),
+            ..trojan.clone()
+        };
+        assert_ne!(
+            ServerIdentity::of(&trojan),
+            ServerIdentity::of(&shadowsocks)
+        );
+    }
+
+    #[test]
+    fn merge_import_keeps_wireguard_accounts_apart() {
+        // Two WARP accounts: same endpoint, no UUID or password, other keys.
+        let warp = |id: &str, key: &str| ServerConfig {
+            id: id.to_string(),
+            name: "WARP".to_string(),
+            protocol: "wireguard".to_string(),
+            address: "engage.cloudflareclient.com".to_string(),
+            port: 2408,
+            uuid: String::new(),
+            flow: String::new(),
+            security: "none".to_string(),
+            wg_private_key: key.to_string(),
+            wg_peer_public_key: "bmXOC+F1FxEMF9dyiK2H5/1SUtzH0JuVo51h2wPfgyo=".to_string(),
+            ..ServerConfig::default()
+        };
+        let keys = [
+            "cGFzc3dvcmQxcGFzc3dvcmQxcGFzc3dvcmQxMjM0NTY=",
+            "cGFzc3dvcmQycGFzc3dvcmQycGFzc3dvcmQyMjM0NTY=",
+            "cGFzc3dvcmQzcGFzc3dvcmQzcGFzc3dvcmQzMjM0NTY=",
+        ];
+        let mut servers = vec![warp("saved", keys[0])];
+        let report = merge_import(
+            &mut servers,
+            vec![warp("a", keys[0]), warp("b", keys[1]), warp("c", keys[2])],
+            MergeStrategy::Skip,
+        );
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].id, "a");
+        assert_eq!(report.added.len(), 2);
+        assert_eq!(servers.len(), 3);
+        assert_eq!(report.added[0].wg_private_key, keys[1]);
+        assert_eq!(report.added[1].wg_private_key, keys[2]);
+    }
+
+    #[test]
+    fn merge_import_strategies() {
+        let saved = sample_server_config();
+        let duplicate = ServerConfig {
+            id: "incoming-1".to_string(),
+            name: "Same server".to_string(),
+            ..saved.clone()
+        };
+        let fresh = ServerConfig {
+            id: "incoming-2".to_string(),
+            name: "New".to_string(),
+            address: "5.6.7.8".to_string(),
+            via: Some("incoming-1".to_string()),
+            subscription_id: Some("elsewhere".to_string()),
+            ..saved.clone()
+        };
+        let batch = vec![duplicate.clone(), fresh.clone(), duplicate.clone()];
+        assert_eq!(
+            find_duplicates(std::slice::from_ref(&saved), &batch),
+            vec!["Same server", "Same server"]
+        );
+
+        let mut servers = vec![saved.clone()];
+        let report = merge_import(&mut servers, batch.clone(), MergeStrategy::Skip);
+        assert_eq!(servers.len(), 2);
+        assert_eq!(servers[0].name, "Test");
+        assert_eq!(report.added.len(), 1);
+        assert_ne!(report.added[0].id, "incoming-2");
+        // The hop was merged into the saved server.
+        assert_eq!(report.added[0].via.as_deref(), Some("test-id-1234"));
+        assert_eq!(report.added[0].subscription_id, None);
+        assert!(report.updated.is_empty());
+        assert_eq!(report.skipped.len(), 2);
+        assert_eq!(report.skipped[0].id, "incoming-1");
+
+        let mut servers = vec![saved.clone()];
+        let report = merge_import(&mut servers, batch.clone(), MergeStrategy::Overwrite);
+        assert_eq!(servers.len(), 2);
+        assert_eq!(servers[0].id, "test-id-1234");
+        assert_eq!(servers[0].name, "Same server");
+        assert_eq!(report.updated.len(), 1);
+        assert_eq!(report.added.len(), 1);
+        assert_eq!(report.skipped.len(), 1);
+
+        let mut servers = vec![saved];
+        let report = merge_import(&mut servers, batch, MergeStrategy::KeepBoth);
+        assert_eq!(servers.len(), 4);
+        assert_eq!(report.added.len(), 3);
+        assert!(report.updated.is_empty() && report.skipped.is_empty());
+        assert!(report.added.iter().all(|s| s.subscription_id.is_none()));
+        // Chains point at the first copy imported alongside, not the saved entry.
+        assert_eq!(report.added[1].via.as_ref(), Some(&report.added[0].id));
+    }
 }
@@ -1,3 +1,4 @@
+use std::net::{IpAddr, ToSocketAddrs};
 use std::process::Command;
 
 use log::{info, warn};
@@ -140,6 +141,95 @@ pub fn detect_default_gateway_and_ip() -> Option<(String, String, String)> {
     Some((gw, dev, local_ip))
 }
 
+/// The host's own IPv6 uplink, used in TUN mode to route IPv6 server
+/// addresses around the TUN and to source xray's own IPv6 connections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ipv6Uplink {
+    /// Default gateway, usually link-local (fe80::...).
+    pub gateway: String,
+    pub dev: String,
+    /// Global address on `dev`; None if the host only has a link-local one.
+    pub local_ip: Option<String>,
+}
+
+/// Detect the physical IPv6 default route and the global address of its
+/// interface. None when the host has no IPv6 connectivity.
+pub fn detect_ipv6_uplink() -> Option<Ipv6Uplink> {
+    let route_out = Command::new("ip")
+        .args(["-6", "-j", "route", "show", "default"])
+        .output()
+        .ok()?;
+    if !route_out.status.success() {
+        return None;
+    }
+    let routes: Vec<IpRoute> = serde_json::from_slice(&route_out.stdout).ok()?;
+    let (gateway, dev) = pick_physical_gateway(&routes)?;
+
+    let local_ip = Command::new("ip")
+        .args(["-j", "-6", "addr", "show", "dev", &dev, "scope", "global"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| parse_global_ipv6(&String::from_utf8_lossy(&o.stdout)));
+
+    info!(
+        "Detected physical IPv6 gateway: {gateway} via {dev} (local IP: {})",
+        local_ip.as_deref().unwrap_or("none")
+    );
+    Some(Ipv6Uplink {
+        gateway,
+        dev,
+        local_ip,
+    })
+}
+
+/// Whether xray has to reach `address` over IPv6: an IPv6 literal (bracketed
+/// or not), or a hostname with AAAA but no A records. Looked up before the
+/// TUN is up, so the query still goes out over the physical interface.
+pub fn dials_over_ipv6(address: &str) -> bool {
+    let host = address.trim().trim_start_matches('[').trim_end_matches(']');
+    match host.parse::<IpAddr>() {
+        Ok(ip) => ip.is_ipv6(),
+        Err(_) => match (host, 0).to_socket_addrs() {
+            Ok(addrs) => {
+                let addrs: Vec<_> = addrs.collect();
+                !addrs.is_empty() && addrs.iter().all(|a| a.is_ipv6())
+            }
+            Err(e) => {
+                warn!("Failed to resolve {host}: {e}");
+                false
+            }
+        },
+    }
+}
+
+/// First stable global address in `ip -j -6 addr show` output. Temporary
+/// (privacy) addresses are skipped: they rotate while connected.
+fn parse_global_ipv6(json: &str) -> Option<String> {
+    let addr_json: serde_json::Value = serde_json::from_str(json).ok()?;
+    addr_json
+        .as_array()?
+        .iter()
+        .filter_map(|link| link.get("addr_info")?.as_array())
+        .flatten()
+        .filter(|info| info.get("family").and_then(|f| f.as_str()) == Some("inet6"))
+        .filter(|info| info.get("scope").and_then(|s| s.as_str()) == Some("global"))
+        .filter(|info| info.get("temporary").is_none())
+        .find_map(|info| info.get("local")?.as_str())
+        .map(str::to_string)
+}
+
+/// First default route with a gateway on a physical (non-VPN, non-virtual)
+/// interface.
+fn pick_physical_gateway(routes: &[IpRoute]) -> Option<(String, String)> {
+    routes.iter().find_map(|r| {
+        let gw = r.gateway.as_deref()?;
+        let dev = r.dev.as_deref()?;
+        (!is_vpn_interface(dev) && !is_virtual_interface(dev))
+            .then(|| (gw.to_string(), dev.to_string()))
+    })
+}
+
 /// Check whether an interface name looks like a VPN interface.
 pub fn is_vpn_interface(name: &str) -> bool {
     let prefixes = ["tun", "tap", "wg", "ppp", "nordlynx", "tailscale"];
@@ -382,6 +472,39 @@ pub fn detect_vpn_dns_servers() -> Vec<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_dials_over_ipv6() {
+        assert!(dials_over_ipv6("2001:db8::1"));
+        assert!(dials_over_ipv6("[2001:db8::1]"));
+        assert!(!dials_over_ipv6("1.2.3.4"));
+    }
+
+    #[test]
+    fn test_pick_physical_gateway6() {
+        let json = r#"[
+            {"dst":"default","gateway":"fe80::1","dev":"tun0","protocol":"static"},
+            {"dst":"default","gateway":"fe80::2","dev":"docker0"},
+            {"dst":"default","gateway":"fe80::3","dev":"wlp2s0","protocol":"ra"}
+        ]"#;
+        let routes: Vec<IpRoute> = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            pick_physical_gateway(&routes),
+            Some(("fe80::3".to_string(), "wlp2s0".to_string()))
+        );
+        assert_eq!(pick_physical_gateway(&routes[..2]), None);
+    }
+
+    #[test]
+    fn test_parse_global_ipv6() {
+        let json = r#"[{"ifname":"wlp2s0","addr_info":[
+            {"family":"inet6","local":"2001:db8::1234","scope":"global","temporary":true},
+            {"family":"inet6","local":"2001:db8::1","scope":"global","dynamic":true},
+            {"family":"inet6","local":"fe80::1","scope":"link"}
+        ]}]"#;
+        assert_eq!(parse_global_ipv6(json), Some("2001:db8::1".to_string()));
+        assert_eq!(parse_global_ipv6("[]"), None);
+    }
+
     #[test]
     fn test_parse_vpn_routes_with_tun() {
         let json = r#"[
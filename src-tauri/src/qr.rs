@@ -0,0 +1,293 @@
+use std::io::Cursor;
+use std::path::Path;
+
+use image::{GrayImage, ImageFormat, Luma};
+use qrcode::render::svg;
+use qrcode::{EcLevel, QrCode};
+
+use crate::models::{AppError, ImportEntryError, ParsedImport, ServerConfig};
+use crate::subscription::decode_body;
+use crate::uri::{parse_vless_uri, share_uri};
+
+/// Most share-link bytes packed into one code of a bundle. A version-40 code
+/// holds more, but phone cameras struggle well before that.
+const BUNDLE_CHUNK_BYTES: usize = 1200;
+
+/// Smallest edge of a rendered code in pixels (SVG user units).
+const MIN_SIZE: u32 = 320;
+
+/// Error-correction level from its letter; empty means "M", which survives a
+/// little screen glare without growing the code much.
+fn ec_level(level: &str) -> Result<EcLevel, AppError> {
+    match level.trim().to_ascii_uppercase().as_str() {
+        "L" => Ok(EcLevel::L),
+        "" | "M" => Ok(EcLevel::M),
+        "Q" => Ok(EcLevel::Q),
+        "H" => Ok(EcLevel::H),
+        other => Err(AppError::Config(format!(
+            "Unsupported error correction level '{other}' (expected 'L', 'M', 'Q' or 'H')"
+        ))),
+    }
+}
+
+/// Encode `data` as one QR code, returned as the bytes of a PNG or SVG file.
+pub fn render_qr(data: &str, format: &str, level: &str) -> Result<Vec<u8>, AppError> {
+    let code = QrCode::with_error_correction_level(data, ec_level(level)?)
+        .map_err(|e| AppError::Config(format!("Can't encode QR code: {e}")))?;
+    match format {
+        "png" => {
+            let image = code
+                .render::<Luma<u8>>()
+                .min_dimensions(MIN_SIZE, MIN_SIZE)
+                .build();
+            let mut png = Vec::new();
+            image
+                .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+                .map_err(|e| AppError::Config(format!("Can't encode PNG: {e}")))?;
+            Ok(png)
+        }
+        "svg" => Ok(code
+            .render::<svg::Color>()
+            .min_dimensions(MIN_SIZE, MIN_SIZE)
+            .build()
+            .into_bytes()),
+        other => Err(AppError::Config(format!(
+            "Unsupported QR format '{other}' (expected 'png' or 'svg')"
+        ))),
+    }
+}
+
+/// Pack share links into newline-separated groups, the layout of a plain-text
+/// subscription body, keeping each group within [`BUNDLE_CHUNK_BYTES`]. A
+/// link longer than that gets a group of its own.
+fn bundle_chunks(uris: &[String]) -> Vec<String> {
+    let mut chunks: Vec<String> = Vec::new();
+    for uri in uris {
+        match chunks.last_mut() {
+            Some(chunk) if chunk.len() + 1 + uri.len() <= BUNDLE_CHUNK_BYTES => {
+                chunk.push('\n');
+                chunk.push_str(uri);
+            }
+            _ => chunks.push(uri.clone()),
+        }
+    }
+    chunks
+}
+
+/// Render several servers as a sequence of QR codes, each carrying as many
+/// of their share links as fit comfortably.
+pub fn render_bundle(
+    servers: &[ServerConfig],
+    format: &str,
+    level: &str,
+) -> Result<Vec<Vec<u8>>, AppError> {
+    if servers.is_empty() {
+        return Err(AppError::Config("No servers to export".to_string()));
+    }
+    let uris = servers
+        .iter()
+        .map(|server| {
+            share_uri(server).map_err(|e| match e {
+                AppError::Config(msg) => AppError::Config(format!("{}: {msg}", server.name)),
+                other => other,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    bundle_chunks(&uris)
+        .iter()
+        .map(|chunk| render_qr(chunk, format, level))
+        .collect()
+}
+
+/// Decode every QR code in a PNG or JPEG file (a photo or a screenshot) and
+/// parse its payload: one share link, a bundle from [`render_bundle`], or a
+/// base64 subscription body.
+pub fn decode_qr_image(path: &Path) -> Result<ParsedImport, AppError> {
+    let image = image::open(path)
+        .map_err(|e| AppError::Config(format!("Can't read image: {e}")))?
+        .to_luma8();
+    decode_qr_codes(image)
+}
+
+/// Servers from every code in `image`. `errors` has an entry, indexed by
+/// code, for each code that can't be read and each link that doesn't parse.
+fn decode_qr_codes(mut image: GrayImage) -> Result<ParsedImport, AppError> {
+    let mut payloads = scan(&image);
+    if payloads.is_empty() {
+        // Light-on-dark codes (dark-mode screenshots) are only found inverted.
+        image::imageops::invert(&mut image);
+        payloads = scan(&image);
+    }
+    if payloads.is_empty() {
+        return Err(AppError::Config(
+            "No QR code found in the image".to_string(),
+        ));
+    }
+
+    let mut parsed = ParsedImport::default();
+    for (index, payload) in payloads.into_iter().enumerate() {
+        let text = payload.and_then(|payload| {
+            decode_body(&payload).map_err(|_| "QR code doesn't contain a share link".to_string())
+        });
+        let text = match text {
+            Ok(text) => text,
+            Err(error) => {
+                parsed.errors.push(ImportEntryError {
+                    index,
+                    name: String::new(),
+                    error,
+                });
+                continue;
+            }
+        };
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match parse_vless_uri(line) {
+                Ok(server) => parsed.servers.push(server),
+                Err(e) => parsed.errors.push(ImportEntryError {
+                    index,
+                    name: String::new(),
+                    error: e.to_string(),
+                }),
+            }
+        }
+    }
+    Ok(parsed)
+}
+
+/// The payload of each QR code found in `image`.
+fn scan(image: &GrayImage) -> Vec<Result<String, String>> {
+    let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+        image.width() as usize,
+        image.height() as usize,
+        |x, y| image.get_pixel(x as u32, y as u32).0[0],
+    );
+    prepared
+        .detect_grids()
+        .iter()
+        .map(|grid| {
+            grid.decode()
+                .map(|(_, content)| content)
+                .map_err(|e| format!("Can't decode QR code: {e}"))
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn export_qr_code(
+    server_config: ServerConfig,
+    format: String,
+    ec_level: String,
+) -> Result<Vec<u8>, String> {
+    let uri = share_uri(&server_config).map_err(|e| e.to_string())?;
+    render_qr(&uri, &format, &ec_level).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn export_qr_bundle(
+    servers: Vec<ServerConfig>,
+    format: String,
+    ec_level: String,
+) -> Result<Vec<Vec<u8>>, String> {
+    render_bundle(&servers, &format, &ec_level).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn parse_qr_image_cmd(path: String) -> Result<ParsedImport, String> {
+    // Scanning a large screenshot takes a moment; keep it off the main thread.
+    tauri::async_runtime::spawn_blocking(move || decode_qr_image(Path::new(&path)))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URI: &str = "vless://aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee@1.2.3.4:443?encryption=none&security=reality&sni=www.microsoft.com&fp=chrome&pbk=abc123&sid=def456&type=tcp#Test";
+
+    #[test]
+    fn render_png_and_svg() {
+        let png = render_qr(URI, "png", "H").unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+
+        let svg = String::from_utf8(render_qr(URI, "svg", "").unwrap()).unwrap();
+        assert!(svg.contains("<svg"), "unexpected svg: {svg}");
+
+        let err = render_qr(URI, "gif", "M").unwrap_err().to_string();
+        assert!(err.contains("'gif'"), "unexpected error: {err}");
+        let err = render_qr(URI, "png", "X").unwrap_err().to_string();
+        assert!(err.contains("'X'"), "unexpected error: {err}");
+        assert!(render_qr(&"x".repeat(4000), "png", "L").is_err());
+    }
+
+    #[test]
+    fn bundle_packs_links_into_chunks() {
+        let uris: Vec<String> = (0..10).map(|i| format!("{URI}{i}")).collect();
+        let chunks = bundle_chunks(&uris);
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| c.len() <= BUNDLE_CHUNK_BYTES));
+        let lines: Vec<&str> = chunks.iter().flat_map(|c| c.lines()).collect();
+        assert_eq!(lines, uris.iter().map(String::as_str).collect::<Vec<_>>());
+
+        let long = vec!["x".repeat(BUNDLE_CHUNK_BYTES + 1), "y".to_string()];
+        assert_eq!(bundle_chunks(&long).len(), 2);
+    }
+
+    #[test]
+    fn bundle_rejects_raw_servers() {
+        let server = crate::uri::parse_vless_uri(URI).unwrap();
+        let codes = render_bundle(std::slice::from_ref(&server), "svg", "M").unwrap();
+        assert_eq!(codes.len(), 1);
+
+        let raw = ServerConfig {
+            name: "custom".to_string(),
+            protocol: "raw".to_string(),
+            ..server
+        };
+        let err = render_bundle(&[raw], "svg", "M").unwrap_err().to_string();
+        assert!(err.contains("custom"), "unexpected error: {err}");
+        assert!(render_bundle(&[], "svg", "M").is_err());
+    }
+
+    fn png_image(data: &str) -> GrayImage {
+        let png = render_qr(data, "png", "M").unwrap();
+        image::load_from_memory(&png).unwrap().to_luma8()
+    }
+
+    #[test]
+    fn decode_rendered_codes() {
+        let parsed = decode_qr_codes(png_image(URI)).unwrap();
+        assert_eq!(parsed.servers.len(), 1);
+        assert_eq!(parsed.servers[0].address, "1.2.3.4");
+        assert!(parsed.errors.is_empty());
+
+        // A bundle code with one bad link, and a light-on-dark code.
+        let parsed = decode_qr_codes(png_image(&format!("{URI}\nfoo://bar"))).unwrap();
+        assert_eq!(parsed.servers.len(), 1);
+        assert_eq!(parsed.errors.len(), 1);
+        assert_eq!(parsed.errors[0].index, 0);
+
+        let mut inverted = png_image(URI);
+        image::imageops::invert(&mut inverted);
+        assert_eq!(decode_qr_codes(inverted).unwrap().servers.len(), 1);
+
+        let err = decode_qr_codes(png_image("WIFI:S:home;;")).unwrap().errors;
+        assert!(
+            err[0].error.contains("share link"),
+            "unexpected: {}",
+            err[0].error
+        );
+    }
+
+    #[test]
+    fn decode_rejects_blank_and_unreadable_images() {
+        let blank = GrayImage::from_pixel(64, 64, Luma([255]));
+        let err = decode_qr_codes(blank).unwrap_err().to_string();
+        assert!(err.contains("No QR code"), "unexpected error: {err}");
+        assert!(decode_qr_image(Path::new("/nonexistent/qr.png")).is_err());
+    }
+}
@@ -0,0 +1,389 @@
+use serde_json::{json, Map, Value};
+
+use crate::models::{
+    AppError, ImportEntryError, ParsedImport, RealitySettings, ServerConfig, TlsSettings,
+};
+
+/// sing-box outbound types that route or resolve rather than proxy; they are
+/// skipped silently instead of being reported as unsupported.
+const NON_PROXY_TYPES: &[&str] = &["direct", "block", "dns", "selector", "urltest"];
+
+/// Read VLESS outbounds from sing-box JSON: a full config with an
+/// `outbounds` array, a bare array of outbounds, or a single outbound.
+/// Other proxy types, and VLESS outbounds that can't be expressed here, are
+/// reported in `errors`.
+pub fn parse_singbox_json(content: &str) -> Result<ParsedImport, AppError> {
+    let root: Value = serde_json::from_str(content)
+        .map_err(|e| AppError::Config(format!("Invalid JSON: {e}")))?;
+    let outbounds = match &root {
+        Value::Array(list) => list.as_slice(),
+        Value::Object(obj) => match obj.get("outbounds") {
+            Some(Value::Array(list)) => list.as_slice(),
+            Some(_) => return Err(AppError::Config("'outbounds' must be an array".to_string())),
+            None => std::slice::from_ref(&root),
+        },
+        _ => {
+            return Err(AppError::Config(
+                "Expected a sing-box config or outbound object".to_string(),
+            ))
+        }
+    };
+
+    let mut parsed = ParsedImport::default();
+    for (index, outbound) in outbounds.iter().enumerate() {
+        let kind = str_field(outbound, "type");
+        if NON_PROXY_TYPES.contains(&kind.as_str()) {
+            continue;
+        }
+        match outbound_to_server(outbound) {
+            Ok(server) => parsed.servers.push(server),
+            Err(error) => parsed.errors.push(ImportEntryError {
+                index,
+                name: str_field(outbound, "tag"),
+                error,
+            }),
+        }
+    }
+    Ok(parsed)
+}
+
+fn str_field(value: &Value, key: &str) -> String {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+fn bool_field(value: &Value, key: &str) -> bool {
+    value.get(key).and_then(Value::as_bool).unwrap_or(false)
+}
+
+fn outbound_to_server(outbound: &Value) -> Result<ServerConfig, String> {
+    let kind = str_field(outbound, "type");
+    if kind != "vless" {
+        return Err(format!("Unsupported outbound type '{kind}'"));
+    }
+
+    let address = str_field(outbound, "server");
+    if address.is_empty() {
+        return Err("Missing server".to_string());
+    }
+    let port = outbound
+        .get("server_port")
+        .and_then(Value::as_u64)
+        .and_then(|p| u16::try_from(p).ok())
+        .ok_or_else(|| "Missing or invalid server_port".to_string())?;
+
+    let mut server = ServerConfig {
+        name: str_field(outbound, "tag"),
+        address,
+        port,
+        uuid: str_field(outbound, "uuid"),
+        flow: str_field(outbound, "flow"),
+        ..ServerConfig::default()
+    };
+    if server.name.is_empty() {
+        server.name = server.address.clone();
+    }
+
+    let tls = outbound.get("tls").unwrap_or(&Value::Null);
+    if !bool_field(tls, "enabled") {
+        return Err("VLESS without TLS or REALITY is not supported".to_string());
+    }
+    let reality = tls.get("reality").unwrap_or(&Value::Null);
+    server.security = if bool_field(reality, "enabled") {
+        server.reality = RealitySettings {
+            public_key: str_field(reality, "public_key"),
+            short_id: str_field(reality, "short_id"),
+            ..RealitySettings::default()
+        };
+        "reality".to_string()
+    } else {
+        "tls".to_string()
+    };
+    server.tls = TlsSettings {
+        server_name: str_field(tls, "server_name"),
+        alpn: string_list(tls.get("alpn")),
+        allow_insecure: bool_field(tls, "insecure"),
+        ..TlsSettings::default()
+    };
+    let utls = tls.get("utls").unwrap_or(&Value::Null);
+    let fingerprint = str_field(utls, "fingerprint");
+    if bool_field(utls, "enabled") && !fingerprint.is_empty() {
+        server.tls.fingerprint = fingerprint;
+    }
+
+    let transport = outbound.get("transport").unwrap_or(&Value::Null);
+    server.network = match str_field(transport, "type").as_str() {
+        "" => "tcp".to_string(),
+        "ws" => {
+            server.ws_path = str_field(transport, "path");
+            server.ws_host = transport
+                .get("headers")
+                .map(|h| str_field(h, "Host"))
+                .unwrap_or_default();
+            let header = str_field(transport, "early_data_header_name");
+            if header.eq_ignore_ascii_case("Sec-WebSocket-Protocol") {
+                server.ws_early_data = transport
+                    .get("max_early_data")
+                    .and_then(Value::as_u64)
+                    .and_then(|n| u32::try_from(n).ok())
+                    .unwrap_or(0);
+            }
+            if server.tls.server_name.is_empty() {
+                server.tls.server_name = server.ws_host.clone();
+            }
+            "ws".to_string()
+        }
+        "grpc" => {
+            server.grpc_service_name = str_field(transport, "service_name");
+            "grpc".to_string()
+        }
+        other => return Err(format!("Unsupported transport '{other}'")),
+    };
+    if server.network != "tcp" {
+        server.flow.clear();
+    }
+
+    server.validate()?;
+    Ok(server)
+}
+
+/// A JSON string array; sing-box also accepts a lone string for lists.
+fn string_list(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::String(s)) => vec![s.clone()],
+        Some(Value::Array(list)) => list
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Build the sing-box outbound equivalent to `server`. sing-box has no xHTTP
+/// transport and no VLESS post-quantum encryption, so such servers (and
+/// non-VLESS ones) are rejected.
+pub fn to_singbox_outbound(server: &ServerConfig) -> Result<Value, AppError> {
+    if server.protocol != "vless" {
+        return Err(AppError::Config(format!(
+            "sing-box export supports VLESS only, not {}",
+            server.protocol
+        )));
+    }
+    if server.vless_encryption() != "none" {
+        return Err(AppError::Config(
+            "sing-box does not support VLESS encryption".to_string(),
+        ));
+    }
+
+    let mut tls = Map::new();
+    tls.insert("enabled".to_string(), json!(true));
+    if !server.tls.server_name.is_empty() {
+        tls.insert("server_name".to_string(), json!(server.tls.server_name));
+    }
+    if !server.tls.fingerprint.is_empty() {
+        tls.insert(
+            "utls".to_string(),
+            json!({ "enabled": true, "fingerprint": server.tls.fingerprint }),
+        );
+    }
+    if server.security == "tls" {
+        if !server.tls.alpn.is_empty() {
+            tls.insert("alpn".to_string(), json!(server.tls.alpn));
+        }
+        if server.tls.allow_insecure {
+            tls.insert("insecure".to_string(), json!(true));
+        }
+    } else {
+        tls.insert(
+            "reality".to_string(),
+            json!({
+                "enabled": true,
+                "public_key": server.reality.public_key,
+                "short_id": server.reality.short_id
+            }),
+        );
+    }
+
+    let mut outbound = json!({
+        "type": "vless",
+        "tag": server.name,
+        "server": server.address,
+        "server_port": server.port,
+        "uuid": server.uuid,
+        // xray multiplexes VLESS UDP as XUDP by default.
+        "packet_encoding": "xudp",
+        "tls": tls
+    });
+    if !server.flow.is_empty() {
+        outbound["flow"] = json!(server.flow);
+    }
+
+    match server.network.as_str() {
+        "tcp" => {}
+        "ws" => {
+            let mut transport = json!({
+                "type": "ws",
+                "path": if server.ws_path.is_empty() { "/" } else { &server.ws_path }
+            });
+            if !server.ws_host.is_empty() {
+                transport["headers"] = json!({ "Host": server.ws_host });
+            }
+            if server.ws_early_data > 0 {
+                transport["max_early_data"] = json!(server.ws_early_data);
+                transport["early_data_header_name"] = json!("Sec-WebSocket-Protocol");
+            }
+            outbound["transport"] = transport;
+        }
+        "grpc" => {
+            outbound["transport"] =
+                json!({ "type": "grpc", "service_name": server.grpc_service_name });
+        }
+        other => {
+            return Err(AppError::Config(format!(
+                "sing-box has no equivalent of the {other} transport"
+            )))
+        }
+    }
+    Ok(outbound)
+}
+
+#[tauri::command]
+pub fn parse_singbox_json_cmd(content: String) -> Result<ParsedImport, String> {
+    parse_singbox_json(&content).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn export_singbox_outbound(server_config: ServerConfig) -> Result<String, String> {
+    let outbound = to_singbox_outbound(&server_config).map_err(|e| e.to_string())?;
+    serde_json::to_string_pretty(&outbound).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"{
+      "log": { "level": "warn" },
+      "outbounds": [
+        {
+          "type": "vless",
+          "tag": "reality",
+          "server": "1.2.3.4",
+          "server_port": 443,
+          "uuid": "aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee",
+          "flow": "xtls-rprx-vision",
+          "packet_encoding": "xudp",
+          "tls": {
+            "enabled": true,
+            "server_name": "www.microsoft.com",
+            "utls": { "enabled": true, "fingerprint": "safari" },
+            "reality": { "enabled": true, "public_key": "abc123", "short_id": "def456" }
+          }
+        },
+        {
+          "type": "vless",
+          "tag": "cdn",
+          "server": "cdn.example.com",
+          "server_port": 8443,
+          "uuid": "aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee",
+          "tls": { "enabled": true, "alpn": "http/1.1" },
+          "transport": {
+            "type": "ws",
+            "path": "/ray",
+            "headers": { "Host": "edge.example.com" },
+            "max_early_data": 2048,
+            "early_data_header_name": "Sec-WebSocket-Protocol"
+          }
+        },
+        { "type": "vless", "tag": "h2", "server": "1.1.1.1", "server_port": 443,
+          "uuid": "aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee",
+          "tls": { "enabled": true, "server_name": "a.com" },
+          "transport": { "type": "http" } },
+        { "type": "trojan", "tag": "trojan", "server": "2.2.2.2", "server_port": 443 },
+        { "type": "direct", "tag": "direct" },
+        { "type": "selector", "tag": "select", "outbounds": ["reality"] }
+      ]
+    }"#;
+
+    #[test]
+    fn parse_config_outbounds() {
+        let parsed = parse_singbox_json(CONFIG).unwrap();
+        assert_eq!(parsed.servers.len(), 2);
+
+        let reality = &parsed.servers[0];
+        assert_eq!(reality.name, "reality");
+        assert_eq!(reality.security, "reality");
+        assert_eq!(reality.flow, "xtls-rprx-vision");
+        assert_eq!(reality.tls.server_name, "www.microsoft.com");
+        assert_eq!(reality.tls.fingerprint, "safari");
+        assert_eq!(reality.reality.public_key, "abc123");
+
+        let cdn = &parsed.servers[1];
+        assert_eq!(cdn.security, "tls");
+        assert_eq!(cdn.network, "ws");
+        assert_eq!(cdn.ws_path, "/ray");
+        assert_eq!(cdn.ws_early_data, 2048);
+        assert_eq!(cdn.tls.server_name, "edge.example.com");
+        assert_eq!(cdn.tls.alpn, vec!["http/1.1"]);
+
+        // direct / selector are skipped without a report
+        assert_eq!(parsed.errors.len(), 2);
+        assert_eq!(parsed.errors[0].name, "h2");
+        assert!(parsed.errors[0].error.contains("'http'"));
+        assert_eq!(parsed.errors[1].index, 3);
+        assert!(parsed.errors[1].error.contains("'trojan'"));
+    }
+
+    #[test]
+    fn parse_single_outbound_and_bad_input() {
+        let outbound = to_singbox_outbound(&parse_singbox_json(CONFIG).unwrap().servers[0])
+            .unwrap()
+            .to_string();
+        assert_eq!(parse_singbox_json(&outbound).unwrap().servers.len(), 1);
+        let list = format!("[{outbound}]");
+        assert_eq!(parse_singbox_json(&list).unwrap().servers.len(), 1);
+
+        assert!(parse_singbox_json("{").is_err());
+        assert!(parse_singbox_json("42").is_err());
+        assert!(parse_singbox_json(r#"{"outbounds": {}}"#).is_err());
+    }
+
+    #[test]
+    fn export_roundtrip() {
+        for server in parse_singbox_json(CONFIG).unwrap().servers {
+            let outbound = to_singbox_outbound(&server).unwrap();
+            let back = parse_singbox_json(&outbound.to_string())
+                .unwrap()
+                .servers
+                .remove(0);
+            assert_eq!(back.name, server.name);
+            assert_eq!(back.address, server.address);
+            assert_eq!(back.port, server.port);
+            assert_eq!(back.flow, server.flow);
+            assert_eq!(back.security, server.security);
+            assert_eq!(back.network, server.network);
+            assert_eq!(back.ws_path, server.ws_path);
+            assert_eq!(back.ws_host, server.ws_host);
+            assert_eq!(back.ws_early_data, server.ws_early_data);
+            assert_eq!(back.reality, server.reality);
+            assert_eq!(back.tls, server.tls);
+        }
+    }
+
+    #[test]
+    fn export_rejects_unsupported() {
+        let mut server = parse_singbox_json(CONFIG).unwrap().servers.remove(0);
+        server.network = "xhttp".to_string();
+        let err = to_singbox_outbound(&server).unwrap_err().to_string();
+        assert!(err.contains("xhttp"), "unexpected error: {err}");
+
+        server.network = "tcp".to_string();
+        server.protocol = "trojan".to_string();
+        assert!(to_singbox_outbound(&server).is_err());
+    }
+}
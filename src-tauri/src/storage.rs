@@ -3,10 +3,11 @@ use std::path::PathBuf;
 
 use tauri::{AppHandle, Manager, Runtime};
 
-use crate::models::{AppError, AppSettings, ServerConfig};
+use crate::models::{self, AppError, AppSettings, ServerConfig, Subscription};
 
 const SERVERS_FILE: &str = "servers.json";
 const SETTINGS_FILE: &str = "settings.json";
+const SUBSCRIPTIONS_FILE: &str = "subscriptions.json";
 
 fn servers_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, AppError> {
     let config_dir = app
@@ -22,7 +23,7 @@ pub fn load_servers<R: Runtime>(app: &AppHandle<R>) -> Result<Vec<ServerConfig>,
         return Ok(Vec::new());
     }
     let data = fs::read_to_string(&path)?;
-    let servers: Vec<ServerConfig> = serde_json::from_str(&data)?;
+    let servers = models::servers_from_json(&data)?;
     Ok(servers)
 }
 
@@ -85,6 +86,38 @@ pub fn save_settings<R: Runtime>(
     Ok(())
 }
 
+fn subscriptions_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, AppError> {
+    let config_dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| AppError::Config(format!("Failed to get app config dir: {e}")))?;
+    Ok(config_dir.join(SUBSCRIPTIONS_FILE))
+}
+
+pub fn load_subscriptions<R: Runtime>(app: &AppHandle<R>) -> Result<Vec<Subscription>, AppError> {
+    let path = subscriptions_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let data = fs::read_to_string(&path)?;
+    Ok(serde_json::from_str(&data)?)
+}
+
+pub fn save_subscriptions<R: Runtime>(
+    app: &AppHandle<R>,
+    subscriptions: &[Subscription],
+) -> Result<(), AppError> {
+    let path = subscriptions_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let data = serde_json::to_string_pretty(subscriptions)?;
+    fs::write(&path, &data)?;
+    // Subscription URLs usually embed the account token.
+    set_restrictive_permissions(&path);
+    Ok(())
+}
+
 /// Set file permissions to 0o600 (owner read/write only) on Unix.
 #[cfg(unix)]
 fn set_restrictive_permissions(path: &std::path::Path) {
@@ -0,0 +1,575 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD};
+use base64::Engine;
+use log::{info, warn};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::models::{AppError, ServerConfig, ServerIdentity, Subscription, SubscriptionUsage};
+use crate::storage;
+use crate::uri;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(20);
+const USER_AGENT: &str = concat!("rustvpn/", env!("CARGO_PKG_VERSION"));
+/// How often the background loop checks for subscriptions that are due.
+const REFRESH_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// Used-traffic percentages that raise a `subscription-quota` event when a
+/// refresh crosses them.
+const QUOTA_THRESHOLDS: [u8; 3] = [80, 90, 100];
+
+/// A subscription response: the body plus the metadata providers send in
+/// headers.
+#[derive(Debug, Clone, Default)]
+pub struct Fetched {
+    pub body: String,
+    pub usage: Option<SubscriptionUsage>,
+    pub title: Option<String>,
+    pub update_interval_secs: Option<u64>,
+}
+
+/// Payload of the `subscription-quota` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaAlert {
+    pub subscription_id: String,
+    pub name: String,
+    pub threshold_percent: u8,
+    pub used: u64,
+    pub total: u64,
+}
+
+/// Download a subscription.
+pub fn fetch(url: &str) -> Result<Fetched, AppError> {
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(FETCH_TIMEOUT))
+        .build()
+        .into();
+    let mut response = agent
+        .get(url)
+        .header("User-Agent", USER_AGENT)
+        .call()
+        .map_err(|e| AppError::Network(format!("Failed to fetch subscription: {e}")))?;
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    let usage = header("subscription-userinfo").and_then(|v| parse_userinfo(&v));
+    let title = header("profile-title").and_then(|v| parse_profile_title(&v));
+    let update_interval_secs =
+        header("profile-update-interval").and_then(|v| parse_update_interval(&v));
+    let body = response
+        .body_mut()
+        .read_to_string()
+        .map_err(|e| AppError::Network(format!("Failed to read subscription: {e}")))?;
+    Ok(Fetched {
+        body,
+        usage,
+        title,
+        update_interval_secs,
+    })
+}
+
+/// Parse `subscription-userinfo: upload=N; download=N; total=N; expire=TS`.
+/// Missing counters are 0; `expire=0` (or none) means it never expires.
+fn parse_userinfo(value: &str) -> Option<SubscriptionUsage> {
+    let mut usage = SubscriptionUsage::default();
+    let mut any = false;
+    for part in value.split(';') {
+        let Some((key, number)) = part.split_once('=') else {
+            continue;
+        };
+        // Some panels send floats ("1.5e+10"); take the integer part.
+        let number = number.trim();
+        let Some(number) = number
+            .parse::<u64>()
+            .ok()
+            .or_else(|| number.parse::<f64>().ok().map(|f| f.max(0.0) as u64))
+        else {
+            continue;
+        };
+        match key.trim().to_ascii_lowercase().as_str() {
+            "upload" => usage.upload = number,
+            "download" => usage.download = number,
+            "total" => usage.total = number,
+            "expire" => usage.expire = (number > 0).then_some(number),
+            _ => continue,
+        }
+        any = true;
+    }
+    any.then_some(usage)
+}
+
+/// `profile-title` is plain text or `base64:<standard base64>`.
+fn parse_profile_title(value: &str) -> Option<String> {
+    let value = value.trim();
+    let title = match value.strip_prefix("base64:") {
+        Some(encoded) => String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?,
+        None => value.to_string(),
+    };
+    let title = title.trim();
+    (!title.is_empty()).then(|| title.to_string())
+}
+
+/// `profile-update-interval` is in hours.
+fn parse_update_interval(value: &str) -> Option<u64> {
+    let hours: u64 = value.trim().parse().ok()?;
+    (hours > 0).then(|| hours.saturating_mul(3600))
+}
+
+/// The highest threshold in `QUOTA_THRESHOLDS` that usage went past between
+/// two refreshes.
+fn crossed_threshold(old: Option<&SubscriptionUsage>, new: &SubscriptionUsage) -> Option<u8> {
+    let new_percent = new.used_percent()?;
+    let old_percent = old.and_then(SubscriptionUsage::used_percent).unwrap_or(0);
+    QUOTA_THRESHOLDS
+        .into_iter()
+        .filter(|t| old_percent < *t && new_percent >= *t)
+        .max()
+}
+
+/// Turn a subscription body into share links: either plain text with one
+/// link per line, or the same text base64-encoded (the common v2rayN format).
+pub(crate) fn decode_body(body: &str) -> Result<String, AppError> {
+    let body = body.trim();
+    if body.contains("://") {
+        return Ok(body.to_string());
+    }
+    let compact: String = body.chars().filter(|c| !c.is_whitespace()).collect();
+    [STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD]
+        .iter()
+        .find_map(|engine| engine.decode(&compact).ok())
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or_else(|| {
+            AppError::Config("Subscription is neither share links nor base64".to_string())
+        })
+}
+
+/// Parse every share link in a subscription body. Lines that fail to parse
+/// (unsupported schemes, provider banners) are skipped; an error is returned
+/// only if nothing usable is left, so a broken response can't wipe the list.
+pub fn parse_servers(body: &str) -> Result<Vec<ServerConfig>, AppError> {
+    let text = decode_body(body)?;
+    let mut servers = Vec::new();
+    let mut first_error = None;
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match uri::parse_vless_uri(line) {
+            Ok(server) => servers.push(server),
+            Err(e) => {
+                warn!("Skipping subscription entry: {e}");
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    if servers.is_empty() {
+        return Err(first_error
+            .unwrap_or_else(|| AppError::Config("Subscription has no servers".to_string())));
+    }
+    Ok(servers)
+}
+
+/// Replace the servers of subscription `subscription_id` with `fetched`,
+/// keeping their place in the list. A fetched server inherits the id (and
+/// `via` hop) of the old entry with the same [`ServerIdentity`], or failing
+/// that the same name, so `last_server_id` and chains survive a refresh. Returns the
+/// subscription's servers as stored.
+pub fn merge_servers(
+    servers: &mut Vec<ServerConfig>,
+    subscription_id: &str,
+    fetched: Vec<ServerConfig>,
+) -> Vec<ServerConfig> {
+    let belongs = |s: &ServerConfig| s.subscription_id.as_deref() == Some(subscription_id);
+    let old: Vec<ServerConfig> = servers.iter().filter(|s| belongs(s)).cloned().collect();
+    let insert_at = servers.iter().position(belongs).unwrap_or(servers.len());
+
+    let mut claimed: HashSet<String> = HashSet::new();
+    let mut merged = Vec::with_capacity(fetched.len());
+    for mut server in fetched {
+        let identity = ServerIdentity::of(&server);
+        let previous = old
+            .iter()
+            .filter(|o| !claimed.contains(&o.id))
+            .find(|o| ServerIdentity::of(o) == identity)
+            .or_else(|| {
+                old.iter()
+                    .filter(|o| !claimed.contains(&o.id))
+                    .find(|o| o.name == server.name)
+            });
+        if let Some(previous) = previous {
+            server.id = previous.id.clone();
+            server.via = previous.via.clone();
+        }
+        claimed.insert(server.id.clone());
+        server.subscription_id = Some(subscription_id.to_string());
+        merged.push(server);
+    }
+
+    // Every entry before `insert_at` is kept, so the index is still valid.
+    servers.retain(|s| !belongs(s));
+    servers.splice(insert_at..insert_at, merged.iter().cloned());
+
+    // Servers chained through an entry that disappeared fall back to
+    // connecting directly, as when a server is deleted by hand.
+    let ids: HashSet<String> = servers.iter().map(|s| s.id.clone()).collect();
+    for server in servers.iter_mut() {
+        if server.via.as_ref().is_some_and(|via| !ids.contains(via)) {
+            server.via = None;
+        }
+    }
+    merged
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Fetch subscription `id` and store its servers and reported usage. Emits
+/// `subscription-quota` when usage crosses one of `QUOTA_THRESHOLDS`.
+/// Returns the subscription's servers after the refresh.
+pub fn refresh<R: Runtime>(app: &AppHandle<R>, id: &str) -> Result<Vec<ServerConfig>, AppError> {
+    let subscription = storage::load_subscriptions(app)?
+        .into_iter()
+        .find(|s| s.id == id)
+        .ok_or_else(|| AppError::Config(format!("Subscription with id {id} not found")))?;
+
+    let response = fetch(&subscription.url)?;
+    let fetched = parse_servers(&response.body)?;
+
+    let mut servers = storage::load_servers(app)?;
+    let merged = merge_servers(&mut servers, id, fetched);
+    storage::save_servers(app, &servers)?;
+
+    // Re-read so edits made while the fetch was in flight aren't lost.
+    let mut subscriptions = storage::load_subscriptions(app)?;
+    if let Some(stored) = subscriptions.iter_mut().find(|s| s.id == id) {
+        stored.last_updated = Some(now_secs());
+        stored.last_failed = None;
+        stored.failures = 0;
+        if let Some(title) = response.title {
+            stored.profile_title = title;
+        }
+        if response.update_interval_secs.is_some() {
+            stored.suggested_refresh_secs = response.update_interval_secs;
+        }
+        if let Some(usage) = response.usage {
+            if let Some(threshold) = crossed_threshold(stored.usage.as_ref(), &usage) {
+                warn!(
+                    "Subscription {} has used {threshold}% of its traffic",
+                    stored.name
+                );
+                let _ = app.emit(
+                    "subscription-quota",
+                    QuotaAlert {
+                        subscription_id: stored.id.clone(),
+                        name: stored.name.clone(),
+                        threshold_percent: threshold,
+                        used: usage.used(),
+                        total: usage.total,
+                    },
+                );
+            }
+            stored.usage = Some(usage);
+        }
+    }
+    storage::save_subscriptions(app, &subscriptions)?;
+
+    info!(
+        "Refreshed subscription {} ({} servers)",
+        subscription.name,
+        merged.len()
+    );
+    Ok(merged)
+}
+
+/// Run `refresh` on every subscription due at `now`. Returns whether any
+/// succeeded, and the ids of those that failed.
+fn refresh_pass(
+    subscriptions: &[Subscription],
+    now: u64,
+    mut refresh: impl FnMut(&Subscription) -> Result<(), AppError>,
+) -> (bool, Vec<String>) {
+    let mut changed = false;
+    let mut failed = Vec::new();
+    for subscription in subscriptions.iter().filter(|s| s.is_due(now)) {
+        match refresh(subscription) {
+            Ok(()) => changed = true,
+            Err(e) => {
+                warn!("Failed to refresh subscription {}: {e}", subscription.name);
+                failed.push(subscription.id.clone());
+            }
+        }
+    }
+    (changed, failed)
+}
+
+/// Refresh every subscription whose interval has elapsed. Emits
+/// `servers-changed` if any server list was updated. Failures are recorded
+/// so the subscription backs off instead of being retried every check.
+pub fn refresh_due<R: Runtime>(app: &AppHandle<R>) {
+    let subscriptions: Vec<Subscription> = match storage::load_subscriptions(app) {
+        Ok(subscriptions) => subscriptions,
+        Err(e) => {
+            warn!("Failed to load subscriptions: {e}");
+            return;
+        }
+    };
+    let now = now_secs();
+    let (changed, failed) = refresh_pass(&subscriptions, now, |s| refresh(app, &s.id).map(|_| ()));
+    if !failed.is_empty() {
+        // Re-read so the successful refreshes just saved aren't overwritten.
+        let result = storage::load_subscriptions(app).and_then(|mut subscriptions| {
+            for subscription in subscriptions.iter_mut() {
+                if failed.contains(&subscription.id) {
+                    subscription.record_failure(now);
+                }
+            }
+            storage::save_subscriptions(app, &subscriptions)
+        });
+        if let Err(e) = result {
+            warn!("Failed to record subscription refresh failure: {e}");
+        }
+    }
+    if changed {
+        let _ = app.emit("servers-changed", ());
+    }
+}
+
+/// Check for due subscriptions in the background for the app's lifetime.
+pub fn spawn_refresh_loop<R: Runtime>(app: AppHandle<R>) {
+    std::thread::spawn(move || loop {
+        refresh_due(&app);
+        std::thread::sleep(REFRESH_CHECK_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    const LINK_A: &str = "vless://aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee@1.2.3.4:443?encryption=none&flow=xtls-rprx-vision&type=tcp&security=reality&sni=example.com&fp=chrome&pbk=abc123&sid=def456#Alpha";
+    const LINK_B: &str = "vless://aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee@5.6.7.8:443?encryption=none&flow=xtls-rprx-vision&type=tcp&security=reality&sni=example.com&fp=chrome&pbk=abc123&sid=def456#Beta";
+
+    /// Serve a single HTTP response from a local stand-in for the provider.
+    fn serve_once(body: String, headers: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                match stream.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => request.extend_from_slice(&buf[..n]),
+                }
+            }
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        format!("http://{addr}/sub")
+    }
+
+    #[test]
+    fn fetch_and_parse_base64_subscription() {
+        let body = STANDARD.encode(format!("{LINK_A}\n{LINK_B}\n"));
+        let url = serve_once(body, "");
+        let fetched = fetch(&url).unwrap();
+        assert!(fetched.usage.is_none());
+        let servers = parse_servers(&fetched.body).unwrap();
+        assert_eq!(servers.len(), 2);
+        assert_eq!(servers[0].name, "Alpha");
+        assert_eq!(servers[1].address, "5.6.7.8");
+    }
+
+    #[test]
+    fn failed_refresh_backs_off() {
+        // A provider that answers every request with a 500.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/sub", listener.local_addr().unwrap());
+        let hits = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = hits.clone();
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf);
+                let _ = stream.write_all(
+                    b"HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                );
+            }
+        });
+        let mut subscriptions = vec![Subscription {
+            id: "sub".to_string(),
+            name: "Down".to_string(),
+            url,
+            refresh_interval_secs: 3600,
+            last_updated: None,
+            usage: None,
+            profile_title: String::new(),
+            suggested_refresh_secs: None,
+            last_failed: None,
+            failures: 0,
+        }];
+        let try_fetch = |s: &Subscription| fetch(&s.url).map(|_| ());
+        let hit_count = || hits.load(std::sync::atomic::Ordering::SeqCst);
+
+        let now = 1_000_000;
+        let (changed, failed) = refresh_pass(&subscriptions, now, try_fetch);
+        assert!(!changed);
+        assert_eq!(failed, ["sub"]);
+        assert_eq!(hit_count(), 1);
+        subscriptions[0].record_failure(now);
+
+        // The next check a minute later leaves the provider alone...
+        let next = now + REFRESH_CHECK_INTERVAL.as_secs();
+        let (_, failed) = refresh_pass(&subscriptions, next, try_fetch);
+        assert!(failed.is_empty());
+        assert_eq!(hit_count(), 1);
+
+        // ...until the backoff has passed.
+        let (_, failed) = refresh_pass(&subscriptions, now + 300, try_fetch);
+        assert_eq!(failed, ["sub"]);
+        assert_eq!(hit_count(), 2);
+    }
+
+    #[test]
+    fn fetch_reads_provider_headers() {
+        let url = serve_once(
+            LINK_A.to_string(),
+            "Subscription-Userinfo: upload=100; download=200; total=1000; expire=1700000000\r\n\
+             Profile-Title: base64:0JzQvtC5IFZQTg==\r\n\
+             Profile-Update-Interval: 12\r\n",
+        );
+        let fetched = fetch(&url).unwrap();
+        assert_eq!(
+            fetched.usage,
+            Some(SubscriptionUsage {
+                upload: 100,
+                download: 200,
+                total: 1000,
+                expire: Some(1_700_000_000),
+            })
+        );
+        assert_eq!(fetched.title.as_deref(), Some("Мой VPN"));
+        assert_eq!(fetched.update_interval_secs, Some(12 * 3600));
+    }
+
+    #[test]
+    fn parse_header_values() {
+        let usage = parse_userinfo("upload=1; download=2.5e3;total=0; expire=0").unwrap();
+        assert_eq!(usage.used(), 2501);
+        assert_eq!(usage.total, 0);
+        assert_eq!(usage.expire, None);
+        assert!(parse_userinfo("garbage").is_none());
+
+        assert_eq!(parse_profile_title(" Plain ").as_deref(), Some("Plain"));
+        assert!(parse_profile_title("base64:!!!").is_none());
+        assert!(parse_update_interval("0").is_none());
+        assert!(parse_update_interval("soon").is_none());
+    }
+
+    #[test]
+    fn quota_threshold_crossing() {
+        let usage = |used: u64| SubscriptionUsage {
+            download: used,
+            total: 100,
+            ..SubscriptionUsage::default()
+        };
+        assert_eq!(crossed_threshold(None, &usage(50)), None);
+        assert_eq!(crossed_threshold(None, &usage(85)), Some(80));
+        assert_eq!(crossed_threshold(Some(&usage(85)), &usage(89)), None);
+        assert_eq!(crossed_threshold(Some(&usage(85)), &usage(100)), Some(100));
+        // A reset (new billing period) doesn't alert.
+        assert_eq!(crossed_threshold(Some(&usage(95)), &usage(10)), None);
+        // Unlimited plans never alert.
+        let unlimited = SubscriptionUsage {
+            download: 1 << 40,
+            ..SubscriptionUsage::default()
+        };
+        assert_eq!(crossed_threshold(None, &unlimited), None);
+    }
+
+    #[test]
+    fn parse_plain_subscription_skips_bad_lines() {
+        let body = format!("# provider banner\n{LINK_A}\r\nnot-a-link\n\n{LINK_B}");
+        let servers = parse_servers(&body).unwrap();
+        assert_eq!(servers.len(), 2);
+
+        assert!(parse_servers("not-a-link\n").is_err());
+        assert!(parse_servers("").is_err());
+        assert!(parse_servers("%%%").is_err());
+    }
+
+    #[test]
+    fn merge_keeps_ids_and_position() {
+        let manual = ServerConfig {
+            name: "Manual".to_string(),
+            ..ServerConfig::default()
+        };
+        let mut servers = vec![manual.clone()];
+        let first = merge_servers(&mut servers, "sub", parse_servers(LINK_A).unwrap());
+        let alpha_id = first[0].id.clone();
+        let mut chained = ServerConfig {
+            name: "Chained".to_string(),
+            via: Some(alpha_id.clone()),
+            ..ServerConfig::default()
+        };
+        servers.push(chained.clone());
+
+        // Alpha renamed, Beta added: Alpha keeps its id and slot.
+        let renamed = LINK_A.replace("#Alpha", "#Alpha%20(renamed)");
+        let body = format!("{renamed}\n{LINK_B}");
+        let second = merge_servers(&mut servers, "sub", parse_servers(&body).unwrap());
+        assert_eq!(second[0].id, alpha_id);
+        assert_eq!(second[0].name, "Alpha (renamed)");
+        let names: Vec<&str> = servers.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["Manual", "Alpha (renamed)", "Beta", "Chained"]);
+        assert!(servers
+            .iter()
+            .all(|s| s.id == manual.id || s.id == chained.id || s.subscription_id.is_some()));
+
+        // Alpha dropped by the provider: the chain through it is cleared.
+        merge_servers(&mut servers, "sub", parse_servers(LINK_B).unwrap());
+        assert_eq!(servers.len(), 3);
+        chained = servers.iter().find(|s| s.id == chained.id).unwrap().clone();
+        assert!(chained.via.is_none());
+    }
+
+    #[test]
+    fn merge_matches_accounts_sharing_an_endpoint() {
+        // Password-authenticated entries have no UUID to tell them apart.
+        let one = "trojan://first@trojan.example.com:443?sni=trojan.example.com#One";
+        let two = "trojan://second@trojan.example.com:443?sni=trojan.example.com#Two";
+        let mut servers = Vec::new();
+        let first = merge_servers(
+            &mut servers,
+            "sub",
+            parse_servers(&format!("{one}\n{two}")).unwrap(),
+        );
+
+        // Reordered and renamed by the provider: ids follow the passwords.
+        let body = format!(
+            "{}\n{}",
+            two.replace("#Two", "#B"),
+            one.replace("#One", "#A")
+        );
+        let second = merge_servers(&mut servers, "sub", parse_servers(&body).unwrap());
+        assert_eq!(second[0].password, "second");
+        assert_eq!(second[0].id, first[1].id);
+        assert_eq!(second[1].id, first[0].id);
+    }
+}
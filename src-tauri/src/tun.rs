@@ -1,9 +1,11 @@
+use std::net::{IpAddr, ToSocketAddrs};
 use std::path::Path;
 use std::process::Command;
 
 use log::{info, warn};
 
 use crate::models::AppError;
+use crate::network::Ipv6Uplink;
 
 /// Validate that a string looks like an IPv4 address (no shell metacharacters).
 fn is_valid_ip(s: &str) -> bool {
@@ -11,6 +13,44 @@ fn is_valid_ip(s: &str) -> bool {
     parts.len() == 4 && parts.iter().all(|p| p.parse::<u8>().is_ok())
 }
 
+/// Validate that a string is an IPv6 address.
+fn is_valid_ip6(s: &str) -> bool {
+    s.parse::<std::net::Ipv6Addr>().is_ok()
+}
+
+/// Validate a comma-separated list of server IPs (IPv4 or IPv6), one or more
+/// per chained hop.
+fn is_valid_ip_list(s: &str) -> bool {
+    s.split(',').all(|ip| ip.parse::<IpAddr>().is_ok())
+}
+
+/// Resolve hop addresses (IP literals, `[v6]` literals or hostnames) to every
+/// IP that needs a bypass route. Runs before the TUN default route exists, so
+/// lookups still go out over the physical interface.
+fn resolve_server_ips(addresses: &[String]) -> Result<Vec<IpAddr>, AppError> {
+    let mut ips = Vec::new();
+    for address in addresses {
+        let host = address.trim().trim_start_matches('[').trim_end_matches(']');
+        let resolved: Vec<IpAddr> = match host.parse::<IpAddr>() {
+            Ok(ip) => vec![ip],
+            Err(_) => (host, 0)
+                .to_socket_addrs()
+                .map_err(|e| AppError::Config(format!("Failed to resolve {host}: {e}")))?
+                .map(|addr| addr.ip())
+                .collect(),
+        };
+        if resolved.is_empty() {
+            return Err(AppError::Config(format!("{host} has no A/AAAA records")));
+        }
+        for ip in resolved {
+            if !ips.contains(&ip) {
+                ips.push(ip);
+            }
+        }
+    }
+    Ok(ips)
+}
+
 /// Validate that a string looks like a safe interface name.
 fn is_valid_iface(s: &str) -> bool {
     !s.is_empty()
@@ -22,6 +62,7 @@ fn is_valid_iface(s: &str) -> bool {
 const TUN_NAME: &str = "rvpn0";
 const TUN_ADDR: &str = "198.18.0.1/15";
 const TUN_GW: &str = "198.18.0.0";
+const TUN_ADDR6: &str = "fd72:7670:6e00::1/64";
 const TUN_MTU: &str = "8500";
 const HELPER_NAME: &str = "rustvpn-helper";
 
@@ -76,12 +117,13 @@ pub fn cleanup_stale_tun(config_dir: &Path) {
                     let server_ip = lines[0];
                     let gateway = lines[1];
                     let dev = lines[2];
-                    if is_valid_ip(server_ip) && is_valid_ip(gateway) && is_valid_iface(dev) {
+                    if is_valid_ip_list(server_ip) && is_valid_ip(gateway) && is_valid_iface(dev) {
                         args.push(server_ip.to_string());
                         args.push(gateway.to_string());
                         args.push(dev.to_string());
                         if lines.len() >= 4 && is_valid_ip(lines[3]) {
                             args.push(lines[3].to_string());
+                            args.extend(ip6_route_args(&lines));
                         }
                     } else {
                         warn!("Stale gateway file contains invalid data, skipping route cleanup");
@@ -109,6 +151,33 @@ pub fn cleanup_stale_tun(config_dir: &Path) {
     }
 }
 
+/// IPv6 gateway, device and local IP from lines 4–6 of `tun_gateway.txt`;
+/// "-" for any the session didn't use.
+fn ip6_route_args(lines: &[&str]) -> Vec<String> {
+    let line = |i: usize, valid: fn(&str) -> bool| {
+        lines
+            .get(i)
+            .filter(|value| valid(value))
+            .map_or_else(|| "-".to_string(), |value| value.to_string())
+    };
+    vec![
+        line(4, is_valid_ip6),
+        line(5, is_valid_iface),
+        line(6, is_valid_ip6),
+    ]
+}
+
+/// IPv6 handling for a TUN session.
+#[derive(Debug, Clone)]
+pub struct Ipv6Setup {
+    /// Blackhole IPv6 instead of tunnelling it (the "block IPv6" setting).
+    pub block: bool,
+    /// The host's own IPv6 route, if any: IPv6 server addresses are routed
+    /// around the TUN through it, and xray's own IPv6 connections (in block
+    /// mode, just the dial to an IPv6 server) are sourced from its address.
+    pub uplink: Option<Ipv6Uplink>,
+}
+
 /// Resolve the helper script path. Checks /usr/local/bin first (installed),
 /// then falls back to the project's scripts/ directory (dev mode).
 fn resolve_helper() -> Result<String, AppError> {
@@ -136,10 +205,11 @@ fn resolve_helper() -> Result<String, AppError> {
 pub fn start_tun(
     hev_bin: &Path,
     socks_port: u16,
-    server_ip: &str,
+    server_addresses: &[String],
     bypass_subnets: &[String],
     config_dir: &Path,
     gateway_info: Option<(String, String, String)>,
+    ipv6: Ipv6Setup,
 ) -> Result<(), AppError> {
     let helper = resolve_helper()?;
 
@@ -163,16 +233,52 @@ pub fn start_tun(
     }
     info!("Detected default gateway: {gateway} via {dev} (local IP: {local_ip})");
 
+    let server_ips = resolve_server_ips(server_addresses)?;
+    info!(
+        "Server bypass routes: {}",
+        server_ips
+            .iter()
+            .map(IpAddr::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    // "-" tells the helper a value is absent: no IPv6 uplink (IPv6 servers are
+    // then unreachable anyway) or no global address to source xray's IPv6
+    // sockets from. Blocking IPv6 keeps the address: it only stops tunnelled
+    // IPv6, not xray's own uplink to an IPv6 server.
+    let none = || "-".to_string();
+    let (gateway6, dev6, local_ip6) = match ipv6.uplink {
+        Some(uplink) => (
+            uplink.gateway,
+            uplink.dev,
+            uplink.local_ip.unwrap_or_else(none),
+        ),
+        None => (none(), none(), none()),
+    };
+    let tun_addr6 = if ipv6.block {
+        none()
+    } else {
+        TUN_ADDR6.to_string()
+    };
+
     // Write hev-socks5-tunnel config
     let hev_config = config_dir.join("hev_config.yml");
     let pid_file = config_dir.join("hev.pid");
-    write_hev_config(&hev_config, socks_port, &pid_file)?;
+    write_hev_config(&hev_config, socks_port, &pid_file, !ipv6.block)?;
+
+    // Every resolved address of every hop gets its own route around the TUN;
+    // the helper takes them as one comma-separated argument.
+    let server_ip = server_ips
+        .iter()
+        .map(IpAddr::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
 
     // Save gateway info for stop_tun (including local_ip for ip rule cleanup)
     let gw_file = config_dir.join("tun_gateway.txt");
     std::fs::write(
         &gw_file,
-        format!("{server_ip}\n{gateway}\n{dev}\n{local_ip}"),
+        format!("{server_ip}\n{gateway}\n{dev}\n{local_ip}\n{gateway6}\n{dev6}\n{local_ip6}"),
     )?;
 
     // Build args for helper (includes app PID for watchdog and local IP for routing)
@@ -187,11 +293,15 @@ pub fn start_tun(
         TUN_ADDR.to_string(),
         TUN_MTU.to_string(),
         TUN_GW.to_string(),
-        server_ip.to_string(),
+        server_ip,
         gateway.clone(),
         dev.clone(),
         app_pid,
         local_ip,
+        gateway6,
+        dev6,
+        tun_addr6,
+        local_ip6,
     ];
 
     // Append bypass subnets as additional args
@@ -251,12 +361,13 @@ pub fn stop_tun(config_dir: &Path) -> Result<(), AppError> {
             let server_ip = lines[0];
             let gateway = lines[1];
             let dev = lines[2];
-            if is_valid_ip(server_ip) && is_valid_ip(gateway) && is_valid_iface(dev) {
+            if is_valid_ip_list(server_ip) && is_valid_ip(gateway) && is_valid_iface(dev) {
                 args.push(server_ip.to_string());
                 args.push(gateway.to_string());
                 args.push(dev.to_string());
                 if lines.len() >= 4 && is_valid_ip(lines[3]) {
                     args.push(lines[3].to_string()); // local_ip
+                    args.extend(ip6_route_args(&lines));
                 }
             } else {
                 warn!("Gateway file contains invalid data, skipping route cleanup");
@@ -330,11 +441,23 @@ fn detect_default_gateway() -> Result<(String, String), AppError> {
 }
 
 /// Write hev-socks5-tunnel YAML config file.
-fn write_hev_config(path: &Path, socks_port: u16, pid_file: &Path) -> Result<(), AppError> {
+fn write_hev_config(
+    path: &Path,
+    socks_port: u16,
+    pid_file: &Path,
+    ipv6: bool,
+) -> Result<(), AppError> {
+    // With an IPv6 address on the TUN, hev accepts IPv6 packets and hands
+    // them to xray like IPv4 ones.
+    let ipv6_line = if ipv6 {
+        format!("\n  ipv6: '{}'", TUN_ADDR6.trim_end_matches("/64"))
+    } else {
+        String::new()
+    };
     let config = format!(
         r#"tunnel:
   name: {TUN_NAME}
-  mtu: {TUN_MTU}
+  mtu: {TUN_MTU}{ipv6_line}
 
 socks5:
   port: {socks_port}
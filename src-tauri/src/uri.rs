@@ -1,15 +1,56 @@
-use crate::models::{AppError, RealitySettings, ServerConfig};
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD};
+use base64::Engine;
+use serde_json::{json, Value};
 
+use crate::models::{
+    AppError, EchSettings, MuxSettings, RealitySettings, ServerConfig, TlsSettings, XhttpExtra,
+};
+
+/// Parse a share link. Despite the name this accepts every scheme the app can
+/// connect with: `vless://`, `vmess://`, `trojan://`, `ss://`,
+/// `hysteria2://` (or its `hy2://` alias) and `wireguard://`.
 pub fn parse_vless_uri(uri: &str) -> Result<ServerConfig, AppError> {
-    // Format: vless://UUID@ADDRESS:PORT?params#NAME
     let uri = uri.trim();
 
-    if !uri.starts_with("vless://") {
-        return Err(AppError::Config("URI must start with vless://".to_string()));
+    if let Some(payload) = uri.strip_prefix("vmess://") {
+        return parse_vmess_uri(payload);
+    }
+    if let Some(rest) = uri.strip_prefix("trojan://") {
+        return parse_trojan_uri(rest);
+    }
+    if let Some(rest) = uri.strip_prefix("ss://") {
+        return parse_ss_uri(rest);
+    }
+    if let Some(rest) = uri
+        .strip_prefix("hysteria2://")
+        .or_else(|| uri.strip_prefix("hy2://"))
+    {
+        return parse_hysteria2_uri(rest);
+    }
+    if let Some(rest) = uri.strip_prefix("wireguard://") {
+        return parse_wireguard_uri(rest);
+    }
+    match uri.strip_prefix("vless://") {
+        Some(rest) => parse_vless_link(rest),
+        None => Err(AppError::Config(
+            "URI must start with vless://, vmess://, trojan://, ss://, hysteria2:// or wireguard://"
+                .to_string(),
+        )),
     }
+}
 
-    let rest = &uri[8..]; // after "vless://"
+/// The generic parts of a `scheme://USERINFO@HOST:PORT?QUERY#NAME` link.
+struct ShareLink<'a> {
+    userinfo: String,
+    address: String,
+    port: u16,
+    query: &'a str,
+    name: String,
+}
 
+/// Split a share link (without its `scheme://` prefix) into its parts.
+/// `USERINFO` is percent-decoded; IPv6 hosts must be bracketed.
+fn split_share_link<'a>(rest: &'a str, scheme: &str) -> Result<ShareLink<'a>, AppError> {
     // Split off fragment (#NAME)
     let (rest, name) = match rest.rfind('#') {
         Some(pos) => (&rest[..pos], url_decode(&rest[pos + 1..])),
@@ -21,12 +62,16 @@ pub fn parse_vless_uri(uri: &str) -> Result<ServerConfig, AppError> {
         Some(pos) => (&rest[..pos], &rest[pos + 1..]),
         None => (rest, ""),
     };
+    // Some generators put a slash between the port and the query.
+    let authority = authority.strip_suffix('/').unwrap_or(authority);
 
-    // Parse UUID@ADDRESS:PORT
+    // Parse USERINFO@ADDRESS:PORT. The userinfo may itself contain '@'
+    // (trojan passwords), so split on the last one.
     let at_pos = authority
-        .find('@')
-        .ok_or_else(|| AppError::Config("Missing @ in vless URI".to_string()))?;
-    let uuid = authority[..at_pos].to_string();
+        .rfind('@')
+        .ok_or_else(|| AppError::Config(format!("Missing @ in {scheme} URI")))?;
+    // '+' is literal in userinfo; only query strings use it for spaces.
+    let userinfo = url_decode(&authority[..at_pos].replace('+', "%2B"));
     let host_port = &authority[at_pos + 1..];
 
     // Handle IPv6 addresses in brackets
@@ -46,7 +91,7 @@ pub fn parse_vless_uri(uri: &str) -> Result<ServerConfig, AppError> {
     } else {
         let colon_pos = host_port
             .rfind(':')
-            .ok_or_else(|| AppError::Config("Missing port in vless URI".to_string()))?;
+            .ok_or_else(|| AppError::Config(format!("Missing port in {scheme} URI")))?;
         let addr = host_port[..colon_pos].to_string();
         let port_str = &host_port[colon_pos + 1..];
         let port: u16 = port_str
@@ -55,17 +100,52 @@ pub fn parse_vless_uri(uri: &str) -> Result<ServerConfig, AppError> {
         (addr, port)
     };
 
+    Ok(ShareLink {
+        userinfo,
+        address,
+        port,
+        query,
+        name,
+    })
+}
+
+/// Iterate `key=value` query parameters, percent-decoding the values.
+fn query_params(query: &str) -> impl Iterator<Item = (&str, String)> {
+    query
+        .split('&')
+        .filter_map(|param| param.split_once('='))
+        .map(|(key, value)| (key, url_decode(value)))
+}
+
+fn parse_vless_link(rest: &str) -> Result<ServerConfig, AppError> {
+    // Format: vless://UUID@ADDRESS:PORT?params#NAME
+    let ShareLink {
+        userinfo: uuid,
+        address,
+        port,
+        query,
+        name,
+    } = split_share_link(rest, "vless")?;
+
     // Parse query parameters
     let mut flow = String::new();
     let mut sni = String::new();
     let mut fingerprint = "chrome".to_string();
     let mut public_key = String::new();
     let mut short_id = String::new();
+    let mut spider_x = String::new();
     let mut network = "tcp".to_string();
     let mut xhttp_path = String::new();
     let mut security = "reality".to_string();
     let mut xhttp_mode = String::new();
     let mut host = String::new();
+    let mut service_name = String::new();
+    let mut alpn = Vec::new();
+    let mut allow_insecure = false;
+    let mut pinned_cert_sha256 = Vec::new();
+    let mut ech = String::new();
+    let mut extra = String::new();
+    let mut encryption = String::new();
 
     for param in query.split('&') {
         if param.is_empty() {
@@ -78,11 +158,19 @@ pub fn parse_vless_uri(uri: &str) -> Result<ServerConfig, AppError> {
                 "fp" => fingerprint = url_decode(value),
                 "pbk" => public_key = url_decode(value),
                 "sid" => short_id = url_decode(value),
+                "spx" => spider_x = url_decode(value),
                 "type" => {
                     // xray/v2ray spell the XHTTP transport variously; normalize them all.
                     network = match url_decode(value).as_str() {
+                        "" | "tcp" | "raw" => "tcp".to_string(),
                         "xhttp" | "http" | "splithttp" => "xhttp".to_string(),
-                        _ => "tcp".to_string(),
+                        "grpc" | "gun" => "grpc".to_string(),
+                        "ws" | "websocket" => "ws".to_string(),
+                        other => {
+                            return Err(AppError::Config(format!(
+                                "Unsupported vless transport '{other}' (expected tcp, xhttp, grpc or ws)"
+                            )))
+                        }
                     };
                 }
                 "path" => xhttp_path = url_decode(value),
@@ -97,21 +185,45 @@ pub fn parse_vless_uri(uri: &str) -> Result<ServerConfig, AppError> {
                 }
                 "mode" => xhttp_mode = url_decode(value),
                 "host" => host = url_decode(value),
-                _ => {} // ignore unknown params (encryption, etc.)
+                "serviceName" => service_name = url_decode(value),
+                "alpn" => alpn = split_list(&url_decode(value)),
+                "allowInsecure" | "insecure" => allow_insecure = is_truthy(value),
+                "pcs" | "pinSHA256" => pinned_cert_sha256 = split_list(&url_decode(value)),
+                "ech" => ech = url_decode(value),
+                "extra" => extra = url_decode(value),
+                "encryption" => encryption = url_decode(value),
+                _ => {} // ignore unknown params
             }
         }
     }
 
-    // XTLS-Vision flow is only valid over raw TCP; drop it for XHTTP so the stored
-    // entry stays internally consistent.
-    if network == "xhttp" {
+    // XTLS-Vision flow is only valid over raw TCP; drop it for the other
+    // transports so the stored entry stays internally consistent.
+    if network != "tcp" {
         flow = String::new();
     }
 
     // For TLS/CDN, sni and host are both the CDN domain; store it as server_name.
     // Fall back to the Host header if only `host` was supplied.
     if sni.is_empty() && !host.is_empty() {
-        sni = host;
+        sni = host.clone();
+    }
+
+    // `path`, `host` and `mode` are shared between transports; route them to
+    // the fields of the one actually in use.
+    let (mut ws_path, mut ws_host, mut ws_early_data) = (String::new(), String::new(), 0);
+    let mut grpc_multi_mode = false;
+    match network.as_str() {
+        "ws" => {
+            (ws_path, ws_early_data) = split_early_data(&std::mem::take(&mut xhttp_path));
+            ws_host = host;
+        }
+        "grpc" => {
+            grpc_multi_mode = xhttp_mode == "multi";
+            xhttp_mode.clear();
+            xhttp_path.clear();
+        }
+        _ => {}
     }
 
     let xhttp_mode = if xhttp_mode.trim().is_empty() {
@@ -120,6 +232,16 @@ pub fn parse_vless_uri(uri: &str) -> Result<ServerConfig, AppError> {
         xhttp_mode
     };
 
+    // `extra` is xray's xhttpSettings.extra block as JSON; other transports
+    // have no use for it.
+    let xhttp_extra = if network == "xhttp" && !extra.trim().is_empty() {
+        let json: Value = serde_json::from_str(&extra)
+            .map_err(|e| AppError::Config(format!("Invalid xHTTP extra JSON: {e}")))?;
+        XhttpExtra::from_xray(&json).map_err(AppError::Config)?
+    } else {
+        XhttpExtra::default()
+    };
+
     Ok(ServerConfig {
         id: uuid::Uuid::new_v4().to_string(),
         name,
@@ -130,17 +252,475 @@ pub fn parse_vless_uri(uri: &str) -> Result<ServerConfig, AppError> {
         reality: RealitySettings {
             public_key,
             short_id,
+            spider_x,
+            ..RealitySettings::default()
+        },
+        tls: TlsSettings {
             server_name: sni,
             fingerprint,
+            alpn,
+            allow_insecure,
+            pinned_cert_sha256,
+            ech: EchSettings::from_xray(&ech),
         },
         network,
         xhttp_path,
         security,
         xhttp_mode,
+        ws_path,
+        ws_host,
+        grpc_service_name: service_name,
+        grpc_multi_mode,
+        ws_early_data,
+        mux: mux_from_query(query)?,
+        xhttp_extra,
+        vless_encryption: if encryption.trim().is_empty() {
+            "none".to_string()
+        } else {
+            encryption
+        },
+        ..ServerConfig::default()
+    })
+}
+
+/// Split xray's `?ed=N` early-data suffix off a WebSocket path.
+fn split_early_data(path: &str) -> (String, u32) {
+    if let Some((base, ed)) = path.rsplit_once("?ed=") {
+        if let Ok(ed) = ed.parse() {
+            return (base.to_string(), ed);
+        }
+    }
+    (path.to_string(), 0)
+}
+
+/// Parse the payload of a `vmess://` link: base64-encoded JSON in the v2rayN
+/// "v2" format. Numeric fields are accepted both as numbers and as strings,
+/// since generators disagree.
+fn parse_vmess_uri(payload: &str) -> Result<ServerConfig, AppError> {
+    let decoded = decode_base64(payload)
+        .ok_or_else(|| AppError::Config("vmess:// payload is not valid base64".to_string()))?;
+    let link: Value = serde_json::from_slice(&decoded)
+        .map_err(|e| AppError::Config(format!("vmess:// payload is not valid JSON: {e}")))?;
+
+    let address = vmess_field(&link, "add");
+    if address.is_empty() {
+        return Err(AppError::Config(
+            "Missing address in vmess link".to_string(),
+        ));
+    }
+    let port_str = vmess_field(&link, "port");
+    let port: u16 = port_str
+        .parse()
+        .map_err(|_| AppError::Config(format!("Invalid port: {port_str}")))?;
+    let alter_id_str = vmess_field(&link, "aid");
+    let alter_id: u16 = if alter_id_str.is_empty() {
+        0
+    } else {
+        alter_id_str
+            .parse()
+            .map_err(|_| AppError::Config(format!("Invalid alterId: {alter_id_str}")))?
+    };
+
+    let network = match vmess_field(&link, "net").as_str() {
+        "" | "tcp" => "tcp".to_string(),
+        "ws" => "ws".to_string(),
+        "grpc" => "grpc".to_string(),
+        other => {
+            return Err(AppError::Config(format!(
+                "Unsupported vmess transport '{other}' (expected tcp, ws or grpc)"
+            )))
+        }
+    };
+    // A raw-TCP link may ask for the HTTP header obfuscation, which xray's
+    // VMess outbound here doesn't build; better to refuse than to connect wrong.
+    let header_type = vmess_field(&link, "type");
+    if network == "tcp" && !header_type.is_empty() && header_type != "none" {
+        return Err(AppError::Config(format!(
+            "Unsupported vmess tcp header type '{header_type}'"
+        )));
+    }
+
+    let security = if vmess_field(&link, "tls") == "tls" {
+        "tls".to_string()
+    } else {
+        "none".to_string()
+    };
+    let host = vmess_field(&link, "host");
+    let mut sni = vmess_field(&link, "sni");
+    if sni.is_empty() && security == "tls" {
+        sni = host.clone();
+    }
+    let fingerprint = match vmess_field(&link, "fp") {
+        fp if fp.is_empty() => "chrome".to_string(),
+        fp => fp,
+    };
+    let cipher = match vmess_field(&link, "scy") {
+        scy if scy.is_empty() => "auto".to_string(),
+        scy => scy,
+    };
+    let (ws_path, ws_host) = if network == "ws" {
+        (vmess_field(&link, "path"), host)
+    } else {
+        (String::new(), String::new())
+    };
+    // v2rayN stores the gRPC service name in `path`.
+    let grpc_service_name = if network == "grpc" {
+        vmess_field(&link, "path")
+    } else {
+        String::new()
+    };
+    let alpn = split_list(&vmess_field(&link, "alpn"));
+
+    Ok(ServerConfig {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: vmess_field(&link, "ps"),
+        address,
+        port,
+        uuid: vmess_field(&link, "id"),
+        flow: String::new(),
+        reality: RealitySettings::default(),
+        tls: TlsSettings {
+            server_name: sni,
+            fingerprint,
+            alpn,
+            ..TlsSettings::default()
+        },
+        network,
+        security,
+        protocol: "vmess".to_string(),
+        alter_id,
+        vmess_cipher: cipher,
+        ws_path,
+        ws_host,
+        grpc_service_name,
+        ..ServerConfig::default()
+    })
+}
+
+/// Parse a `trojan://PASSWORD@HOST:PORT?params#NAME` link. Trojan always runs
+/// over TLS; `security=none` links (plain-text trojan) are rejected.
+fn parse_trojan_uri(rest: &str) -> Result<ServerConfig, AppError> {
+    let link = split_share_link(rest, "trojan")?;
+
+    let mut sni = String::new();
+    let mut fingerprint = "chrome".to_string();
+    let mut alpn = Vec::new();
+    let mut network = "tcp".to_string();
+    let mut path = String::new();
+    let mut host = String::new();
+    let mut service_name = String::new();
+    let mut allow_insecure = false;
+    let mut pinned_cert_sha256 = Vec::new();
+    let mut ech = String::new();
+
+    for (key, value) in query_params(link.query) {
+        match key {
+            "sni" | "peer" => sni = value,
+            "fp" => fingerprint = value,
+            "alpn" => alpn = split_list(&value),
+            "allowInsecure" | "insecure" => allow_insecure = is_truthy(&value),
+            "pcs" | "pinSHA256" => pinned_cert_sha256 = split_list(&value),
+            "ech" => ech = value,
+            "type" => {
+                network = match value.as_str() {
+                    "" | "tcp" => "tcp".to_string(),
+                    "ws" | "grpc" => value,
+                    other => {
+                        return Err(AppError::Config(format!(
+                            "Unsupported trojan transport '{other}' (expected tcp, ws or grpc)"
+                        )))
+                    }
+                };
+            }
+            "security" if value != "tls" && !value.is_empty() => {
+                return Err(AppError::Config(format!(
+                    "Unsupported trojan security '{value}' (expected tls)"
+                )));
+            }
+            "path" => path = value,
+            "host" => host = value,
+            "serviceName" => service_name = value,
+            _ => {}
+        }
+    }
+
+    if sni.is_empty() && !host.is_empty() {
+        sni = host.clone();
+    }
+    let (ws_path, ws_host) = if network == "ws" {
+        (path, host)
+    } else {
+        (String::new(), String::new())
+    };
+
+    Ok(ServerConfig {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: link.name,
+        address: link.address,
+        port: link.port,
+        uuid: String::new(),
+        flow: String::new(),
+        reality: RealitySettings::default(),
+        tls: TlsSettings {
+            server_name: sni,
+            fingerprint,
+            alpn,
+            allow_insecure,
+            pinned_cert_sha256,
+            ech: EchSettings::from_xray(&ech),
+        },
+        network,
+        security: "tls".to_string(),
+        protocol: "trojan".to_string(),
+        ws_path,
+        ws_host,
+        password: link.userinfo,
+        grpc_service_name: service_name,
+        mux: mux_from_query(link.query)?,
+        ..ServerConfig::default()
+    })
+}
+
+/// Parse a SIP002 `ss://USERINFO@HOST:PORT/?plugin=...#NAME` link. USERINFO is
+/// base64url of `method:password`, or percent-encoded `method:password`
+/// (SIP022, used for 2022 ciphers whose keys are already base64). The older
+/// `ss://BASE64(method:password@host:port)#NAME` form is accepted as well.
+fn parse_ss_uri(rest: &str) -> Result<ServerConfig, AppError> {
+    let (body, fragment) = match rest.rfind('#') {
+        Some(pos) => (&rest[..pos], &rest[pos..]),
+        None => (rest, ""),
+    };
+    let expanded;
+    let rest = if body.contains('@') {
+        rest
+    } else {
+        let decoded = decode_base64_str(body).ok_or_else(|| {
+            AppError::Config("ss:// link is neither SIP002 nor base64".to_string())
+        })?;
+        expanded = format!("{decoded}{fragment}");
+        expanded.as_str()
+    };
+
+    let link = split_share_link(rest, "ss")?;
+    let credentials = if link.userinfo.contains(':') {
+        link.userinfo
+    } else {
+        decode_base64_str(&link.userinfo)
+            .ok_or_else(|| AppError::Config("ss:// credentials are not valid base64".to_string()))?
+    };
+    let (method, password) = credentials
+        .split_once(':')
+        .ok_or_else(|| AppError::Config("Missing method in ss URI".to_string()))?;
+
+    let plugin = query_params(link.query)
+        .find(|(key, _)| *key == "plugin")
+        .map(|(_, value)| value)
+        .unwrap_or_default();
+
+    Ok(ServerConfig {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: link.name,
+        address: link.address,
+        port: link.port,
+        uuid: String::new(),
+        flow: String::new(),
+        reality: RealitySettings::default(),
+        tls: TlsSettings {
+            server_name: String::new(),
+            ..TlsSettings::default()
+        },
+        security: "none".to_string(),
+        protocol: "shadowsocks".to_string(),
+        password: password.to_string(),
+        ss_method: method.to_lowercase(),
+        ss_plugin: plugin,
+        mux: mux_from_query(link.query)?,
+        ..ServerConfig::default()
+    })
+}
+
+/// Parse a `hysteria2://AUTH@HOST:PORT/?sni=...&obfs=salamander&obfs-password=...#NAME`
+/// link as defined by the Hysteria2 URI scheme. Port-hopping ranges
+/// (`HOST:20000-30000`) are not supported by xray and fail the port parse.
+fn parse_hysteria2_uri(rest: &str) -> Result<ServerConfig, AppError> {
+    let link = split_share_link(rest, "hysteria2")?;
+
+    let mut sni = String::new();
+    let mut alpn = Vec::new();
+    let mut obfs_password = String::new();
+    let mut allow_insecure = false;
+    let mut pinned_cert_sha256 = Vec::new();
+    let mut ech = String::new();
+
+    for (key, value) in query_params(link.query) {
+        match key {
+            "sni" | "peer" => sni = value,
+            "alpn" => alpn = split_list(&value),
+            "insecure" | "allowInsecure" => allow_insecure = is_truthy(&value),
+            "pinSHA256" | "pcs" => pinned_cert_sha256 = split_list(&value),
+            "ech" => ech = value,
+            "obfs" if value != "salamander" && !value.is_empty() => {
+                return Err(AppError::Config(format!(
+                    "Unsupported hysteria2 obfs '{value}' (expected salamander)"
+                )));
+            }
+            "obfs-password" => obfs_password = value,
+            _ => {}
+        }
+    }
+
+    Ok(ServerConfig {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: link.name,
+        address: link.address,
+        port: link.port,
+        uuid: String::new(),
+        flow: String::new(),
+        reality: RealitySettings::default(),
+        tls: TlsSettings {
+            server_name: sni,
+            alpn,
+            allow_insecure,
+            pinned_cert_sha256,
+            ech: EchSettings::from_xray(&ech),
+            ..TlsSettings::default()
+        },
+        security: "tls".to_string(),
+        protocol: "hysteria2".to_string(),
+        password: link.userinfo,
+        hy2_obfs_password: obfs_password,
+        ..ServerConfig::default()
     })
 }
 
-pub fn to_vless_uri(server: &ServerConfig) -> String {
+/// Parse a v2rayN-style `wireguard://PRIVATEKEY@HOST:PORT/?publickey=...&address=...#NAME`
+/// link. `.conf` files go through [`crate::wireguard`] instead.
+fn parse_wireguard_uri(rest: &str) -> Result<ServerConfig, AppError> {
+    let link = split_share_link(rest, "wireguard")?;
+    let mut server = ServerConfig {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: link.name,
+        address: link.address,
+        port: link.port,
+        uuid: String::new(),
+        flow: String::new(),
+        security: "none".to_string(),
+        protocol: "wireguard".to_string(),
+        wg_private_key: link.userinfo,
+        ..ServerConfig::default()
+    };
+    for (key, value) in query_params(link.query) {
+        match key {
+            "publickey" => server.wg_peer_public_key = value,
+            "presharedkey" => server.wg_preshared_key = value,
+            "address" => server.wg_local_addresses = split_list(&value),
+            "allowedips" => server.wg_allowed_ips = split_list(&value),
+            "mtu" => {
+                server.wg_mtu = value
+                    .parse()
+                    .map_err(|_| AppError::Config(format!("Invalid mtu: {value}")))?
+            }
+            "reserved" => {
+                server.wg_reserved = split_list(&value)
+                    .iter()
+                    .map(|b| b.parse::<u8>())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| AppError::Config(format!("Invalid reserved: {value}")))?
+            }
+            _ => {}
+        }
+    }
+    Ok(server)
+}
+
+/// Read the mux options carried as `mux=1&muxConcurrency=N&xudpConcurrency=N&xudpProxyUDP443=...`.
+fn mux_from_query(query: &str) -> Result<MuxSettings, AppError> {
+    let mut mux = MuxSettings::default();
+    let number = |key: &str, value: &str| {
+        value
+            .parse::<i16>()
+            .map_err(|_| AppError::Config(format!("Invalid {key}: {value}")))
+    };
+    for (key, value) in query_params(query) {
+        match key {
+            "mux" => mux.enabled = is_truthy(&value),
+            "muxConcurrency" => mux.concurrency = number(key, &value)?,
+            "xudpConcurrency" => mux.xudp_concurrency = number(key, &value)?,
+            "xudpProxyUDP443" => mux.xudp_proxy_udp_443 = value,
+            _ => {}
+        }
+    }
+    Ok(mux)
+}
+
+/// Boolean share-link flags are spelled `1` or `true`.
+fn is_truthy(value: &str) -> bool {
+    matches!(value, "1" | "true")
+}
+
+/// Split a comma-separated list (ALPN, certificate hashes), dropping empty
+/// entries.
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Read a vmess link field as a trimmed string, stringifying numbers.
+fn vmess_field(link: &Value, key: &str) -> String {
+    match link.get(key) {
+        Some(Value::String(s)) => s.trim().to_string(),
+        Some(Value::Number(n)) => n.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Decode base64 the way share links use it in the wild: standard or URL-safe
+/// alphabet, with or without padding, ignoring surrounding whitespace.
+pub(crate) fn decode_base64(s: &str) -> Option<Vec<u8>> {
+    let s: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+    [STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD]
+        .iter()
+        .find_map(|engine| engine.decode(&s).ok())
+}
+
+/// [`decode_base64`] into UTF-8 text.
+fn decode_base64_str(s: &str) -> Option<String> {
+    decode_base64(s).and_then(|bytes| String::from_utf8(bytes).ok())
+}
+
+/// The share link for `server`, refusing the raw xray servers that no
+/// link format can express.
+pub fn share_uri(server: &ServerConfig) -> Result<String, AppError> {
+    if server.protocol == "raw" {
+        return Err(AppError::Config(
+            "Raw xray outbounds have no share link".to_string(),
+        ));
+    }
+    Ok(to_vless_uri(server))
+}
+
+/// Serialize a server as a share link in its protocol's native scheme.
+/// Unchecked: a raw server comes out as a bogus `vless://` link, so callers
+/// go through [`share_uri`].
+fn to_vless_uri(server: &ServerConfig) -> String {
+    match server.protocol.as_str() {
+        "vmess" => return to_vmess_uri(server),
+        "trojan" => return to_trojan_uri(server),
+        "shadowsocks" => return to_ss_uri(server),
+        "hysteria2" => return to_hysteria2_uri(server),
+        "wireguard" => return to_wireguard_uri(server),
+        _ => {}
+    }
+    if server.network == "grpc"
+        || server.network == "ws"
+        || (server.network == "tcp" && server.security == "tls")
+    {
+        return to_vless_stream_uri(server);
+    }
     let name = url_encode(&server.name);
     if server.network == "xhttp" {
         let path = if server.xhttp_path.trim().is_empty() {
@@ -158,15 +738,19 @@ pub fn to_vless_uri(server: &ServerConfig) -> String {
             // HTTP Host header are both the CDN domain (server_name). mode is
             // always emitted so the (usually stream-one) profile round-trips.
             return format!(
-                "vless://{}@{}:{}?encryption=none&type=xhttp&security=tls&host={}&path={}&sni={}&fp={}&mode={}#{}",
+                "vless://{}@{}:{}?encryption={}&type=xhttp&security=tls&host={}&path={}&sni={}&fp={}&mode={}{}{}{}#{}",
                 server.uuid,
-                server.address,
+                format_host(&server.address),
                 server.port,
-                url_encode(&server.reality.server_name),
+                url_encode(server.vless_encryption()),
+                url_encode(&server.tls.server_name),
                 url_encode(path),
-                url_encode(&server.reality.server_name),
-                url_encode(&server.reality.fingerprint),
+                url_encode(&server.tls.server_name),
+                url_encode(&server.tls.fingerprint),
                 url_encode(mode),
+                tls_params(server, "allowInsecure", "pcs"),
+                xhttp_extra_param(server),
+                mux_params(server),
                 name,
             );
         }
@@ -178,34 +762,323 @@ pub fn to_vless_uri(server: &ServerConfig) -> String {
             format!("&mode={}", url_encode(mode))
         };
         format!(
-            "vless://{}@{}:{}?encryption=none&type=xhttp&path={}&security=reality&sni={}&fp={}&pbk={}&sid={}{}#{}",
+            "vless://{}@{}:{}?encryption={}&type=xhttp&path={}&security=reality&sni={}&fp={}&pbk={}&sid={}{}{}{}{}#{}",
             server.uuid,
-            server.address,
+            format_host(&server.address),
             server.port,
+            url_encode(server.vless_encryption()),
             url_encode(path),
-            url_encode(&server.reality.server_name),
-            url_encode(&server.reality.fingerprint),
+            url_encode(&server.tls.server_name),
+            url_encode(&server.tls.fingerprint),
             url_encode(&server.reality.public_key),
             url_encode(&server.reality.short_id),
+            spx_param(server),
             mode_param,
+            xhttp_extra_param(server),
+            mux_params(server),
             name,
         )
     } else {
         format!(
-            "vless://{}@{}:{}?encryption=none&flow={}&type=tcp&security=reality&sni={}&fp={}&pbk={}&sid={}#{}",
+            "vless://{}@{}:{}?encryption={}&flow={}&type=tcp&security=reality&sni={}&fp={}&pbk={}&sid={}{}{}#{}",
             server.uuid,
-            server.address,
+            format_host(&server.address),
             server.port,
+            url_encode(server.vless_encryption()),
             url_encode(&server.flow),
-            url_encode(&server.reality.server_name),
-            url_encode(&server.reality.fingerprint),
+            url_encode(&server.tls.server_name),
+            url_encode(&server.tls.fingerprint),
             url_encode(&server.reality.public_key),
             url_encode(&server.reality.short_id),
+            spx_param(server),
+            mux_params(server),
             name,
         )
     }
 }
 
+/// Emit a VLESS link for the gRPC / WebSocket transports (REALITY on gRPC
+/// only, or TLS) and for raw TCP with real TLS.
+fn to_vless_stream_uri(server: &ServerConfig) -> String {
+    let is_tls = server.security == "tls";
+    let mut params = format!(
+        "encryption={}&type={}&security={}&sni={}&fp={}",
+        url_encode(server.vless_encryption()),
+        server.network,
+        if is_tls { "tls" } else { "reality" },
+        url_encode(&server.tls.server_name),
+        url_encode(&server.tls.fingerprint),
+    );
+    if is_tls {
+        params.push_str(&tls_params(server, "allowInsecure", "pcs"));
+    } else {
+        params.push_str(&format!(
+            "&pbk={}&sid={}{}",
+            url_encode(&server.reality.public_key),
+            url_encode(&server.reality.short_id),
+            spx_param(server)
+        ));
+    }
+    match server.network.as_str() {
+        "ws" => {
+            let path = if server.ws_early_data > 0 {
+                format!("{}?ed={}", server.ws_path, server.ws_early_data)
+            } else {
+                server.ws_path.clone()
+            };
+            params.push_str(&format!(
+                "&path={}&host={}",
+                url_encode(&path),
+                url_encode(&server.ws_host)
+            ));
+        }
+        "grpc" => params.push_str(&format!(
+            "&serviceName={}&mode={}",
+            url_encode(&server.grpc_service_name),
+            if server.grpc_multi_mode {
+                "multi"
+            } else {
+                "gun"
+            }
+        )),
+        _ => params.push_str(&format!("&flow={}", url_encode(&server.flow))),
+    }
+    params.push_str(&mux_params(server));
+    format!(
+        "vless://{}@{}:{}?{}#{}",
+        server.uuid,
+        format_host(&server.address),
+        server.port,
+        params,
+        url_encode(&server.name),
+    )
+}
+
+/// Emit a `vmess://` link in the v2rayN "v2" JSON format. All values are
+/// strings, which every importer we know of accepts.
+fn to_vmess_uri(server: &ServerConfig) -> String {
+    let is_ws = server.network == "ws";
+    let host = if is_ws { server.ws_host.as_str() } else { "" };
+    let path = match server.network.as_str() {
+        "ws" => server.ws_path.as_str(),
+        "grpc" => server.grpc_service_name.as_str(),
+        _ => "",
+    };
+    let is_tls = server.security == "tls";
+    let link = json!({
+        "v": "2",
+        "ps": server.name,
+        "add": server.address,
+        "port": server.port.to_string(),
+        "id": server.uuid,
+        "aid": server.alter_id.to_string(),
+        "scy": server.vmess_cipher,
+        "net": server.network,
+        "type": "none",
+        "host": host,
+        "path": path,
+        "tls": if is_tls { "tls" } else { "" },
+        "sni": if is_tls { server.tls.server_name.as_str() } else { "" },
+        "fp": if is_tls { server.tls.fingerprint.as_str() } else { "" },
+        "alpn": if is_tls { server.tls.alpn.join(",") } else { String::new() }
+    });
+    format!("vmess://{}", STANDARD.encode(link.to_string()))
+}
+
+/// Emit a `trojan://` link in the de-facto format shared by v2rayN, Shadowrocket
+/// and sing-box importers.
+fn to_trojan_uri(server: &ServerConfig) -> String {
+    let mut params = format!(
+        "security=tls&sni={}&fp={}&type={}",
+        url_encode(&server.tls.server_name),
+        url_encode(&server.tls.fingerprint),
+        url_encode(&server.network),
+    );
+    params.push_str(&tls_params(server, "allowInsecure", "pcs"));
+    match server.network.as_str() {
+        "ws" => params.push_str(&format!(
+            "&path={}&host={}",
+            url_encode(&server.ws_path),
+            url_encode(&server.ws_host)
+        )),
+        "grpc" => params.push_str(&format!(
+            "&serviceName={}",
+            url_encode(&server.grpc_service_name)
+        )),
+        _ => {}
+    }
+    params.push_str(&mux_params(server));
+    format!(
+        "trojan://{}@{}:{}?{}#{}",
+        url_encode(&server.password),
+        format_host(&server.address),
+        server.port,
+        params,
+        url_encode(&server.name),
+    )
+}
+
+/// Emit a SIP002 `ss://` link. 2022 ciphers use the percent-encoded SIP022
+/// userinfo (their keys are base64 already); older ciphers the base64url form
+/// every client understands.
+fn to_ss_uri(server: &ServerConfig) -> String {
+    let userinfo = if server.ss_method.starts_with("2022-") {
+        format!(
+            "{}:{}",
+            url_encode(&server.ss_method),
+            url_encode(&server.password)
+        )
+    } else {
+        URL_SAFE_NO_PAD.encode(format!("{}:{}", server.ss_method, server.password))
+    };
+    let mut params = String::new();
+    if !server.ss_plugin.trim().is_empty() {
+        params.push_str(&format!("&plugin={}", url_encode(server.ss_plugin.trim())));
+    }
+    params.push_str(&mux_params(server));
+    let query = match params.strip_prefix('&') {
+        Some(params) => format!("/?{params}"),
+        None => String::new(),
+    };
+    format!(
+        "ss://{}@{}:{}{}#{}",
+        userinfo,
+        format_host(&server.address),
+        server.port,
+        query,
+        url_encode(&server.name),
+    )
+}
+
+/// Emit a `hysteria2://` link. Bandwidth hints are client-side settings and
+/// have no place in the URI scheme, so they are not exported.
+fn to_hysteria2_uri(server: &ServerConfig) -> String {
+    let mut params = format!("sni={}", url_encode(&server.tls.server_name));
+    if !server.hy2_obfs_password.is_empty() {
+        params.push_str(&format!(
+            "&obfs=salamander&obfs-password={}",
+            url_encode(&server.hy2_obfs_password)
+        ));
+    }
+    params.push_str(&tls_params(server, "insecure", "pinSHA256"));
+    format!(
+        "hysteria2://{}@{}:{}/?{}#{}",
+        url_encode(&server.password),
+        format_host(&server.address),
+        server.port,
+        params,
+        url_encode(&server.name),
+    )
+}
+
+/// Emit a v2rayN-style `wireguard://` link. Optional fields are only
+/// written when set.
+fn to_wireguard_uri(server: &ServerConfig) -> String {
+    let mut params = format!(
+        "publickey={}&address={}",
+        url_encode(&server.wg_peer_public_key),
+        url_encode(&server.wg_local_addresses.join(","))
+    );
+    if !server.wg_preshared_key.is_empty() {
+        params.push_str(&format!(
+            "&presharedkey={}",
+            url_encode(&server.wg_preshared_key)
+        ));
+    }
+    if !server.wg_allowed_ips.is_empty() {
+        params.push_str(&format!(
+            "&allowedips={}",
+            url_encode(&server.wg_allowed_ips.join(","))
+        ));
+    }
+    if server.wg_mtu > 0 {
+        params.push_str(&format!("&mtu={}", server.wg_mtu));
+    }
+    if !server.wg_reserved.is_empty() {
+        let reserved: Vec<String> = server.wg_reserved.iter().map(u8::to_string).collect();
+        params.push_str(&format!("&reserved={}", url_encode(&reserved.join(","))));
+    }
+    format!(
+        "wireguard://{}@{}:{}/?{}#{}",
+        url_encode(&server.wg_private_key),
+        format_host(&server.address),
+        server.port,
+        params,
+        url_encode(&server.name),
+    )
+}
+
+/// Optional TLS parameters (`&alpn=...` etc.) for URL-style share links.
+/// Schemes disagree on the names of the insecure and pinning flags.
+fn tls_params(server: &ServerConfig, insecure_key: &str, pin_key: &str) -> String {
+    let mut params = String::new();
+    if !server.tls.alpn.is_empty() {
+        params.push_str(&format!("&alpn={}", url_encode(&server.tls.alpn.join(","))));
+    }
+    if server.tls.allow_insecure {
+        params.push_str(&format!("&{insecure_key}=1"));
+    }
+    let pins = server.tls.pinned_hashes();
+    if !pins.is_empty() {
+        params.push_str(&format!("&{pin_key}={}", url_encode(&pins.join(","))));
+    }
+    params.push_str(&ech_param(server));
+    params
+}
+
+/// REALITY `&spx=` (spiderX); empty when unset. Share links carry a single
+/// `sid`, so extra short IDs stay local to the profile.
+fn spx_param(server: &ServerConfig) -> String {
+    if server.reality.spider_x.is_empty() {
+        return String::new();
+    }
+    format!("&spx={}", url_encode(&server.reality.spider_x))
+}
+
+/// `&ech=` in xray's echConfigList form; empty when ECH is off.
+fn ech_param(server: &ServerConfig) -> String {
+    server
+        .tls
+        .ech
+        .to_xray()
+        .map(|ech| format!("&ech={}", url_encode(&ech)))
+        .unwrap_or_default()
+}
+
+/// Mux parameters for URL-style share links; empty when mux is off so
+/// existing links stay unchanged.
+fn mux_params(server: &ServerConfig) -> String {
+    if !server.mux.enabled {
+        return String::new();
+    }
+    format!(
+        "&mux=1&muxConcurrency={}&xudpConcurrency={}&xudpProxyUDP443={}",
+        server.mux.concurrency,
+        server.mux.xudp_concurrency,
+        url_encode(&server.mux.xudp_proxy_udp_443),
+    )
+}
+
+/// xHTTP `extra` block as a URL-encoded JSON parameter; empty when unset.
+fn xhttp_extra_param(server: &ServerConfig) -> String {
+    if server.xhttp_extra.is_empty() {
+        return String::new();
+    }
+    format!(
+        "&extra={}",
+        url_encode(&server.xhttp_extra.to_xray().to_string())
+    )
+}
+
+/// Bracket IPv6 literals so the port separator stays unambiguous.
+pub(crate) fn format_host(address: &str) -> String {
+    if address.contains(':') && !address.starts_with('[') {
+        format!("[{address}]")
+    } else {
+        address.to_string()
+    }
+}
+
 fn url_encode(s: &str) -> String {
     let mut result = String::with_capacity(s.len());
     for b in s.bytes() {
@@ -225,7 +1098,7 @@ fn url_encode(s: &str) -> String {
 
 const HEX_CHARS: [u8; 16] = *b"0123456789ABCDEF";
 
-fn url_decode(s: &str) -> String {
+pub(crate) fn url_decode(s: &str) -> String {
     let mut result = Vec::with_capacity(s.len());
     let bytes = s.as_bytes();
     let mut i = 0;
@@ -263,7 +1136,7 @@ pub fn parse_vless_uri_cmd(uri: String) -> Result<ServerConfig, String> {
 
 #[tauri::command]
 pub fn export_vless_uri(server_config: ServerConfig) -> Result<String, String> {
-    Ok(to_vless_uri(&server_config))
+    share_uri(&server_config).map_err(|e| e.to_string())
 }
 
 #[cfg(test)]
@@ -281,13 +1154,18 @@ mod tests {
             reality: RealitySettings {
                 public_key: "kieJgZYLW9ZiKbGLpKnv4XyVo6_42inSONJrr-96tUU".to_string(),
                 short_id: "d64736262cd50811".to_string(),
+                ..RealitySettings::default()
+            },
+            tls: TlsSettings {
                 server_name: "www.microsoft.com".to_string(),
                 fingerprint: "chrome".to_string(),
+                ..TlsSettings::default()
             },
             network: "tcp".to_string(),
             xhttp_path: String::new(),
             security: "reality".to_string(),
             xhttp_mode: "auto".to_string(),
+            ..ServerConfig::default()
         }
     }
 
@@ -301,8 +1179,8 @@ mod tests {
         assert_eq!(config.port, 443);
         assert_eq!(config.flow, "xtls-rprx-vision");
         assert_eq!(config.name, "My Server");
-        assert_eq!(config.reality.server_name, "www.microsoft.com");
-        assert_eq!(config.reality.fingerprint, "chrome");
+        assert_eq!(config.tls.server_name, "www.microsoft.com");
+        assert_eq!(config.tls.fingerprint, "chrome");
         assert_eq!(
             config.reality.public_key,
             "kieJgZYLW9ZiKbGLpKnv4XyVo6_42inSONJrr-96tUU"
@@ -357,8 +1235,8 @@ mod tests {
         assert_eq!(parsed.name, server.name);
         assert_eq!(parsed.reality.public_key, server.reality.public_key);
         assert_eq!(parsed.reality.short_id, server.reality.short_id);
-        assert_eq!(parsed.reality.server_name, server.reality.server_name);
-        assert_eq!(parsed.reality.fingerprint, server.reality.fingerprint);
+        assert_eq!(parsed.tls.server_name, server.tls.server_name);
+        assert_eq!(parsed.tls.fingerprint, server.tls.fingerprint);
     }
 
     #[test]
@@ -398,8 +1276,8 @@ mod tests {
 
     #[test]
     fn parse_uri_wrong_scheme() {
-        // vmess:// should fail
-        let result = parse_vless_uri("vmess://aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee@1.2.3.4:443");
+        // socks:// should fail
+        let result = parse_vless_uri("socks://aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee@1.2.3.4:443");
         assert!(result.is_err());
         let err = result.unwrap_err().to_string();
         assert!(
@@ -408,11 +1286,625 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_vmess_uri_rejects_non_base64_payload() {
+        // vmess links are base64 JSON, not the vless userinfo@host form.
+        let result = parse_vless_uri("vmess://aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee@1.2.3.4:443");
+        let err = result.unwrap_err().to_string();
+        assert!(
+            err.contains("vmess://"),
+            "expected vmess hint in error: {err}"
+        );
+    }
+
+    #[test]
+    fn parse_vmess_ws_tls_uri() {
+        let link = r#"{"v":"2","ps":"DE VMess","add":"vm.example.com","port":"443","id":"aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee","aid":"0","scy":"auto","net":"ws","type":"none","host":"cdn.example.com","path":"/ray","tls":"tls","sni":"","fp":"firefox"}"#;
+        let uri = format!("vmess://{}", STANDARD.encode(link));
+        let config = parse_vless_uri(&uri).unwrap();
+        assert_eq!(config.protocol, "vmess");
+        assert_eq!(config.name, "DE VMess");
+        assert_eq!(config.address, "vm.example.com");
+        assert_eq!(config.port, 443);
+        assert_eq!(config.network, "ws");
+        assert_eq!(config.ws_path, "/ray");
+        assert_eq!(config.ws_host, "cdn.example.com");
+        assert_eq!(config.security, "tls");
+        // No sni → the Host header doubles as SNI.
+        assert_eq!(config.tls.server_name, "cdn.example.com");
+        assert_eq!(config.tls.fingerprint, "firefox");
+        assert_eq!(config.flow, "");
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn parse_vmess_numeric_fields_and_unpadded_base64() {
+        let link = r#"{"v":2,"ps":"old","add":"1.2.3.4","port":10086,"id":"aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee","aid":64,"net":"tcp","tls":""}"#;
+        let uri = format!("vmess://{}", URL_SAFE_NO_PAD.encode(link));
+        let config = parse_vless_uri(&uri).unwrap();
+        assert_eq!(config.port, 10086);
+        assert_eq!(config.alter_id, 64);
+        assert_eq!(config.vmess_cipher, "auto");
+        assert_eq!(config.network, "tcp");
+        assert_eq!(config.security, "none");
+    }
+
+    #[test]
+    fn parse_vmess_unsupported_transport() {
+        let link = r#"{"v":"2","add":"1.2.3.4","port":"443","id":"aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee","net":"kcp"}"#;
+        let uri = format!("vmess://{}", STANDARD.encode(link));
+        assert!(parse_vless_uri(&uri).is_err());
+    }
+
+    #[test]
+    fn roundtrip_vmess() {
+        let server = ServerConfig {
+            name: "VMess WS".to_string(),
+            address: "vm.example.com".to_string(),
+            port: 8443,
+            uuid: "aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee".to_string(),
+            flow: String::new(),
+            tls: TlsSettings {
+                server_name: "vm.example.com".to_string(),
+                ..TlsSettings::default()
+            },
+            network: "ws".to_string(),
+            security: "tls".to_string(),
+            protocol: "vmess".to_string(),
+            vmess_cipher: "chacha20-poly1305".to_string(),
+            ws_path: "/ws?ed=2048".to_string(),
+            ws_host: "vm.example.com".to_string(),
+            ..ServerConfig::default()
+        };
+        let uri = to_vless_uri(&server);
+        assert!(uri.starts_with("vmess://"));
+        let parsed = parse_vless_uri(&uri).unwrap();
+        assert_eq!(parsed.protocol, "vmess");
+        assert_eq!(parsed.name, server.name);
+        assert_eq!(parsed.address, server.address);
+        assert_eq!(parsed.port, server.port);
+        assert_eq!(parsed.uuid, server.uuid);
+        assert_eq!(parsed.vmess_cipher, server.vmess_cipher);
+        assert_eq!(parsed.network, "ws");
+        assert_eq!(parsed.ws_path, server.ws_path);
+        assert_eq!(parsed.ws_host, server.ws_host);
+        assert_eq!(parsed.security, "tls");
+        assert_eq!(parsed.tls.server_name, "vm.example.com");
+    }
+
     #[test]
     fn parse_uri_trojan_scheme() {
-        // trojan:// should fail
-        let result = parse_vless_uri("trojan://password@1.2.3.4:443");
-        assert!(result.is_err());
+        let config = parse_vless_uri("trojan://password@1.2.3.4:443").unwrap();
+        assert_eq!(config.protocol, "trojan");
+        assert_eq!(config.password, "password");
+        assert_eq!(config.address, "1.2.3.4");
+        assert_eq!(config.port, 443);
+        assert_eq!(config.network, "tcp");
+        assert_eq!(config.security, "tls");
+        // No sni: the config falls back to the address at connect time.
+        assert_eq!(config.tls.server_name, "");
+    }
+
+    #[test]
+    fn parse_trojan_ws_uri() {
+        let uri = "trojan://p%40ss%3Aword@[2001:db8::1]:8443/?security=tls&sni=tj.example.com&alpn=h2%2Chttp%2F1.1&fp=safari&type=ws&path=%2Ftj&host=cdn.example.com#Trojan%20WS";
+        let config = parse_vless_uri(uri).unwrap();
+        assert_eq!(config.password, "p@ss:word");
+        assert_eq!(config.address, "2001:db8::1");
+        assert_eq!(config.port, 8443);
+        assert_eq!(config.tls.server_name, "tj.example.com");
+        assert_eq!(config.tls.fingerprint, "safari");
+        assert_eq!(config.tls.alpn, vec!["h2", "http/1.1"]);
+        assert_eq!(config.network, "ws");
+        assert_eq!(config.ws_path, "/tj");
+        assert_eq!(config.ws_host, "cdn.example.com");
+        assert_eq!(config.name, "Trojan WS");
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn parse_trojan_rejects_plaintext_and_unknown_transport() {
+        assert!(parse_vless_uri("trojan://pw@1.2.3.4:443?security=none").is_err());
+        assert!(parse_vless_uri("trojan://pw@1.2.3.4:443?type=kcp").is_err());
+    }
+
+    #[test]
+    fn parse_ss_sip002_base64_userinfo() {
+        // base64url("chacha20-ietf-poly1305:secret")
+        let uri = format!(
+            "ss://{}@5.6.7.8:8388#SS%20Backup",
+            URL_SAFE_NO_PAD.encode("chacha20-ietf-poly1305:secret")
+        );
+        let config = parse_vless_uri(&uri).unwrap();
+        assert_eq!(config.protocol, "shadowsocks");
+        assert_eq!(config.ss_method, "chacha20-ietf-poly1305");
+        assert_eq!(config.password, "secret");
+        assert_eq!(config.address, "5.6.7.8");
+        assert_eq!(config.port, 8388);
+        assert_eq!(config.name, "SS Backup");
+        assert_eq!(config.ss_plugin, "");
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn parse_ss_sip022_plain_userinfo_with_plugin() {
+        let uri = "ss://2022-blake3-aes-128-gcm:AAAAAAAAAAAAAAAAAAAAAA%3D%3D@[2001:db8::5]:443/?plugin=v2ray-plugin%3Btls%3Bhost%3Dss.example.com#ss2022";
+        let config = parse_vless_uri(uri).unwrap();
+        assert_eq!(config.ss_method, "2022-blake3-aes-128-gcm");
+        assert_eq!(config.password, "AAAAAAAAAAAAAAAAAAAAAA==");
+        assert_eq!(config.address, "2001:db8::5");
+        assert_eq!(config.ss_plugin, "v2ray-plugin;tls;host=ss.example.com");
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn parse_ss_legacy_whole_base64() {
+        let uri = format!(
+            "ss://{}#old",
+            STANDARD.encode("aes-256-gcm:pa:ss@9.9.9.9:443")
+        );
+        let config = parse_vless_uri(&uri).unwrap();
+        assert_eq!(config.ss_method, "aes-256-gcm");
+        // Only the first ':' separates method from password.
+        assert_eq!(config.password, "pa:ss");
+        assert_eq!(config.address, "9.9.9.9");
+        assert_eq!(config.port, 443);
+        assert_eq!(config.name, "old");
+    }
+
+    #[test]
+    fn roundtrip_ss() {
+        let key = "AAAAAAAAAAAAAAAAAAAAAA==";
+        for (method, password, plugin) in [
+            ("aes-256-gcm", "p@ss word+/=", ""),
+            (
+                "2022-blake3-aes-128-gcm",
+                key,
+                "v2ray-plugin;path=/ws;host=a.example",
+            ),
+        ] {
+            let server = ServerConfig {
+                name: "SS".to_string(),
+                address: "ss.example.com".to_string(),
+                port: 8388,
+                uuid: String::new(),
+                flow: String::new(),
+                security: "none".to_string(),
+                protocol: "shadowsocks".to_string(),
+                ss_method: method.to_string(),
+                password: password.to_string(),
+                ss_plugin: plugin.to_string(),
+                ..ServerConfig::default()
+            };
+            let uri = to_vless_uri(&server);
+            assert!(uri.starts_with("ss://"), "{uri}");
+            let parsed = parse_vless_uri(&uri).unwrap();
+            assert_eq!(parsed.ss_method, method);
+            assert_eq!(parsed.password, password);
+            assert_eq!(parsed.ss_plugin, plugin);
+            assert_eq!(parsed.address, server.address);
+            assert_eq!(parsed.port, server.port);
+            assert_eq!(parsed.name, server.name);
+        }
+    }
+
+    #[test]
+    fn roundtrip_mux() {
+        let mux = MuxSettings {
+            enabled: true,
+            concurrency: -1,
+            xudp_concurrency: 32,
+            xudp_proxy_udp_443: "skip".to_string(),
+        };
+        let vless = ServerConfig {
+            mux: mux.clone(),
+            ..sample_server()
+        };
+        let ss = ServerConfig {
+            uuid: String::new(),
+            flow: String::new(),
+            security: "none".to_string(),
+            protocol: "shadowsocks".to_string(),
+            ss_method: "aes-128-gcm".to_string(),
+            password: "secret".to_string(),
+            ss_plugin: "v2ray-plugin;path=/ws".to_string(),
+            mux: mux.clone(),
+            ..sample_server()
+        };
+        for server in [vless, ss] {
+            let uri = to_vless_uri(&server);
+            assert!(uri.contains("&mux=1") || uri.contains("?mux=1"), "{uri}");
+            let parsed = parse_vless_uri(&uri).unwrap();
+            assert_eq!(parsed.mux, mux);
+            assert_eq!(parsed.ss_plugin, server.ss_plugin);
+        }
+
+        // Mux off leaves links unchanged.
+        assert!(!to_vless_uri(&sample_server()).contains("mux"));
+        let err =
+            parse_vless_uri("trojan://pw@example.com:443?security=tls&mux=1&muxConcurrency=lots#T")
+                .unwrap_err();
+        assert!(err.to_string().contains("muxConcurrency"), "{err}");
+    }
+
+    #[test]
+    fn roundtrip_vless_encryption() {
+        let encryption =
+            "mlkem768x25519plus.native.0rtt.100-111-1111.WUe0I6Pm0A8ZPdwaqGB0BZtfEBEpkJcCvWb7YmiTcXc";
+        let tcp = ServerConfig {
+            vless_encryption: encryption.to_string(),
+            ..sample_server()
+        };
+        let xhttp = ServerConfig {
+            network: "xhttp".to_string(),
+            flow: String::new(),
+            ..tcp.clone()
+        };
+        let ws = ServerConfig {
+            network: "ws".to_string(),
+            security: "tls".to_string(),
+            flow: String::new(),
+            ..tcp.clone()
+        };
+        for server in [tcp, xhttp, ws] {
+            let uri = to_vless_uri(&server);
+            assert!(
+                uri.contains("?encryption=mlkem768x25519plus.native.0rtt.100-111-1111."),
+                "{uri}"
+            );
+            assert_eq!(parse_vless_uri(&uri).unwrap().vless_encryption, encryption);
+        }
+
+        let parsed = parse_vless_uri(
+            "vless://aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee@1.2.3.4:443?sni=example.com&pbk=key&sid=id",
+        )
+        .unwrap();
+        assert_eq!(parsed.vless_encryption, "none");
+        assert!(to_vless_uri(&parsed).contains("?encryption=none&"));
+    }
+
+    #[test]
+    fn roundtrip_spider_x() {
+        let tcp = ServerConfig {
+            reality: RealitySettings {
+                public_key: "pbk".to_string(),
+                short_id: "ab".to_string(),
+                extra_short_ids: vec!["cd".to_string()],
+                spider_x: "/search?q=1".to_string(),
+            },
+            ..sample_server()
+        };
+        let xhttp = ServerConfig {
+            network: "xhttp".to_string(),
+            flow: String::new(),
+            ..tcp.clone()
+        };
+        let grpc = ServerConfig {
+            network: "grpc".to_string(),
+            flow: String::new(),
+            ..tcp.clone()
+        };
+        for server in [tcp, xhttp, grpc] {
+            let uri = to_vless_uri(&server);
+            assert!(uri.contains("&spx=%2Fsearch%3Fq%3D1"), "{uri}");
+            let parsed = parse_vless_uri(&uri).unwrap();
+            assert_eq!(parsed.reality.spider_x, "/search?q=1");
+            assert_eq!(parsed.reality.short_id, "ab");
+            assert!(parsed.reality.extra_short_ids.is_empty());
+        }
+        assert!(!to_vless_uri(&sample_server()).contains("spx"));
+    }
+
+    #[test]
+    fn roundtrip_ech() {
+        let ech = EchSettings {
+            doh_server: "https://1.1.1.1/dns-query".to_string(),
+            query_domain: "cloudflare-ech.com".to_string(),
+            ..EchSettings::default()
+        };
+        let tls = TlsSettings {
+            server_name: "cdn.example.com".to_string(),
+            ech: ech.clone(),
+            ..TlsSettings::default()
+        };
+        let xhttp = ServerConfig {
+            network: "xhttp".to_string(),
+            security: "tls".to_string(),
+            tls: tls.clone(),
+            ..sample_server()
+        };
+        let ws = ServerConfig {
+            network: "ws".to_string(),
+            flow: String::new(),
+            ..xhttp.clone()
+        };
+        let trojan = ServerConfig {
+            protocol: "trojan".to_string(),
+            uuid: String::new(),
+            password: "pw".to_string(),
+            network: "tcp".to_string(),
+            flow: String::new(),
+            ..xhttp.clone()
+        };
+        for server in [xhttp, ws, trojan] {
+            let uri = to_vless_uri(&server);
+            assert!(uri.contains("&ech=cloudflare-ech.com%2Bhttps"), "{uri}");
+            assert_eq!(parse_vless_uri(&uri).unwrap().tls.ech, ech);
+        }
+
+        let parsed = parse_vless_uri(
+            "hysteria2://auth@hy.example.com:443/?sni=hy.example.com&ech=AEX%2BDQBBpQAgACA%3D#HY",
+        )
+        .unwrap();
+        assert_eq!(parsed.tls.ech.config_list, "AEX+DQBBpQAgACA=");
+    }
+
+    #[test]
+    fn roundtrip_xhttp_extra() {
+        let server = ServerConfig {
+            network: "xhttp".to_string(),
+            xhttp_path: "/up".to_string(),
+            xhttp_extra: XhttpExtra {
+                x_padding_bytes: "100-1000".to_string(),
+                no_grpc_header: true,
+                xmux: crate::models::XmuxSettings {
+                    max_concurrency: "16-32".to_string(),
+                    h_keep_alive_period: 30,
+                    ..Default::default()
+                },
+                download: Some(crate::models::XhttpDownload {
+                    address: "5.6.7.8".to_string(),
+                    port: 443,
+                    security: "tls".to_string(),
+                    path: "/down".to_string(),
+                    tls: TlsSettings {
+                        server_name: "cdn.example.com".to_string(),
+                        ..TlsSettings::default()
+                    },
+                    reality: RealitySettings::default(),
+                }),
+                ..XhttpExtra::default()
+            },
+            ..sample_server()
+        };
+        let uri = to_vless_uri(&server);
+        assert!(uri.contains("&extra=%7B"), "{uri}");
+        let parsed = parse_vless_uri(&uri).unwrap();
+        assert_eq!(parsed.xhttp_extra, server.xhttp_extra);
+
+        // Links written by other clients carry plain numbers.
+        let uri = "vless://id@1.2.3.4:443?type=xhttp&security=reality&pbk=k&sid=ab&extra=%7B%22xPaddingBytes%22%3A%22100-1000%22%2C%22xmux%22%3A%7B%22maxConnections%22%3A4%7D%7D#X";
+        let parsed = parse_vless_uri(uri).unwrap();
+        assert_eq!(parsed.xhttp_extra.x_padding_bytes, "100-1000");
+        assert_eq!(parsed.xhttp_extra.xmux.max_connections, "4");
+
+        let err = parse_vless_uri("vless://id@1.2.3.4:443?type=xhttp&extra=%7Bnope#X").unwrap_err();
+        assert!(err.to_string().contains("extra"), "{err}");
+        assert!(!to_vless_uri(&sample_server()).contains("extra"));
+    }
+
+    #[test]
+    fn parse_hysteria2_uri_with_obfs() {
+        let uri = "hysteria2://p%40ss@hy.example.com:8443/?sni=real.example.com&obfs=salamander&obfs-password=salty&insecure=0#HY2";
+        let config = parse_vless_uri(uri).unwrap();
+        assert_eq!(config.protocol, "hysteria2");
+        assert_eq!(config.password, "p@ss");
+        assert_eq!(config.address, "hy.example.com");
+        assert_eq!(config.port, 8443);
+        assert_eq!(config.tls.server_name, "real.example.com");
+        assert_eq!(config.hy2_obfs_password, "salty");
+        assert_eq!(config.security, "tls");
+        assert_eq!(config.name, "HY2");
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn parse_hy2_alias_without_query() {
+        let config = parse_vless_uri("hy2://secret@1.2.3.4:443#short").unwrap();
+        assert_eq!(config.protocol, "hysteria2");
+        assert_eq!(config.password, "secret");
+        assert_eq!(config.hy2_obfs_password, "");
+        assert_eq!(config.name, "short");
+    }
+
+    #[test]
+    fn parse_hysteria2_rejects_unknown_obfs_and_port_hopping() {
+        assert!(parse_vless_uri("hysteria2://a@h.example:443/?obfs=gost#x").is_err());
+        assert!(parse_vless_uri("hysteria2://a@h.example:20000-30000/#x").is_err());
+    }
+
+    #[test]
+    fn roundtrip_hysteria2() {
+        let server = ServerConfig {
+            name: "HY 2".to_string(),
+            address: "2001:db8::9".to_string(),
+            port: 443,
+            uuid: String::new(),
+            flow: String::new(),
+            security: "tls".to_string(),
+            protocol: "hysteria2".to_string(),
+            password: "a:b@c".to_string(),
+            hy2_obfs_password: "o b".to_string(),
+            tls: TlsSettings {
+                alpn: vec!["h3".to_string()],
+                ..TlsSettings::default()
+            },
+            ..ServerConfig::default()
+        };
+        let uri = to_vless_uri(&server);
+        assert!(uri.starts_with("hysteria2://"), "{uri}");
+        let parsed = parse_vless_uri(&uri).unwrap();
+        assert_eq!(parsed.password, server.password);
+        assert_eq!(parsed.address, server.address);
+        assert_eq!(parsed.port, server.port);
+        assert_eq!(parsed.tls.server_name, server.tls.server_name);
+        assert_eq!(parsed.hy2_obfs_password, server.hy2_obfs_password);
+        assert_eq!(parsed.tls.alpn, server.tls.alpn);
+        assert_eq!(parsed.name, server.name);
+    }
+
+    #[test]
+    fn roundtrip_wireguard() {
+        let server = ServerConfig {
+            name: "Office WG".to_string(),
+            address: "wg.example.com".to_string(),
+            port: 51820,
+            uuid: String::new(),
+            flow: String::new(),
+            security: "none".to_string(),
+            protocol: "wireguard".to_string(),
+            wg_private_key: "yAnz5TF+lXXJte14tji3zlMNq+hd2rYUIgJBgB3fBmk=".to_string(),
+            wg_peer_public_key: "xTIBA5rboUvnH4htodjb6e697QjLERt1NAB4mZqp8Dg=".to_string(),
+            wg_preshared_key: STANDARD.encode([0u8; 32]),
+            wg_local_addresses: vec!["10.8.0.2/32".to_string(), "fd00::2/128".to_string()],
+            wg_allowed_ips: vec!["10.0.0.0/8".to_string()],
+            wg_mtu: 1380,
+            wg_reserved: vec![7, 8, 9],
+            ..ServerConfig::default()
+        };
+        let uri = to_vless_uri(&server);
+        assert!(uri.starts_with("wireguard://"), "{uri}");
+        let parsed = parse_vless_uri(&uri).unwrap();
+        assert_eq!(parsed.protocol, "wireguard");
+        assert_eq!(parsed.wg_private_key, server.wg_private_key);
+        assert_eq!(parsed.wg_peer_public_key, server.wg_peer_public_key);
+        assert_eq!(parsed.wg_preshared_key, server.wg_preshared_key);
+        assert_eq!(parsed.wg_local_addresses, server.wg_local_addresses);
+        assert_eq!(parsed.wg_allowed_ips, server.wg_allowed_ips);
+        assert_eq!(parsed.wg_mtu, server.wg_mtu);
+        assert_eq!(parsed.wg_reserved, server.wg_reserved);
+        assert_eq!(parsed.name, server.name);
+        assert!(parsed.validate().is_ok());
+    }
+
+    #[test]
+    fn parse_vless_grpc_reality() {
+        let uri = "vless://aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee@1.2.3.4:443?encryption=none&flow=xtls-rprx-vision&type=grpc&serviceName=tun&mode=multi&security=reality&sni=ya.ru&fp=chrome&pbk=PBK&sid=ab#g";
+        let config = parse_vless_uri(uri).unwrap();
+        assert_eq!(config.network, "grpc");
+        assert_eq!(config.security, "reality");
+        assert_eq!(config.grpc_service_name, "tun");
+        assert!(config.grpc_multi_mode);
+        assert_eq!(config.xhttp_mode, "auto");
+        assert_eq!(config.flow, "", "Vision flow is dropped off raw TCP");
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn parse_vless_ws_tls_with_early_data() {
+        let uri = "vless://aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee@cdn.example.com:443?encryption=none&type=ws&security=tls&sni=cdn.example.com&path=%2Fws%3Fed%3D2048&host=front.example.com#w";
+        let config = parse_vless_uri(uri).unwrap();
+        assert_eq!(config.network, "ws");
+        assert_eq!(config.security, "tls");
+        assert_eq!(config.ws_path, "/ws");
+        assert_eq!(config.ws_early_data, 2048);
+        assert_eq!(config.ws_host, "front.example.com");
+        assert_eq!(config.xhttp_path, "");
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn parse_vless_unknown_transport_is_an_error() {
+        let uri = "vless://aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee@1.2.3.4:443?type=kcp&security=reality&pbk=x&sid=y";
+        let err = parse_vless_uri(uri).unwrap_err().to_string();
+        assert!(err.contains("kcp"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn roundtrip_vless_grpc_and_ws() {
+        let mut grpc = sample_server();
+        grpc.network = "grpc".to_string();
+        grpc.flow = String::new();
+        grpc.grpc_service_name = "svc".to_string();
+        grpc.grpc_multi_mode = true;
+
+        let mut ws = sample_server();
+        ws.network = "ws".to_string();
+        ws.flow = String::new();
+        ws.security = "tls".to_string();
+        ws.ws_path = "/ray".to_string();
+        ws.ws_host = "cdn.example.com".to_string();
+        ws.ws_early_data = 2048;
+        ws.tls.alpn = vec!["http/1.1".to_string()];
+
+        for server in [grpc, ws] {
+            let uri = to_vless_uri(&server);
+            let parsed = parse_vless_uri(&uri).unwrap();
+            assert_eq!(parsed.network, server.network, "{uri}");
+            assert_eq!(parsed.security, server.security, "{uri}");
+            assert_eq!(parsed.grpc_service_name, server.grpc_service_name);
+            assert_eq!(parsed.grpc_multi_mode, server.grpc_multi_mode);
+            assert_eq!(parsed.ws_path, server.ws_path);
+            assert_eq!(parsed.ws_host, server.ws_host);
+            assert_eq!(parsed.ws_early_data, server.ws_early_data);
+            assert_eq!(parsed.tls.alpn, server.tls.alpn);
+            assert_eq!(parsed.tls.server_name, server.tls.server_name);
+            assert_eq!(
+                parsed.reality.public_key,
+                if server.security == "tls" {
+                    ""
+                } else {
+                    server.reality.public_key.as_str()
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn roundtrip_vless_tcp_tls() {
+        let mut server = sample_server();
+        server.security = "tls".to_string();
+        server.reality = RealitySettings::default();
+        server.tls.server_name = "vpn.example.com".to_string();
+        server.tls.alpn = vec!["h2".to_string()];
+        server.tls.allow_insecure = true;
+        server.tls.pinned_cert_sha256 = vec!["ab".repeat(32)];
+
+        let uri = to_vless_uri(&server);
+        assert!(uri.contains("type=tcp&security=tls"), "{uri}");
+        assert!(!uri.contains("pbk="), "{uri}");
+        let parsed = parse_vless_uri(&uri).unwrap();
+        assert_eq!(parsed.network, "tcp");
+        assert_eq!(parsed.security, "tls");
+        assert_eq!(parsed.flow, "xtls-rprx-vision");
+        assert_eq!(parsed.tls.server_name, "vpn.example.com");
+        assert_eq!(parsed.tls.alpn, server.tls.alpn);
+        assert!(parsed.tls.allow_insecure);
+        assert_eq!(parsed.tls.pinned_cert_sha256, server.tls.pinned_cert_sha256);
+        assert!(parsed.validate().is_ok());
+    }
+
+    #[test]
+    fn roundtrip_trojan_grpc() {
+        let server = ServerConfig {
+            name: "TJ gRPC".to_string(),
+            address: "2001:db8::2".to_string(),
+            port: 443,
+            uuid: String::new(),
+            flow: String::new(),
+            tls: TlsSettings {
+                server_name: "tj.example.com".to_string(),
+                alpn: vec!["h2".to_string()],
+                ..TlsSettings::default()
+            },
+            network: "grpc".to_string(),
+            security: "tls".to_string(),
+            protocol: "trojan".to_string(),
+            password: "secret&pass".to_string(),
+            grpc_service_name: "TunService".to_string(),
+            ..ServerConfig::default()
+        };
+        let uri = to_vless_uri(&server);
+        assert!(uri.starts_with("trojan://secret%26pass@[2001:db8::2]:443?"));
+        let parsed = parse_vless_uri(&uri).unwrap();
+        assert_eq!(parsed.protocol, "trojan");
+        assert_eq!(parsed.password, server.password);
+        assert_eq!(parsed.address, server.address);
+        assert_eq!(parsed.network, "grpc");
+        assert_eq!(parsed.grpc_service_name, "TunService");
+        assert_eq!(parsed.tls.alpn, server.tls.alpn);
+        assert_eq!(parsed.tls.server_name, "tj.example.com");
+        assert_eq!(parsed.name, server.name);
     }
 
     #[test]
@@ -450,13 +1942,18 @@ mod tests {
             reality: RealitySettings {
                 public_key: "ABC123xyz-public-key".to_string(),
                 short_id: "abcd1234".to_string(),
+                ..RealitySettings::default()
+            },
+            tls: TlsSettings {
                 server_name: "www.example.org".to_string(),
                 fingerprint: "firefox".to_string(),
+                ..TlsSettings::default()
             },
             network: "tcp".to_string(),
             xhttp_path: String::new(),
             security: "reality".to_string(),
             xhttp_mode: "auto".to_string(),
+            ..ServerConfig::default()
         };
 
         let uri = to_vless_uri(&server);
@@ -476,11 +1973,11 @@ mod tests {
             "short_id mismatch"
         );
         assert_eq!(
-            parsed.reality.server_name, server.reality.server_name,
+            parsed.tls.server_name, server.tls.server_name,
             "server_name mismatch"
         );
         assert_eq!(
-            parsed.reality.fingerprint, server.reality.fingerprint,
+            parsed.tls.fingerprint, server.tls.fingerprint,
             "fingerprint mismatch"
         );
         // Parsed id will be a new UUID (not the original id), but must not be empty
@@ -503,7 +2000,7 @@ mod tests {
         assert_eq!(config.network, "xhttp");
         assert_eq!(config.xhttp_path, "/xhttp");
         assert_eq!(config.port, 36712);
-        assert_eq!(config.reality.server_name, "ya.ru");
+        assert_eq!(config.tls.server_name, "ya.ru");
         // Vision flow must not survive on an xhttp entry.
         assert_eq!(config.flow, "");
         assert_eq!(config.name, "NL xHTTP");
@@ -530,13 +2027,18 @@ mod tests {
             reality: RealitySettings {
                 public_key: "YHFxtSGXra6I4LSWG2Hua1yyGXznPouZ7kV9osEfB0E".to_string(),
                 short_id: "713a3828823be899".to_string(),
+                ..RealitySettings::default()
+            },
+            tls: TlsSettings {
                 server_name: "ya.ru".to_string(),
                 fingerprint: "chrome".to_string(),
+                ..TlsSettings::default()
             },
             network: "xhttp".to_string(),
             xhttp_path: "/xhttp".to_string(),
             security: "reality".to_string(),
             xhttp_mode: "auto".to_string(),
+            ..ServerConfig::default()
         };
         let uri = to_vless_uri(&server);
         assert!(uri.contains("type=xhttp"));
@@ -548,6 +2050,51 @@ mod tests {
         assert_eq!(parsed.reality.short_id, "713a3828823be899");
     }
 
+    #[test]
+    fn roundtrip_xhttp_tls_params() {
+        let server = ServerConfig {
+            name: "CDN xHTTP".to_string(),
+            address: "2001:db8::7".to_string(),
+            flow: String::new(),
+            tls: TlsSettings {
+                server_name: "cdn.example.com".to_string(),
+                fingerprint: "chrome".to_string(),
+                alpn: vec!["h2".to_string(), "http/1.1".to_string()],
+                allow_insecure: true,
+                pinned_cert_sha256: vec!["ab".repeat(32)],
+                ..TlsSettings::default()
+            },
+            network: "xhttp".to_string(),
+            xhttp_path: "/x".to_string(),
+            security: "tls".to_string(),
+            xhttp_mode: "stream-one".to_string(),
+            ..ServerConfig::default()
+        };
+        let uri = to_vless_uri(&server);
+        assert!(uri.contains("@[2001:db8::7]:443?"), "unexpected uri: {uri}");
+        let parsed = parse_vless_uri(&uri).unwrap();
+        assert_eq!(parsed.address, "2001:db8::7");
+        assert_eq!(parsed.security, "tls");
+        assert_eq!(parsed.tls.alpn, server.tls.alpn);
+        assert!(parsed.tls.allow_insecure);
+        assert_eq!(parsed.tls.pinned_cert_sha256, server.tls.pinned_cert_sha256);
+
+        // REALITY links bracket IPv6 literals too.
+        let reality = ServerConfig {
+            address: "2001:db8::8".to_string(),
+            ..sample_server()
+        };
+        for network in ["tcp", "xhttp"] {
+            let server = ServerConfig {
+                network: network.to_string(),
+                ..reality.clone()
+            };
+            let uri = to_vless_uri(&server);
+            assert!(uri.contains("@[2001:db8::8]:"), "unexpected uri: {uri}");
+            assert_eq!(parse_vless_uri(&uri).unwrap().address, "2001:db8::8");
+        }
+    }
+
     #[test]
     fn to_uri_contains_required_vless_fields() {
         let server = sample_server();
@@ -0,0 +1,205 @@
+use crate::models::{AppError, ServerConfig};
+
+/// Parse a `wg-quick` `.conf` file into a WireGuard profile. Only keys that
+/// affect the tunnel itself are read; wg-quick host settings (DNS, Table,
+/// PostUp, ...) have no meaning inside xray and are ignored. `name` is used as
+/// the profile name, falling back to the endpoint host.
+pub fn parse_wg_quick_conf(content: &str, name: &str) -> Result<ServerConfig, AppError> {
+    let mut server = ServerConfig {
+        protocol: "wireguard".to_string(),
+        uuid: String::new(),
+        flow: String::new(),
+        security: "none".to_string(),
+        name: name.trim().to_string(),
+        ..ServerConfig::default()
+    };
+
+    let mut section = String::new();
+    let mut peers = 0;
+    let mut endpoint = None;
+
+    for (lineno, raw) in content.lines().enumerate() {
+        let line = raw.split(['#', ';']).next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = header.trim().to_ascii_lowercase();
+            if section == "peer" {
+                peers += 1;
+            }
+            continue;
+        }
+        let (key, value) = line.split_once('=').ok_or_else(|| {
+            AppError::Config(format!("Line {}: expected 'Key = Value'", lineno + 1))
+        })?;
+        let (key, value) = (key.trim().to_ascii_lowercase(), value.trim());
+
+        match (section.as_str(), key.as_str()) {
+            ("interface", "privatekey") => server.wg_private_key = value.to_string(),
+            ("interface", "address") => server.wg_local_addresses.extend(split_list(value)),
+            ("interface", "mtu") => {
+                server.wg_mtu = value.parse().map_err(|_| {
+                    AppError::Config(format!("Line {}: invalid MTU '{value}'", lineno + 1))
+                })?
+            }
+            // Not part of wg-quick, but WARP generators emit it.
+            ("interface" | "peer", "reserved") => {
+                server.wg_reserved = split_list(value)
+                    .map(|b| b.parse::<u8>())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| {
+                        AppError::Config(format!(
+                            "Line {}: reserved must be comma-separated bytes",
+                            lineno + 1
+                        ))
+                    })?
+            }
+            ("peer", "publickey") => server.wg_peer_public_key = value.to_string(),
+            ("peer", "presharedkey") => server.wg_preshared_key = value.to_string(),
+            ("peer", "allowedips") => server.wg_allowed_ips.extend(split_list(value)),
+            ("peer", "endpoint") => endpoint = Some(value.to_string()),
+            ("peer", "persistentkeepalive") => {
+                server.wg_keepalive = if value == "off" {
+                    0
+                } else {
+                    value.parse().map_err(|_| {
+                        AppError::Config(format!(
+                            "Line {}: invalid PersistentKeepalive '{value}'",
+                            lineno + 1
+                        ))
+                    })?
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // xray's outbound talks to one peer; a multi-peer mesh config has no
+    // single endpoint to connect to.
+    match peers {
+        0 => return Err(AppError::Config("No [Peer] section found".to_string())),
+        1 => {}
+        _ => {
+            return Err(AppError::Config(
+                "Only single-peer WireGuard configs are supported".to_string(),
+            ))
+        }
+    }
+
+    let endpoint = endpoint.ok_or_else(|| AppError::Config("Peer has no Endpoint".to_string()))?;
+    let (address, port) = split_endpoint(&endpoint)?;
+    server.address = address;
+    server.port = port;
+    if server.name.is_empty() {
+        server.name = server.address.clone();
+    }
+
+    server.validate().map_err(AppError::Config)?;
+    Ok(server)
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = String> + '_ {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+/// Split `host:port` / `[v6]:port`.
+fn split_endpoint(endpoint: &str) -> Result<(String, u16), AppError> {
+    let invalid = || AppError::Config(format!("Invalid Endpoint '{endpoint}'"));
+    let (host, port) = if let Some(rest) = endpoint.strip_prefix('[') {
+        let (host, port) = rest.split_once("]:").ok_or_else(invalid)?;
+        (host, port)
+    } else {
+        endpoint.rsplit_once(':').ok_or_else(invalid)?
+    };
+    let port = port.parse().map_err(|_| invalid())?;
+    Ok((host.to_string(), port))
+}
+
+#[tauri::command]
+pub fn parse_wireguard_conf_cmd(content: String, name: String) -> Result<ServerConfig, String> {
+    parse_wg_quick_conf(&content, &name).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRIVATE: &str = "yAnz5TF+lXXJte14tji3zlMNq+hd2rYUIgJBgB3fBmk=";
+    const PUBLIC: &str = "xTIBA5rboUvnH4htodjb6e697QjLERt1NAB4mZqp8Dg=";
+    const PSK: &str = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=";
+
+    #[test]
+    fn parse_full_conf() {
+        let conf = format!(
+            "# office\n\
+             [Interface]\n\
+             PrivateKey = {PRIVATE}\n\
+             Address = 10.8.0.2/32, fd00::2/128\n\
+             DNS = 10.8.0.1\n\
+             MTU = 1380\n\
+             \n\
+             [Peer]\n\
+             PublicKey = {PUBLIC}\n\
+             PresharedKey = {PSK}\n\
+             AllowedIPs = 10.0.0.0/8,192.168.10.0/24\n\
+             Endpoint = [2001:db8::1]:51820\n\
+             PersistentKeepalive = 25 ; keep NAT open\n"
+        );
+        let server = parse_wg_quick_conf(&conf, "Office").unwrap();
+        assert_eq!(server.protocol, "wireguard");
+        assert_eq!(server.name, "Office");
+        assert_eq!(server.address, "2001:db8::1");
+        assert_eq!(server.port, 51820);
+        assert_eq!(server.wg_private_key, PRIVATE);
+        assert_eq!(server.wg_peer_public_key, PUBLIC);
+        assert_eq!(server.wg_preshared_key, PSK);
+        assert_eq!(
+            server.wg_local_addresses,
+            vec!["10.8.0.2/32", "fd00::2/128"]
+        );
+        assert_eq!(server.wg_allowed_ips, vec!["10.0.0.0/8", "192.168.10.0/24"]);
+        assert_eq!(server.wg_mtu, 1380);
+        assert_eq!(server.wg_keepalive, 25);
+        assert!(server.wg_reserved.is_empty());
+    }
+
+    #[test]
+    fn parse_warp_reserved_and_default_name() {
+        let conf = format!(
+            "[Interface]\nPrivateKey={PRIVATE}\nAddress=172.16.0.2/32\nReserved = 1, 2, 3\n\
+             [Peer]\nPublicKey={PUBLIC}\nEndpoint=engage.cloudflareclient.com:2408\n"
+        );
+        let server = parse_wg_quick_conf(&conf, "  ").unwrap();
+        assert_eq!(server.name, "engage.cloudflareclient.com");
+        assert_eq!(server.port, 2408);
+        assert_eq!(server.wg_reserved, vec![1, 2, 3]);
+        assert!(server.wg_allowed_ips.is_empty());
+    }
+
+    #[test]
+    fn parse_rejects_multi_peer_and_missing_endpoint() {
+        let peer = format!("[Peer]\nPublicKey={PUBLIC}\nEndpoint=1.2.3.4:51820\n");
+        let conf = format!("[Interface]\nPrivateKey={PRIVATE}\nAddress=10.0.0.2/32\n{peer}{peer}");
+        let err = parse_wg_quick_conf(&conf, "x").unwrap_err().to_string();
+        assert!(err.contains("single-peer"), "unexpected error: {err}");
+
+        let conf = format!(
+            "[Interface]\nPrivateKey={PRIVATE}\nAddress=10.0.0.2/32\n[Peer]\nPublicKey={PUBLIC}\n"
+        );
+        let err = parse_wg_quick_conf(&conf, "x").unwrap_err().to_string();
+        assert!(err.contains("Endpoint"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn parse_rejects_bad_key() {
+        let conf = format!(
+            "[Interface]\nPrivateKey=nope\nAddress=10.0.0.2/32\n[Peer]\nPublicKey={PUBLIC}\nEndpoint=1.2.3.4:51820\n"
+        );
+        assert!(parse_wg_quick_conf(&conf, "x").is_err());
+    }
+}
@@ -11,14 +11,16 @@ use tauri_plugin_shell::process::{CommandChild, CommandEvent};
 use tauri_plugin_shell::ShellExt;
 
 use crate::config;
-use crate::config::generate_client_config;
+use crate::config::{generate_chained_client_config, SendThrough};
 use crate::models::{
-    AppError, ConnectionInfo, ConnectionStatus, DetectedVpn, LogEntry, ServerConfig, SpeedStats,
+    self, AppError, ConnectionInfo, ConnectionStatus, DetectedVpn, LogEntry, ServerConfig,
+    SpeedStats,
 };
 #[cfg(desktop)]
 use crate::network;
 #[cfg(desktop)]
 use crate::proxy;
+use crate::storage;
 #[cfg(target_os = "linux")]
 use crate::tun;
 
@@ -105,6 +107,7 @@ impl XrayManager {
         state.status = ConnectionStatus::Connected;
         state.server_name = Some(server.name.clone());
         state.server_address = Some(server.address.clone());
+        state.chain = vec![server.name.clone()];
         state.connected_since = Some(now);
         state.error_message = None;
     }
@@ -135,20 +138,29 @@ impl XrayManager {
             }
         }
 
+        // Resolve the entry hop up front so a dangling chain fails before we
+        // flip the UI to Connecting.
+        let via = resolve_via(app, server)?;
+
         // Reset stats counters for new connection
         self.reset_stats();
 
         // Update status to connecting
         self.update_status(ConnectionStatus::Connecting, Some(server), None);
+        self.state.lock().unwrap().chain = via
+            .iter()
+            .chain(std::iter::once(server))
+            .map(|s| s.name.clone())
+            .collect();
 
         #[cfg(desktop)]
         {
-            self.start_desktop(app, server, bypass_domains)?;
+            self.start_desktop(app, server, via.as_ref(), bypass_domains)?;
         }
 
         #[cfg(mobile)]
         {
-            self.start_mobile(app, server, bypass_domains)?;
+            self.start_mobile(app, server, via.as_ref(), bypass_domains)?;
         }
 
         Ok(())
@@ -159,6 +171,7 @@ impl XrayManager {
         &self,
         app: &AppHandle<R>,
         server: &ServerConfig,
+        via: Option<&ServerConfig>,
         bypass_domains: &[String],
     ) -> Result<(), AppError> {
         // Kill any stale xray process from a previous run
@@ -200,10 +213,27 @@ impl XrayManager {
         #[cfg(target_os = "linux")]
         let gateway_info = network::detect_default_gateway_and_ip();
 
+        // IPv6 is tunnelled too unless the user opted to drop it. Either way
+        // xray needs the global IPv6 address to dial an IPv6 server, and when
+        // tunnelling also to reach IPv6 destinations directly.
+        #[cfg(target_os = "linux")]
+        let block_ipv6 = storage::load_settings(app)
+            .map(|s| s.block_ipv6)
+            .unwrap_or(false);
+        #[cfg(target_os = "linux")]
+        let ipv6_uplink = network::detect_ipv6_uplink();
+
         #[cfg(target_os = "linux")]
-        let send_through = gateway_info.as_ref().map(|(_, _, ip)| ip.as_str());
+        let send_through = SendThrough {
+            ipv4: gateway_info.as_ref().map(|(_, _, ip)| ip.as_str()),
+            ipv6: ipv6_uplink.as_ref().and_then(|u| u.local_ip.as_deref()),
+            block_ipv6,
+            // Only TUN mode binds sockets, so only then is the family needed.
+            entry_ipv6: gateway_info.is_some()
+                && network::dials_over_ipv6(&via.unwrap_or(server).address),
+        };
         #[cfg(not(target_os = "linux"))]
-        let send_through: Option<&str> = None;
+        let send_through = SendThrough::default();
 
         // Detect corporate VPN DNS servers from resolv.conf (private IPs only).
         // Filter out DNS servers inside VPN-routed subnets — xray can't reach them
@@ -225,8 +255,9 @@ impl XrayManager {
         let vpn_dns_servers: Vec<String> = Vec::new();
 
         // Generate xray config
-        let config_json = generate_client_config(
+        let config_json = generate_chained_client_config(
             server,
+            via,
             DEFAULT_SOCKS_PORT,
             bypass_domains,
             &bypass_subnet_list,
@@ -314,9 +345,17 @@ impl XrayManager {
             (
                 hev_bin,
                 config_dir.clone(),
-                server.address.clone(),
+                std::iter::once(server)
+                    .chain(via)
+                    .flat_map(ServerConfig::dial_addresses)
+                    .map(str::to_string)
+                    .collect::<Vec<_>>(),
                 bypass_subnet_list.clone(),
                 gateway_info.clone(),
+                tun::Ipv6Setup {
+                    block: block_ipv6,
+                    uplink: ipv6_uplink.clone(),
+                },
             )
         };
 
@@ -569,8 +608,14 @@ impl XrayManager {
         // Start TUN mode after xray connects (Linux only)
         #[cfg(target_os = "linux")]
         {
-            let (hev_bin, tun_config_dir, tun_server_ip, tun_bypass_subnets, tun_gateway_info) =
-                tun_data;
+            let (
+                hev_bin,
+                tun_config_dir,
+                tun_server_ips,
+                tun_bypass_subnets,
+                tun_gateway_info,
+                tun_ipv6,
+            ) = tun_data;
             let tun_logs = self.logs.clone();
             let tun_state = self.state.clone();
             std::thread::spawn({
@@ -636,10 +681,11 @@ impl XrayManager {
                     match tun::start_tun(
                         &hev_bin,
                         DEFAULT_SOCKS_PORT,
-                        &tun_server_ip,
+                        &tun_server_ips,
                         &tun_bypass_subnets,
                         &tun_config_dir,
                         tun_gateway_info,
+                        tun_ipv6,
                     ) {
                         Ok(()) => {
                             push_log_entry(
@@ -772,20 +818,30 @@ impl XrayManager {
         &self,
         app: &AppHandle<R>,
         server: &ServerConfig,
+        via: Option<&ServerConfig>,
         bypass_domains: &[String],
     ) -> Result<(), AppError> {
         use tauri_plugin_vpn::VpnPluginExt;
 
         // Generate xray config (no bypass subnets on mobile)
-        let mut config_json =
-            generate_client_config(server, DEFAULT_SOCKS_PORT, bypass_domains, &[], None, &[])?;
+        let mut config_json = generate_chained_client_config(
+            server,
+            via,
+            DEFAULT_SOCKS_PORT,
+            bypass_domains,
+            &[],
+            SendThrough::default(),
+            &[],
+        )?;
 
         // Apply Android-specific modifications
         config_json = config::modify_config_for_android(&config_json)?;
 
         // Start VPN via plugin (this triggers the Android service asynchronously)
+        // The service needs the host it dials directly: the entry hop of a chain.
+        let first_hop = via.unwrap_or(server);
         app.vpn()
-            .start_vpn(config_json, DEFAULT_SOCKS_PORT, server.address.clone())
+            .start_vpn(config_json, DEFAULT_SOCKS_PORT, first_hop.address.clone())
             .map_err(|e| AppError::XrayProcess(format!("VPN plugin error: {e}")))?;
 
         // Poll the Android service status to verify the pipeline is actually working.
@@ -1240,6 +1296,7 @@ impl XrayManager {
         if status == ConnectionStatus::Disconnected {
             state.server_name = None;
             state.server_address = None;
+            state.chain.clear();
             state.connected_since = None;
             state.error_message = None;
         }
@@ -1249,6 +1306,19 @@ impl XrayManager {
     }
 }
 
+/// Load the server `server` is chained through, if it has one.
+fn resolve_via<R: Runtime>(
+    app: &AppHandle<R>,
+    server: &ServerConfig,
+) -> Result<Option<ServerConfig>, AppError> {
+    if server.via.as_deref().unwrap_or_default().is_empty() {
+        return Ok(None);
+    }
+    let servers = storage::load_servers(app)?;
+    let via = models::resolve_via(server, &servers).map_err(AppError::Config)?;
+    Ok(via.cloned())
+}
+
 /// Transition state Connecting → Connected atomically under the state lock.
 ///
 /// Returns true if this call was the one that performed the transition
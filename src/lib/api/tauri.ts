@@ -1,5 +1,19 @@
 import { invoke } from '@tauri-apps/api/core';
-import type { AppSettings, ConnectionInfo, DetectedVpn, LogEntry, ServerConfig, SpeedStats } from '$lib/types';
+import type {
+	AppSettings,
+	ConnectionInfo,
+	DetectedVpn,
+	ImportReport,
+	LogEntry,
+	MergeStrategy,
+	ParsedImport,
+	QrEcLevel,
+	QrFormat,
+	ServerConfig,
+	SpeedStats,
+	Subscription,
+	SubscriptionQuota
+} from '$lib/types';
 
 export async function connect(config: ServerConfig): Promise<void> {
 	await invoke<void>('connect', { serverConfig: config });
@@ -35,8 +49,66 @@ export async function exportServers(): Promise<string> {
 	return await invoke<string>('export_servers');
 }
 
-export async function importServers(json: string): Promise<ServerConfig[]> {
-	return await invoke<ServerConfig[]>('import_servers', { json });
+/** Names of the servers in `json` that duplicate saved ones. */
+export async function findImportDuplicates(json: string): Promise<string[]> {
+	return await invoke<string[]>('find_import_duplicates', { json });
+}
+
+export async function importServers(
+	json: string,
+	strategy: MergeStrategy
+): Promise<ImportReport> {
+	return await invoke<ImportReport>('import_servers', { json, strategy });
+}
+
+// Clash / Mihomo
+/** Parse the `proxies:` of a Clash YAML profile. Does not save. */
+export async function parseClashYaml(content: string): Promise<ParsedImport> {
+	return await invoke<ParsedImport>('parse_clash_yaml_cmd', { content });
+}
+
+export async function parseSingboxJson(content: string): Promise<ParsedImport> {
+	return await invoke<ParsedImport>('parse_singbox_json_cmd', { content });
+}
+
+export async function parseShareText(text: string): Promise<ParsedImport> {
+	return await invoke<ParsedImport>('parse_share_text_cmd', { text });
+}
+
+export async function parseQrImage(path: string): Promise<ParsedImport> {
+	return await invoke<ParsedImport>('parse_qr_image_cmd', { path });
+}
+
+export async function parseXrayConfig(content: string, name: string): Promise<ServerConfig> {
+	return await invoke<ServerConfig>('parse_xray_config_cmd', { content, name });
+}
+
+// Subscriptions
+export async function getSubscriptions(): Promise<Subscription[]> {
+	return await invoke<Subscription[]>('get_subscriptions');
+}
+
+export async function addSubscription(subscription: Subscription): Promise<Subscription> {
+	return await invoke<Subscription>('add_subscription', { subscription });
+}
+
+export async function updateSubscription(subscription: Subscription): Promise<void> {
+	await invoke<void>('update_subscription', { subscription });
+}
+
+/** Delete a subscription and the servers it provided. */
+export async function deleteSubscription(id: string): Promise<void> {
+	await invoke<void>('delete_subscription', { id });
+}
+
+/** Fetch a subscription now; returns its servers after the refresh. */
+export async function refreshSubscription(id: string): Promise<ServerConfig[]> {
+	return await invoke<ServerConfig[]>('refresh_subscription', { id });
+}
+
+/** Remaining traffic and expiry as last reported; null if the provider sends none. */
+export async function getSubscriptionQuota(id: string): Promise<SubscriptionQuota | null> {
+	return await invoke<SubscriptionQuota | null>('get_subscription_quota', { id });
 }
 
 // VLESS URI
@@ -48,6 +120,32 @@ export async function exportVlessUri(config: ServerConfig): Promise<string> {
 	return await invoke<string>('export_vless_uri', { serverConfig: config });
 }
 
+export async function exportSingboxOutbound(config: ServerConfig): Promise<string> {
+	return await invoke<string>('export_singbox_outbound', { serverConfig: config });
+}
+
+// QR codes: the bytes of a PNG or SVG file per code.
+export async function exportQrCode(
+	config: ServerConfig,
+	format: QrFormat,
+	ecLevel: QrEcLevel
+): Promise<number[]> {
+	return await invoke<number[]>('export_qr_code', { serverConfig: config, format, ecLevel });
+}
+
+export async function exportQrBundle(
+	servers: ServerConfig[],
+	format: QrFormat,
+	ecLevel: QrEcLevel
+): Promise<number[][]> {
+	return await invoke<number[][]>('export_qr_bundle', { servers, format, ecLevel });
+}
+
+// WireGuard
+export async function parseWireguardConf(content: string, name: string): Promise<ServerConfig> {
+	return await invoke<ServerConfig>('parse_wireguard_conf_cmd', { content, name });
+}
+
 export async function getSpeedStats(): Promise<SpeedStats> {
 	return await invoke<SpeedStats>('get_speed_stats');
 }
@@ -6,7 +6,8 @@ const DEFAULT_INFO: ConnectionInfo = {
 	server_name: null,
 	server_address: null,
 	connected_since: null,
-	error_message: null
+	error_message: null,
+	chain: []
 };
 
 const DEFAULT_STATS: SpeedStats = {
@@ -1,10 +1,21 @@
-import type { ServerConfig } from '$lib/types';
+import type {
+	ImportEntryError,
+	ImportReport,
+	MergeStrategy,
+	ParsedImport,
+	QrEcLevel,
+	QrFormat,
+	ServerConfig
+} from '$lib/types';
 import * as api from '$lib/api/tauri';
 
 function createServersStore() {
 	let servers = $state<ServerConfig[]>([]);
 	let selectedId = $state<string | null>(null);
 	let loadError = $state<string | null>(null);
+	// Asked how to merge when an import duplicates saved servers; null cancels.
+	let chooseStrategy: (duplicates: string[]) => Promise<MergeStrategy | null> = async () =>
+		'skip';
 
 	const selectedServer = $derived(
 		selectedId !== null ? (servers.find((s) => s.id === selectedId) ?? null) : null
@@ -101,13 +112,22 @@ function createServersStore() {
 		}
 	}
 
-	async function importFromJson(json: string): Promise<ServerConfig[]> {
-		const imported = await api.importServers(json);
+	function setStrategyPrompt(prompt: (duplicates: string[]) => Promise<MergeStrategy | null>) {
+		chooseStrategy = prompt;
+	}
+
+	/** Import a server list, asking how to merge duplicates; null if the user cancelled. */
+	async function importFromJson(json: string): Promise<ImportReport | null> {
+		const duplicates = await api.findImportDuplicates(json);
+		const strategy = duplicates.length > 0 ? await chooseStrategy(duplicates) : 'skip';
+		if (strategy === null) return null;
+		const report = await api.importServers(json, strategy);
 		await load();
-		if (imported.length > 0) {
-			selectedId = imported[0].id;
+		const first = report.added[0] ?? report.updated[0];
+		if (first) {
+			selectedId = first.id;
 		}
-		return imported;
+		return report;
 	}
 
 	async function importFromUri(uri: string): Promise<ServerConfig> {
@@ -118,6 +138,48 @@ function createServersStore() {
 		return created;
 	}
 
+	async function importFromWireguard(content: string, name: string): Promise<ServerConfig> {
+		const parsed = await api.parseWireguardConf(content, name);
+		const created = await api.addServer(parsed);
+		await load();
+		selectedId = created.id;
+		return created;
+	}
+
+	async function importFromXrayConfig(content: string, name: string): Promise<ServerConfig> {
+		const parsed = await api.parseXrayConfig(content, name);
+		const created = await api.addServer(parsed);
+		await load();
+		selectedId = created.id;
+		return created;
+	}
+
+	/**
+	 * Save the convertible entries of a parsed import. `report` is null if the
+	 * user cancelled; `errors` are the entries that couldn't be converted.
+	 */
+	async function importParsed(
+		parsed: ParsedImport
+	): Promise<{ report: ImportReport | null; errors: ImportEntryError[] }> {
+		const report =
+			parsed.servers.length > 0
+				? await importFromJson(JSON.stringify(parsed.servers))
+				: { added: [], updated: [], skipped: [] };
+		return { report, errors: parsed.errors };
+	}
+
+	async function importFromClash(content: string) {
+		return await importParsed(await api.parseClashYaml(content));
+	}
+
+	async function importFromSingbox(content: string) {
+		return await importParsed(await api.parseSingboxJson(content));
+	}
+
+	async function importFromQrImage(path: string) {
+		return await importParsed(await api.parseQrImage(path));
+	}
+
 	async function exportToJson(): Promise<string> {
 		return await api.exportServers();
 	}
@@ -126,6 +188,24 @@ function createServersStore() {
 		return await api.exportVlessUri(server);
 	}
 
+	async function exportToSingbox(server: ServerConfig): Promise<string> {
+		return await api.exportSingboxOutbound(server);
+	}
+
+	async function exportToQr(
+		server: ServerConfig,
+		format: QrFormat,
+		ecLevel: QrEcLevel
+	): Promise<number[]> {
+		return await api.exportQrCode(server, format, ecLevel);
+	}
+
+	/** Every saved server with a share link as a sequence of QR codes. */
+	async function exportAllToQr(format: QrFormat, ecLevel: QrEcLevel): Promise<number[][]> {
+		const shareable = servers.filter((s) => s.protocol !== 'raw');
+		return await api.exportQrBundle(shareable, format, ecLevel);
+	}
+
 	return {
 		get servers() {
 			return servers;
@@ -149,10 +229,20 @@ function createServersStore() {
 		deleteServer,
 		selectServer,
 		selectServerByIndex,
+		setStrategyPrompt,
 		importFromJson,
 		importFromUri,
+		importFromWireguard,
+		importFromClash,
+		importFromSingbox,
+		importFromXrayConfig,
+		importFromQrImage,
+		importParsed,
 		exportToJson,
-		exportToUri
+		exportToUri,
+		exportToSingbox,
+		exportToQr,
+		exportAllToQr
 	};
 }
 
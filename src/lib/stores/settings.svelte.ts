@@ -4,7 +4,8 @@ import type { AppSettings } from '$lib/types';
 const DEFAULT_SETTINGS: AppSettings = {
 	auto_connect: false,
 	last_server_id: null,
-	bypass_domains: ['claude.ai', 'anthropic.com', 'api.anthropic.com', 'wb.ru', 'wildberries.ru']
+	bypass_domains: ['claude.ai', 'anthropic.com', 'api.anthropic.com', 'wb.ru', 'wildberries.ru'],
+	block_ipv6: false
 };
 
 function createSettingsStore() {
@@ -46,6 +47,11 @@ function createSettingsStore() {
 		await save({ ...settings, auto_connect: value });
 	}
 
+	// Takes effect on the next connect: the TUN routes are set up once.
+	async function setBlockIpv6(value: boolean): Promise<void> {
+		await save({ ...settings, block_ipv6: value });
+	}
+
 	async function setBypassDomains(domains: string[]): Promise<boolean> {
 		// No-op guard: the textarea's `onchange` can fire on blur even when the
 		// user didn't edit anything, and without this check we'd tear down a live
@@ -87,6 +93,7 @@ function createSettingsStore() {
 		},
 		load,
 		setAutoConnect,
+		setBlockIpv6,
 		setBypassDomains
 	};
 }
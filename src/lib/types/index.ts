@@ -1,8 +1,100 @@
 export interface RealitySettings {
 	public_key: string;
 	short_id: string;
+	/** Further accepted short IDs; each connect picks one (or short_id) at random. */
+	extra_short_ids?: string[];
+	/** spiderX crawl path, e.g. "/"; empty = xray default. */
+	spider_x?: string;
+}
+
+/** ClientHello parameters; SNI and fingerprint apply to REALITY too. */
+export interface TlsSettings {
+	/** SNI; for CDN profiles also the HTTP Host. Empty = server address. */
 	server_name: string;
+	/** uTLS fingerprint, e.g. "chrome". */
 	fingerprint: string;
+	/** ALPN list; empty = transport default. */
+	alpn?: string[];
+	/** Skip certificate verification (self-signed test servers only). */
+	allow_insecure?: boolean;
+	/** Hex SHA-256 hashes of accepted server certificates. */
+	pinned_cert_sha256?: string[];
+	ech?: EchSettings;
+}
+
+/** Encrypted Client Hello; off unless config_list or doh_server is set (not both). */
+export interface EchSettings {
+	/** Static base64 ECHConfigList. */
+	config_list: string;
+	/** DNS server to fetch the list from: "https://1.1.1.1/dns-query" (DoH) or "udp://1.1.1.1". */
+	doh_server: string;
+	/** Domain whose HTTPS record is queried; empty = the server name. */
+	query_domain: string;
+}
+
+/** Mux.Cool multiplexing: many streams share one proxied connection. */
+export interface MuxSettings {
+	enabled: boolean;
+	/** Max TCP streams per connection (1–1024); -1 = UDP only (required with Vision). */
+	concurrency: number;
+	/** Max UDP sessions per XUDP connection (1–1024); -1 disables XUDP. */
+	xudp_concurrency: number;
+	/** QUIC (UDP/443) handling: "reject", "allow" or "skip". */
+	xudp_proxy_udp_443: string;
+}
+
+/** freedom `fragment` options; ranges are "min-max" strings. Off while packets is empty. */
+export interface FragmentSettings {
+	/** "tlshello" or a packet range such as "1-3". */
+	packets: string;
+	/** Fragment size in bytes, e.g. "100-200". */
+	length: string;
+	/** Pause between fragments in ms, e.g. "10-20". */
+	interval: string;
+}
+
+/** One freedom `noises` entry: a junk UDP packet sent ahead of real traffic. */
+export interface NoiseSettings {
+	/** "rand", "str", "base64" or "hex". */
+	type: string;
+	/** Length range for "rand", otherwise the payload. */
+	packet: string;
+	/** Delay in ms after sending, e.g. "10-16"; empty = none. */
+	delay: string;
+}
+
+/** xHTTP connection reuse limits; numeric knobs are "N" or "min-max" strings, empty = xray default. */
+export interface XmuxSettings {
+	max_concurrency: string;
+	max_connections: string;
+	c_max_reuse_times: string;
+	h_max_request_times: string;
+	h_max_reusable_secs: string;
+	/** Keep-alive ping period in seconds; 0 = xray default. */
+	h_keep_alive_period: number;
+}
+
+/** Separate endpoint for the download half of a split xHTTP connection. */
+export interface XhttpDownload {
+	address: string;
+	port: number;
+	/** "reality" or "tls". */
+	security: string;
+	/** Empty = the upload path. */
+	path: string;
+	tls: TlsSettings;
+	reality: RealitySettings;
+}
+
+/** xray's xhttpSettings.extra block. */
+export interface XhttpExtra {
+	/** Random request padding, e.g. "100-1000". */
+	x_padding_bytes: string;
+	no_grpc_header: boolean;
+	sc_max_each_post_bytes: string;
+	sc_min_posts_interval_ms: string;
+	xmux: XmuxSettings;
+	download: XhttpDownload | null;
 }
 
 export interface ServerConfig {
@@ -13,6 +105,7 @@ export interface ServerConfig {
 	uuid: string;
 	flow: string;
 	reality: RealitySettings;
+	tls: TlsSettings;
 	/** Transport: "tcp" (REALITY + XTLS-Vision) or "xhttp" (REALITY over XHTTP, DPI-resistant). */
 	network: string;
 	/** XHTTP request path; only used when network === "xhttp". */
@@ -20,12 +113,155 @@ export interface ServerConfig {
 	/**
 	 * Transport security: "reality" (default) or "tls". TLS is required for CDN
 	 * fronting (Cloudflare terminates TLS, so REALITY can't be used through a
-	 * CDN). When "tls", reality.server_name is the CDN domain (SNI + Host) and
-	 * reality.fingerprint is the uTLS fingerprint; public_key/short_id are unused.
+	 * CDN, though it also works directly over raw TCP). When "tls", the reality
+	 * keypair is unused and tls.server_name is the SNI (and Host for CDNs).
 	 */
 	security: string;
 	/** XHTTP mode: "auto" (default), "stream-one", "stream-up", "packet-up". */
 	xhttp_mode: string;
+	// Fields below are optional on input: the backend fills in its defaults
+	// (a VLESS profile) when they are omitted.
+	/**
+	 * Outbound protocol: "vless" (default), "vmess", "trojan", "shadowsocks",
+	 * "hysteria2", "wireguard" or "raw" (a hand-written xray outbound).
+	 */
+	protocol?: string;
+	/** VMess alterId; 0 = AEAD. */
+	alter_id?: number;
+	/** VMess user cipher: "auto", "aes-128-gcm", "chacha20-poly1305", "none", "zero". */
+	vmess_cipher?: string;
+	/** VLESS encryption: "none" or an "mlkem768x25519plus...." string from the server. */
+	vless_encryption?: string;
+	/** WebSocket path; only used when network === "ws". */
+	ws_path?: string;
+	/** WebSocket Host header; empty falls back to the TLS server name. */
+	ws_host?: string;
+	/** Trojan / Shadowsocks password or Hysteria2 auth (replaces uuid). */
+	password?: string;
+	/** gRPC service name; only used when network === "grpc". */
+	grpc_service_name?: string;
+	/** gRPC "multi" mode instead of the default "gun" mode. */
+	grpc_multi_mode?: boolean;
+	/** WebSocket early data in bytes (xray `?ed=`); 0 = off. */
+	ws_early_data?: number;
+	/** Shadowsocks cipher, e.g. "aes-256-gcm" or "2022-blake3-aes-128-gcm". */
+	ss_method?: string;
+	/** SIP003 plugin string; only v2ray-plugin websocket mode is supported. */
+	ss_plugin?: string;
+	/** Hysteria2 salamander obfuscation password; empty = no obfuscation. */
+	hy2_obfs_password?: string;
+	/** Hysteria2 bandwidth hints in Mbit/s; 0 = BBR congestion control. */
+	hy2_up_mbps?: number;
+	hy2_down_mbps?: number;
+	/** WireGuard interface private key (base64); the endpoint is address:port. */
+	wg_private_key?: string;
+	wg_peer_public_key?: string;
+	/** Optional peer preshared key (base64). */
+	wg_preshared_key?: string;
+	/** Interface addresses, e.g. ["10.8.0.2/32"]. */
+	wg_local_addresses?: string[];
+	/** Peer AllowedIPs; empty = everything. */
+	wg_allowed_ips?: string[];
+	/** Tunnel MTU; 0 = xray default (1420). */
+	wg_mtu?: number;
+	/** Three reserved header bytes (Cloudflare WARP client id). */
+	wg_reserved?: number[];
+	/** PersistentKeepalive in seconds; 0 = off. */
+	wg_keepalive?: number;
+	/** Id of a saved server to dial this one through; null connects directly. */
+	via?: string | null;
+	mux?: MuxSettings;
+	fragment?: FragmentSettings;
+	noises?: NoiseSettings[];
+	xhttp_extra?: XhttpExtra;
+	/** Id of the subscription that provides this server; null if added by hand. */
+	subscription_id?: string | null;
+	/** The xray outbound object of a "raw" server, used as-is apart from its tag. */
+	raw_outbound?: Record<string, unknown> | null;
+}
+
+/** File format of a rendered QR code. */
+export type QrFormat = 'png' | 'svg';
+
+/** QR error-correction level: L ≈ 7%, M ≈ 15%, Q ≈ 25%, H ≈ 30% of the code recoverable. */
+export type QrEcLevel = 'L' | 'M' | 'Q' | 'H';
+
+/** Servers read from a foreign profile format plus the entries that were skipped. */
+export interface ParsedImport {
+	servers: ServerConfig[];
+	errors: ImportEntryError[];
+}
+
+export interface ImportEntryError {
+	/** 0-based position of the entry in the source. */
+	index: number;
+	name: string;
+	error: string;
+}
+
+/**
+ * What to do with an imported server that matches a saved one by protocol, address,
+ * port, user, transport and security.
+ */
+export type MergeStrategy = 'skip' | 'overwrite' | 'keep_both';
+
+export interface ImportReport {
+	/** New entries, with their assigned IDs. */
+	added: ServerConfig[];
+	/** Saved entries that were overwritten, as they are now. */
+	updated: ServerConfig[];
+	/** Incoming duplicates that were dropped. */
+	skipped: ServerConfig[];
+}
+
+export interface Subscription {
+	id: string;
+	name: string;
+	url: string;
+	/** Seconds between automatic refreshes; 0 = manual only. */
+	refresh_interval_secs: number;
+	/** Unix time of the last successful refresh. */
+	last_updated: number | null;
+	/** Traffic and expiry from the provider's `subscription-userinfo` header. */
+	usage?: SubscriptionUsage | null;
+	/** The provider's name for the profile; empty if not sent. */
+	profile_title?: string;
+	/** Refresh interval the provider suggests, in seconds. */
+	suggested_refresh_secs?: number | null;
+	/** Unix time of the last failed refresh; null after a successful one. */
+	last_failed?: number | null;
+	/** Failed refreshes in a row; automatic retries back off with each. */
+	failures?: number;
+}
+
+export interface SubscriptionUsage {
+	upload: number;
+	download: number;
+	/** Allowance in bytes; 0 = unlimited. */
+	total: number;
+	/** Unix time of expiry; null = never. */
+	expire: number | null;
+}
+
+export interface SubscriptionQuota {
+	subscription_id: string;
+	title: string;
+	used: number;
+	total: number;
+	/** null for an unlimited plan. */
+	remaining: number | null;
+	used_percent: number | null;
+	expire: number | null;
+	expired: boolean;
+}
+
+/** Payload of the `subscription-quota` event. */
+export interface QuotaAlert {
+	subscription_id: string;
+	name: string;
+	threshold_percent: number;
+	used: number;
+	total: number;
 }
 
 export type ConnectionStatus =
@@ -41,6 +277,8 @@ export interface ConnectionInfo {
 	server_address: string | null;
 	connected_since: number | null;
 	error_message: string | null;
+	/** Server names from the first hop to the exit. */
+	chain: string[];
 }
 
 export interface SpeedStats {
@@ -60,6 +298,7 @@ export interface AppSettings {
 	auto_connect: boolean;
 	last_server_id: string | null;
 	bypass_domains: string[];
+	block_ipv6?: boolean;
 }
 
 export interface DetectedVpn {